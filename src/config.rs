@@ -0,0 +1,281 @@
+//! Loads the optional tuckr configuration file
+//!
+//! Tuckr works without any configuration, but a `tuckr.toml` file can be placed at the root
+//! of the dotfiles directory to enable extra features such as profiles:
+//!
+//! ```toml
+//! [profiles.work]
+//! groups = ["zsh", "neovim", "git"]
+//! exclude = ["git"]
+//!
+//! # groups that are set up before all others, in this order
+//! order = ["zsh"]
+//!
+//! # store hidden files as `dot-name` instead of `.name`
+//! dot_prefix = true
+//!
+//! # directories only holding other directories, eg: `Configs/editors/{nvim,emacs}`, hold
+//! # subgroups called `editors/nvim` and `editors/emacs` which `editors` stands for
+//! recursive_groups = true
+//!
+//! # run hooks from dotfiles/Hooks/<group> instead of where the group is deployed to
+//! hooks_cwd = "hooks"
+//!
+//! # numbered hooks below 30 run before neovim is symlinked, defaults to 50
+//! [symlink_phase]
+//! neovim = 30
+//!
+//! # values substituted into `.tmpl` files when they're deployed
+//! [template_values]
+//! email = "me@example.com"
+//! ```
+
+use crate::dotfiles;
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fs;
+use std::process::ExitCode;
+use std::sync::OnceLock;
+
+pub const CONFIG_FILE: &str = "tuckr.toml";
+
+/// The config of the running command, see [`Config::load_for_command`]
+static COMMAND_CONFIG: OnceLock<Config> = OnceLock::new();
+
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct Config {
+    /// Named sets of groups that can be deployed together
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+
+    /// Groups that should be set up before the others
+    #[serde(default)]
+    pub order: Vec<String>,
+
+    /// Deploys files prefixed with `dot-` as hidden files, eg: `dot-zshrc` becomes `.zshrc`
+    #[serde(default)]
+    pub dot_prefix: bool,
+
+    /// Treats directories inside of groups that only hold directories as groups of their own,
+    /// see [`dotfiles::is_parent_group`]
+    #[serde(default)]
+    pub recursive_groups: bool,
+
+    /// Directory hooks are run from
+    #[serde(default)]
+    pub hooks_cwd: HooksCwd,
+
+    /// Per group phase at which dotfiles are symlinked, numbered hooks below it run before
+    /// symlinking and the rest after it
+    #[serde(default)]
+    pub symlink_phase: BTreeMap<String, u32>,
+
+    /// Values of the `{{ name }}` placeholders in template dotfiles
+    #[serde(default)]
+    pub template_values: BTreeMap<String, String>,
+}
+
+/// Phase at which groups without a `symlink_phase` are symlinked
+pub const DEFAULT_SYMLINK_PHASE: u32 = 50;
+
+/// Where hooks are run from
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HooksCwd {
+    /// The directory the group's dotfiles are deployed to, usually $HOME
+    #[default]
+    Target,
+    /// The group's directory inside of dotfiles/Hooks
+    Hooks,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+pub struct Profile {
+    #[serde(default)]
+    pub groups: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+impl Config {
+    /// Loads the config file from the dotfiles directory
+    ///
+    /// A missing config file is not an error, the default configuration is used instead
+    pub fn load(output: &mut String) -> Result<Self, ExitCode> {
+        let dotfiles_dir = dotfiles::get_dotfiles_path(output)?;
+
+        let config_path = dotfiles_dir.join(CONFIG_FILE);
+        if !config_path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = match fs::read_to_string(&config_path) {
+            Ok(content) => content,
+            Err(e) => {
+                output.push_str(&format!("Could not read {}: {e}\n", config_path.display()));
+                return Err(ExitCode::FAILURE);
+            }
+        };
+
+        Self::parse(&content, output)
+    }
+
+    /// Loads the config a single time for the whole command, so that it isn't read again for
+    /// every dotfile, and makes [`Config::current`] return it
+    ///
+    /// Fails if the config file is invalid. A missing dotfiles directory is left for the command
+    /// to report
+    pub fn load_for_command(output: &mut String) -> Result<(), ExitCode> {
+        if dotfiles::get_dotfiles_path(&mut String::new()).is_err() {
+            return Ok(());
+        }
+
+        let config = Self::load(output)?;
+        _ = COMMAND_CONFIG.set(config);
+        Ok(())
+    }
+
+    /// Returns the config of the running command, which is loaded from the config file when
+    /// `load_for_command` wasn't called, eg: in tests
+    pub fn current(output: &mut String) -> Result<Cow<'static, Self>, ExitCode> {
+        match COMMAND_CONFIG.get() {
+            Some(config) => Ok(Cow::Borrowed(config)),
+            None => Self::load(output).map(Cow::Owned),
+        }
+    }
+
+    fn parse(content: &str, output: &mut String) -> Result<Self, ExitCode> {
+        toml::from_str(content).map_err(|e| {
+            output.push_str(&format!("Invalid {CONFIG_FILE}: {e}\n"));
+            ExitCode::FAILURE
+        })
+    }
+
+    /// Sorts groups so that the ones listed in `order` come first, in the order they're listed
+    ///
+    /// The remaining groups keep their relative order
+    pub fn sort_by_deploy_order(&self, groups: &mut [String]) {
+        groups.sort_by_key(|group| {
+            self.order
+                .iter()
+                .position(|ordered| ordered == group)
+                .unwrap_or(self.order.len())
+        });
+    }
+
+    /// Returns the phase at which the group's dotfiles are symlinked
+    pub fn symlink_phase(&self, group: &str) -> u32 {
+        self.symlink_phase
+            .get(group)
+            .copied()
+            .unwrap_or(DEFAULT_SYMLINK_PHASE)
+    }
+
+    /// Returns the profile called `name` or lists the available profiles if it doesn't exist
+    pub fn profile(&self, name: &str, output: &mut String) -> Result<&Profile, ExitCode> {
+        if let Some(profile) = self.profiles.get(name) {
+            return Ok(profile);
+        }
+
+        output.push_str(&format!("There's no profile called `{name}`.\n"));
+        if self.profiles.is_empty() {
+            output.push_str(&format!(
+                "No profiles have been defined in {CONFIG_FILE}.\n"
+            ));
+        } else {
+            output.push_str("Available profiles:\n");
+            for profile in self.profiles.keys() {
+                output.push_str(&format!("\t{profile}\n"));
+            }
+        }
+
+        Err(ExitCode::FAILURE)
+    }
+}
+
+/// Appends the groups and exclusions defined by `profile` to the ones supplied by the user
+pub fn expand_profile(
+    profile: &str,
+    groups: &mut Vec<String>,
+    exclude: &mut Vec<String>,
+    output: &mut String,
+) -> Result<(), ExitCode> {
+    let config = Config::current(output)?;
+    let profile = config.profile(profile, output)?;
+
+    groups.extend(profile.groups.iter().cloned());
+    exclude.extend(profile.exclude.iter().cloned());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_profiles() {
+        let config = Config::parse(
+            r#"
+            [profiles.work]
+            groups = ["zsh", "neovim"]
+            exclude = ["neovim"]
+
+            [profiles.server]
+            groups = ["zsh"]
+            "#,
+            &mut "".into(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.profile("work", &mut "".into()).unwrap(),
+            &Profile {
+                groups: vec!["zsh".into(), "neovim".into()],
+                exclude: vec!["neovim".into()],
+            }
+        );
+        assert!(config
+            .profile("server", &mut "".into())
+            .unwrap()
+            .exclude
+            .is_empty());
+    }
+
+    #[test]
+    fn deploy_order() {
+        let config = Config::parse("order = [\"zsh\", \"base\"]", &mut "".into()).unwrap();
+
+        let mut groups = ["alacritty", "base", "neovim", "zsh"].map(String::from);
+        config.sort_by_deploy_order(&mut groups);
+        assert_eq!(groups, ["zsh", "base", "alacritty", "neovim"]);
+    }
+
+    #[test]
+    fn hooks_cwd() {
+        let config = Config::parse("", &mut "".into()).unwrap();
+        assert_eq!(config.hooks_cwd, HooksCwd::Target);
+
+        let config = Config::parse("hooks_cwd = \"hooks\"", &mut "".into()).unwrap();
+        assert_eq!(config.hooks_cwd, HooksCwd::Hooks);
+
+        assert!(Config::parse("hooks_cwd = \"home\"", &mut "".into()).is_err());
+    }
+
+    #[test]
+    fn symlink_phase() {
+        let config = Config::parse("[symlink_phase]\nneovim = 30", &mut "".into()).unwrap();
+        assert_eq!(config.symlink_phase("neovim"), 30);
+        assert_eq!(config.symlink_phase("zsh"), DEFAULT_SYMLINK_PHASE);
+    }
+
+    #[test]
+    fn unknown_profile_lists_available() {
+        let config = Config::parse("[profiles.home]\ngroups = [\"zsh\"]", &mut "".into()).unwrap();
+
+        let mut output = String::new();
+        assert!(config.profile("work", &mut output).is_err());
+        assert!(output.contains("home"));
+    }
+}
@@ -1,17 +1,42 @@
 //! Contains utilities to handle dotfiles
 
-use owo_colors::OwoColorize;
-
+use crate::config::{Config, CONFIG_FILE};
 use crate::dotfiles;
 use crate::fileops;
 use std::env;
+use std::fs;
 use std::path::PathBuf;
-use std::process::ExitStatus;
+use std::sync::OnceLock;
 use std::{
     path::{self, Component},
     process,
 };
 
+/// Directory inside of the dotfiles where tuckr keeps what only applies to this machine
+pub const STATE_DIR: &str = ".tuckr";
+
+/// Optional file at the root of a group containing the directory the group should be deployed to
+pub const TARGET_OVERRIDE_FILE: &str = ".tuckr-target";
+
+/// Optional file at the root of a group containing a relative path its files are deployed under,
+/// eg: `.config/myapp` deploys `Configs/myapp/config.toml` to `~/.config/myapp/config.toml`
+pub const PREFIX_FILE: &str = ".tuckr-prefix";
+
+/// Optional file at the root of a group mapping files to the name they should be deployed as
+///
+/// Each line has the form `source = target`, both relative to the group, eg: `bashrc = .bashrc`.
+/// Empty lines and lines starting with `#` are ignored
+pub const RENAME_FILE: &str = ".tuckr-rename";
+
+/// Optional file at the root of a group listing additional places files should be symlinked to
+///
+/// Each line has the form `source = target`, like in `.tuckr-rename`, a file can be listed once
+/// per extra target, eg: `.bashrc = .bash_profile`
+pub const LINKS_FILE: &str = ".tuckr-links";
+
+/// Prefix that stands in for a leading `.` when `dot_prefix` is enabled in the config
+pub const DOT_PREFIX: &str = "dot-";
+
 pub const VALID_TARGETS: &[&str] = &[
     // default target_os values
     "_windows",
@@ -24,26 +49,76 @@ pub const VALID_TARGETS: &[&str] = &[
     "_openbsd",
     "_netbsd",
     "_none",
-    // default target_family values
+    // default target_family values, `_windows` is both an os and a family
     "_unix",
-    "_windows",
 ];
 
 /// Exit codes
-/// Couldn't find the dotfiles directory
-#[derive(Debug, thiserror::Error)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
 pub enum ReturnCode {
+    /// Couldn't find the dotfiles directory
+    #[error("Couldn't Find Dotfiles")]
     CouldntFindDotfiles = 2,
     /// No Configs/Hooks/Secrets folder setup
+    #[error("No Setup Folder")]
     NoSetupFolder = 3,
     /// Referenced file does not exist in the current directory
+    #[error("No Such File Or Dir")]
     NoSuchFileOrDir = 4,
     /// Failed to encrypt referenced file
+    #[error("Encryption Failed")]
     EncryptionFailed = 5,
     /// Failed to decrypt referenced file
+    #[error("Decryption Failed")]
     DecryptionFailed = 6,
     /// Failed to Symlink
+    #[error("Couldn't Symlink File")]
     CouldntSymlinkFile = 7,
+    /// A file couldn't be read, written or removed
+    #[error("File Operation Failed")]
+    FileOperationFailed = 8,
+}
+
+impl ReturnCode {
+    /// Every exit code tuckr uses besides 0 for success and 1 for generic failures
+    pub const ALL: [ReturnCode; 7] = [
+        ReturnCode::CouldntFindDotfiles,
+        ReturnCode::NoSetupFolder,
+        ReturnCode::NoSuchFileOrDir,
+        ReturnCode::EncryptionFailed,
+        ReturnCode::DecryptionFailed,
+        ReturnCode::CouldntSymlinkFile,
+        ReturnCode::FileOperationFailed,
+    ];
+
+    /// Returns the exit code tuckr exits with, eg: 6 for DecryptionFailed
+    pub fn code(self) -> u8 {
+        self as u8
+    }
+
+    /// Returns the name of the variant, eg: `DecryptionFailed`
+    pub fn name(self) -> &'static str {
+        match self {
+            ReturnCode::CouldntFindDotfiles => "CouldntFindDotfiles",
+            ReturnCode::NoSetupFolder => "NoSetupFolder",
+            ReturnCode::NoSuchFileOrDir => "NoSuchFileOrDir",
+            ReturnCode::EncryptionFailed => "EncryptionFailed",
+            ReturnCode::DecryptionFailed => "DecryptionFailed",
+            ReturnCode::CouldntSymlinkFile => "CouldntSymlinkFile",
+            ReturnCode::FileOperationFailed => "FileOperationFailed",
+        }
+    }
+}
+
+impl TryFrom<u8> for ReturnCode {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        ReturnCode::ALL
+            .into_iter()
+            .find(|code| code.code() == value)
+            .ok_or(())
+    }
 }
 
 impl From<ReturnCode> for process::ExitCode {
@@ -52,15 +127,58 @@ impl From<ReturnCode> for process::ExitCode {
     }
 }
 
-impl std::fmt::Display for ReturnCode {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            ReturnCode::CouldntFindDotfiles => write!(f, "Couldn't Find Dotfiles"),
-            ReturnCode::NoSetupFolder => write!(f, "No Setup Folder"),
-            ReturnCode::NoSuchFileOrDir => write!(f, "No Such File Or Dir"),
-            ReturnCode::EncryptionFailed => write!(f, "Encryption Failed"),
-            ReturnCode::DecryptionFailed => write!(f, "Decryption Failed"),
-            ReturnCode::CouldntSymlinkFile => write!(f, "Couldn't Symlink File"),
+impl From<std::io::Error> for ReturnCode {
+    fn from(value: std::io::Error) -> Self {
+        match value.kind() {
+            std::io::ErrorKind::NotFound => ReturnCode::NoSuchFileOrDir,
+            std::io::ErrorKind::AlreadyExists => ReturnCode::CouldntSymlinkFile,
+            _ => ReturnCode::FileOperationFailed,
+        }
+    }
+}
+
+/// Errors raised while handling dotfiles, carrying the path that caused them
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("couldn't find the dotfiles directory")]
+    DotfilesNotFound,
+    #[error("couldn't find the home directory")]
+    HomeNotFound,
+    #[error("{} does not belong to the dotfiles directory", path.display())]
+    NotADotfile { path: PathBuf },
+    #[error("{} is outside of the home directory", path.display())]
+    OutsideHome { path: PathBuf },
+    #[error("{} already exists", path.display())]
+    AlreadyExists { path: PathBuf },
+    #[error("{} uses ${{{var}}} but it isn't set", path.display())]
+    MissingEnvVar { var: String, path: PathBuf },
+    #[error("failed to symlink {}: {source}", path.display())]
+    Symlink {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to {operation} {}: {source}", path.display())]
+    Io {
+        operation: &'static str,
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to render {}: {reason}", path.display())]
+    Template { path: PathBuf, reason: String },
+}
+
+impl From<Error> for ReturnCode {
+    fn from(value: Error) -> Self {
+        match value {
+            Error::DotfilesNotFound => ReturnCode::CouldntFindDotfiles,
+            Error::HomeNotFound => ReturnCode::NoSuchFileOrDir,
+            Error::NotADotfile { .. } | Error::OutsideHome { .. } | Error::MissingEnvVar { .. } => {
+                ReturnCode::NoSuchFileOrDir
+            }
+            Error::AlreadyExists { .. } | Error::Symlink { .. } | Error::Template { .. } => {
+                ReturnCode::CouldntSymlinkFile
+            }
+            Error::Io { source, .. } => ReturnCode::from(source),
         }
     }
 }
@@ -73,16 +191,20 @@ pub struct Dotfile {
 }
 
 impl TryFrom<path::PathBuf> for Dotfile {
-    type Error = String;
+    type Error = Error;
 
     fn try_from(value: path::PathBuf) -> Result<Self, Self::Error> {
         let mut output: String = "".into();
-        /// Extracts group name from tuckr directories
-        pub fn to_group_path(group_path: &path::PathBuf, output: &mut String) -> Result<path::PathBuf, ReturnCode> {
+        /// Extracts group path and name from tuckr directories
+        pub fn to_group_path(
+            group_path: &path::PathBuf,
+            output: &mut String,
+        ) -> Result<(path::PathBuf, String), ReturnCode> {
             let dotfiles_dir = get_dotfiles_path(output)?;
-            let configs_dir = dotfiles_dir.join("Configs");
-            let hooks_dir = dotfiles_dir.join("Hooks");
-            let secrets_dir = dotfiles_dir.join("Secrets");
+            let configs_dir = DotfileType::Configs.dir_in(&dotfiles_dir);
+            let hooks_dir = DotfileType::Hooks.dir_in(&dotfiles_dir);
+            let secrets_dir = DotfileType::Secrets.dir_in(&dotfiles_dir);
+            let decrypted_dir = DotfileType::Decrypted.dir_in(&dotfiles_dir);
 
             let dotfile_root_dir = if group_path.starts_with(&configs_dir) {
                 configs_dir
@@ -90,38 +212,62 @@ impl TryFrom<path::PathBuf> for Dotfile {
                 hooks_dir
             } else if group_path.starts_with(&secrets_dir) {
                 secrets_dir
+            } else if group_path.starts_with(&decrypted_dir) {
+                decrypted_dir
             } else {
-                output.push_str("path does not belong to dotfiles.");
+                output.push_str("path does not belong to dotfiles.\n");
                 return Err(ReturnCode::NoSuchFileOrDir);
             };
 
-            let group = if *group_path == dotfile_root_dir {
-                Ok(dotfile_root_dir)
-            } else {
-                let Component::Normal(group_relpath) = group_path
-                    .strip_prefix(&dotfile_root_dir)
+            if *group_path == dotfile_root_dir {
+                let group_name = dotfile_root_dir
+                    .file_name()
                     .unwrap()
-                    .components()
-                    .next()
+                    .to_str()
                     .unwrap()
-                else {
-                    output.push_str("failed to get group path relative to dotfile dir.");
-                    return Err(ReturnCode::NoSuchFileOrDir);
-                };
+                    .into();
+                return Ok((dotfile_root_dir, group_name));
+            }
 
-                Ok(dotfile_root_dir.join(group_relpath))
+            let mut components = group_path
+                .strip_prefix(&dotfile_root_dir)
+                .unwrap()
+                .components();
+            let Some(Component::Normal(group_relpath)) = components.next() else {
+                output.push_str("failed to get group path relative to dotfile dir.\n");
+                return Err(ReturnCode::NoSuchFileOrDir);
             };
 
-            group
+            let mut group_dir = dotfile_root_dir.join(group_relpath);
+            let mut group_name: String = group_relpath.to_str().unwrap().into();
+
+            // subgroups are named after their path inside of the groups directory, eg: editors/nvim,
+            // the special groups are never split since their directories mirror where they're
+            // deployed to, eg: `Root/etc` and `Root/usr`
+            if recursive_groups() && !is_special_group(&group_name) {
+                for component in components {
+                    let Component::Normal(name) = component else {
+                        break;
+                    };
+                    if !is_parent_group(&group_dir) {
+                        break;
+                    }
+                    group_dir.push(name);
+                    group_name = format!("{group_name}/{}", name.to_str().unwrap());
+                }
+            }
+
+            Ok((group_dir, group_name))
         }
 
-        let group_path = match to_group_path(&value, &mut output) {
-            Ok(p) => p,
-            Err(_) => return Err(output),
+        let (group_path, group_name) = match to_group_path(&value, &mut output) {
+            Ok(group) => group,
+            Err(ReturnCode::CouldntFindDotfiles) => return Err(Error::DotfilesNotFound),
+            Err(_) => return Err(Error::NotADotfile { path: value }),
         };
 
         Ok(Dotfile {
-            group_name: group_path.file_name().unwrap().to_str().unwrap().into(),
+            group_name,
             path: value,
             group_path,
         })
@@ -132,75 +278,728 @@ pub fn group_ends_with_target_name(group: &str) -> bool {
     VALID_TARGETS.iter().any(|target| group.ends_with(target))
 }
 
+/// Returns the valid target a group's suffix was most likely meant to be, eg: `_linux` for
+/// `vim_linx`
+///
+/// Groups with a misspelled suffix aren't conditional and would be deployed on every platform
+pub fn misspelled_target_suffix(group: &str) -> Option<&'static str> {
+    if group_ends_with_target_name(group) {
+        return None;
+    }
+
+    let (_, suffix) = group.rsplit_once('_')?;
+    let suffix = format!("_{}", suffix.to_lowercase());
+
+    // typos usually keep the first letter, longer names are allowed to have more of them
+    VALID_TARGETS
+        .iter()
+        .filter(|target| target.chars().nth(1) == suffix.chars().nth(1))
+        .map(|target| (*target, edit_distance(&suffix, target)))
+        .filter(|(target, distance)| *distance <= ((target.len() - 1) / 3).max(1))
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(target, _)| target)
+}
+
+/// Number of single character insertions, removals or substitutions to turn `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut distances: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut previous = distances[0];
+        distances[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous + usize::from(a_char != *b_char);
+            previous = distances[j + 1];
+            distances[j + 1] = substitution.min(previous + 1).min(distances[j] + 1);
+        }
+    }
+
+    distances[b.len()]
+}
+
+/// Warns about groups whose suffix looks like a misspelled target
+pub fn warn_misspelled_targets(groups: &[String], output: &mut String) {
+    for group in groups {
+        if let Some(target) = misspelled_target_suffix(group) {
+            output.push_str(&format!(
+                "{group} doesn't end with a valid target, did you mean `{target}`? It will be deployed on every platform.\n"
+            ));
+        }
+    }
+}
+
+/// Returns true if a name has no target suffix or if its suffix matches the current platform
+pub fn is_valid_target_name(name: &str) -> bool {
+    // Gets the current OS and OS family
+    let current_target_os = format!("_{}", env::consts::OS);
+    let current_target_family = format!("_{}", env::consts::FAMILY);
+
+    if group_ends_with_target_name(name) {
+        name.ends_with(&current_target_os) || name.ends_with(&current_target_family)
+    } else {
+        true
+    }
+}
+
+/// Returns the group without its target suffix, eg: `alacritty` for `alacritty_linux`
+pub fn base_group_name(group: &str) -> &str {
+    VALID_TARGETS
+        .iter()
+        .find_map(|target| group.strip_suffix(target))
+        .unwrap_or(group)
+}
+
+/// Whether `name` is the group itself or one of its platform specific groups, eg: `vim_linux`
+/// for `vim` but not `vimrc_linux`
+pub fn is_related_group(name: &str, group: &str) -> bool {
+    name == group || base_group_name(name) == group
+}
+
+/// Ranks how specific a group is, files from more specific groups replace the ones from less
+/// specific groups: the base group comes first, then `_unix` and then the operating systems
+pub fn target_precedence(group: &str) -> u8 {
+    if !group_ends_with_target_name(group) {
+        0
+    } else if group.ends_with("_unix") {
+        1
+    } else {
+        2
+    }
+}
+
+/// Returns true if `group` is deployed on this platform and replaces the files of `base` that
+/// it also has, eg: `alacritty_linux` overrides `alacritty` and `alacritty_unix` on Linux
+pub fn overrides_group(group: &str, base: &str) -> bool {
+    base_group_name(group) == base_group_name(base)
+        && is_valid_target_name(group)
+        && target_precedence(group) > target_precedence(base)
+}
+
+/// Returns true if `file` is deployed on this platform instead of `base`, which has the same
+/// target, eg: `config_linux` replaces `config` and `config_unix` on Linux
+pub fn overrides_file(file: &Dotfile, base: &Dotfile) -> bool {
+    file.group_name == base.group_name
+        && file.is_valid_target()
+        && file.target_precedence() > base.target_precedence()
+}
+
+/// Returns the path without the target suffixes of its components, eg: `config` for `config_linux`
+fn strip_target_suffixes(path: &path::Path) -> path::PathBuf {
+    path.components()
+        .map(|component| match component {
+            Component::Normal(name) => match name.to_str() {
+                Some(name) => base_group_name(name).into(),
+                None => name.to_owned(),
+            },
+            component => component.as_os_str().to_owned(),
+        })
+        .collect()
+}
+
 impl Dotfile {
     /// Returns true if the target can be used by the current platform
+    ///
+    /// Both the group and the files and directories inside of it can have a target suffix
     pub fn is_valid_target(&self) -> bool {
-        // Gets the current OS and OS family
-        let current_target_os = format!("_{}", env::consts::OS);
-        let current_target_family = format!("_{}", env::consts::FAMILY);
+        is_valid_target_name(&self.group_name) && self.file_targets_platform()
+    }
 
-        // returns true if a group has no suffix or its suffix matches the current OS
-        let group = self.group_name.as_str();
+    /// Returns true if the target suffixes of the file and of the directories it's in below its
+    /// group, if there are any, match the current platform, eg: `config_linux` only does on Linux
+    pub fn file_targets_platform(&self) -> bool {
+        self.group_relpath()
+            .components()
+            .all(|component| is_valid_target_name(&component.as_os_str().to_string_lossy()))
+    }
 
-        if group_ends_with_target_name(group) {
-            group.ends_with(&current_target_os) || group.ends_with(&current_target_family)
-        } else {
-            true
-        }
+    /// Returns true if the name of the file itself ends with a target suffix, eg: `config_linux`
+    pub fn has_target_suffix(&self) -> bool {
+        self.path != self.group_path
+            && self
+                .path
+                .file_name()
+                .is_some_and(|name| group_ends_with_target_name(&name.to_string_lossy()))
+    }
+
+    /// Ranks how specific the target suffixes of the file and the directories it's in below its
+    /// group are, like `target_precedence` does for groups
+    pub fn target_precedence(&self) -> u8 {
+        self.group_relpath()
+            .components()
+            .map(|component| target_precedence(&component.as_os_str().to_string_lossy()))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns the path of the file relative to its group
+    fn group_relpath(&self) -> &path::Path {
+        self.path
+            .strip_prefix(&self.group_path)
+            .unwrap_or(path::Path::new(""))
     }
 
     /// Checks whether the current groups is targetting the root path aka `/`
-    pub fn targets_root(&self, output: &mut String) -> bool {
-        let root_dir = get_dotfiles_path(output).unwrap().join("Configs").join("Root");
-        self.group_path.starts_with(root_dir)
+    pub fn targets_root(&self, output: &mut String) -> Result<bool, ReturnCode> {
+        let root_dir = DotfileType::Configs
+            .dir_in(&get_dotfiles_path(output)?)
+            .join("Root");
+        Ok(self.group_path.starts_with(root_dir))
+    }
+
+    /// Whether this is a template that's rendered to its target instead of being symlinked
+    pub fn is_template(&self) -> bool {
+        self.path.is_file()
+            && self
+                .path
+                .extension()
+                .is_some_and(|ext| ext == crate::templates::TEMPLATE_EXTENSION)
+    }
+
+    /// Returns true if the file is used to configure its group rather than being a dotfile
+    ///
+    /// These are the files prefixed with `.tuckr` at the root of a group, eg: `.tuckr-target`
+    pub fn is_group_metadata(&self) -> bool {
+        self.path.parent() == Some(self.group_path.as_path())
+            && self
+                .path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(".tuckr"))
+    }
+
+    /// Returns the directory where the group's files are deployed to
+    ///
+    /// This is the directory of the group if it's one of the special groups, eg: `/` for the Root
+    /// group, the path inside of the group's `.tuckr-target` file if it has one, or $HOME otherwise
+    pub fn target_base_dir(&self, output: &mut String) -> Result<path::PathBuf, Error> {
+        let home_dir = dirs::home_dir().ok_or(Error::HomeNotFound)?;
+
+        if let Some(special_dir) = special_group_dir(&self.group_name) {
+            return Ok(special_dir);
+        }
+
+        let override_file = self.group_path.join(TARGET_OVERRIDE_FILE);
+        let Ok(target_override) = fs::read_to_string(&override_file) else {
+            return Ok(home_dir);
+        };
+
+        let target_override = target_override.trim();
+        let target_dir = match target_override.strip_prefix('~') {
+            Some(rest) => home_dir.join(rest.trim_start_matches(['/', '\\'])),
+            None => path::PathBuf::from(target_override),
+        };
+
+        if target_override.is_empty() || !target_dir.is_absolute() {
+            output.push_str(&format!(
+                "{} must contain an absolute path, deploying `{}` to $HOME instead.\n",
+                override_file.display(),
+                self.group_name,
+            ));
+            return Ok(home_dir);
+        }
+
+        Ok(target_dir)
+    }
+
+    /// Returns the relative path inside of the group's `.tuckr-prefix`, or an empty path if it
+    /// doesn't have one
+    pub fn target_prefix(&self, output: &mut String) -> path::PathBuf {
+        let prefix_file = self.group_path.join(PREFIX_FILE);
+        let Ok(prefix) = fs::read_to_string(&prefix_file) else {
+            return path::PathBuf::new();
+        };
+
+        let prefix = path::PathBuf::from(prefix.trim());
+        let is_relative = prefix
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+        if !is_relative {
+            output.push_str(&format!(
+                "{} must contain a relative path inside of the target directory, ignoring it.\n",
+                prefix_file.display(),
+            ));
+            return path::PathBuf::new();
+        }
+
+        prefix
+    }
+
+    /// Returns the directory the group's files are deployed under, which is `target_base_dir`
+    /// followed by the group's `.tuckr-prefix`, with its environment variables expanded
+    pub fn target_dir(&self, output: &mut String) -> Result<path::PathBuf, Error> {
+        let target_dir = self
+            .target_base_dir(output)?
+            .join(self.target_prefix(output));
+        expand_env_vars(&target_dir).map_err(|var| Error::MissingEnvVar {
+            var,
+            path: self.group_path.clone(),
+        })
+    }
+
+    /// Reads the `source = target` pairs of one of the group's manifests
+    ///
+    /// Invalid lines are reported to `output` and skipped
+    fn read_manifest(
+        &self,
+        manifest: &str,
+        output: &mut String,
+    ) -> Vec<(path::PathBuf, path::PathBuf)> {
+        let manifest = self.group_path.join(manifest);
+        let Ok(entries) = fs::read_to_string(&manifest) else {
+            return Vec::new();
+        };
+
+        let mut pairs = Vec::new();
+        for line in entries.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let Some(pair) = line
+                .split_once('=')
+                .map(|(source, target)| {
+                    (
+                        path::PathBuf::from(source.trim()),
+                        path::PathBuf::from(target.trim()),
+                    )
+                })
+                .filter(|(_, target)| target.is_relative() && !target.as_os_str().is_empty())
+            else {
+                output.push_str(&format!(
+                    "{}: ignoring invalid line `{line}`, expected `source = target` with a relative target.\n",
+                    manifest.display(),
+                ));
+                continue;
+            };
+
+            pairs.push(pair);
+        }
+
+        pairs
+    }
+
+    /// Applies the group's `.tuckr-rename` manifest to a path relative to the group
+    ///
+    /// Renaming a directory also renames everything inside of it
+    pub fn renamed_path(&self, group_relpath: &path::Path, output: &mut String) -> path::PathBuf {
+        for (source, target) in self.read_manifest(RENAME_FILE, output) {
+            if let Ok(rest) = group_relpath.strip_prefix(&source) {
+                return if rest.as_os_str().is_empty() {
+                    target
+                } else {
+                    target.join(rest)
+                };
+            }
+        }
+
+        group_relpath.to_path_buf()
+    }
+
+    /// Returns the additional places the file is symlinked to from the group's `.tuckr-links`
+    pub fn extra_target_paths(&self, output: &mut String) -> Result<Vec<path::PathBuf>, Error> {
+        let Ok(group_relpath) = self.path.strip_prefix(&self.group_path) else {
+            return Ok(Vec::new());
+        };
+
+        let base_dir = self
+            .target_base_dir(output)?
+            .join(self.target_prefix(output));
+        self.read_manifest(LINKS_FILE, output)
+            .into_iter()
+            .filter(|(source, _)| source == group_relpath)
+            .map(|(_, target)| {
+                expand_env_vars(&base_dir.join(target)).map_err(|var| Error::MissingEnvVar {
+                    var,
+                    path: self.path.clone(),
+                })
+            })
+            .collect()
     }
 
     /// Converts a path string from dotfiles/Configs to where they should be
     /// deployed on $HOME
-    pub fn to_target_path(&self, output: &mut String) -> path::PathBuf {
-        // uses join("") so that the path appends / or \ depending on platform
-        let dotfiles_configs_path = get_dotfiles_path(output).unwrap().join("Configs").join("");
-        let dotfiles_configs_path = dotfiles_configs_path.to_str().unwrap();
-        let group_path = self.path.clone();
-        let group_path = {
-            let group_path = group_path.to_str().unwrap();
-            let group_path = group_path.strip_prefix(dotfiles_configs_path).unwrap();
-            match group_path.split_once(path::MAIN_SEPARATOR) {
-                Some(path) => path.1,
-                None => group_path,
+    ///
+    /// Errors, like the path using an environment variable that isn't set, are reported to
+    /// `output` and returned
+    pub fn to_target_path(&self, output: &mut String) -> Result<path::PathBuf, ReturnCode> {
+        self.try_to_target_path(output).map_err(|err| {
+            output.push_str(&format!("{err}\n"));
+            err.into()
+        })
+    }
+
+    /// Same as `to_target_path` but the environment variables that aren't set are reported to
+    /// `output` and left as they are, eg: `${XDG_DATA_HOME}/fonts`, so that the target can still
+    /// be shown. It must never be written to
+    pub fn display_target_path(&self, output: &mut String) -> Result<path::PathBuf, ReturnCode> {
+        match self.try_to_target_path(output) {
+            Ok(target) => Ok(target),
+            Err(err @ Error::MissingEnvVar { .. }) => {
+                output.push_str(&format!("{err}\n"));
+                self.unexpanded_target_path(output)
+                    .map_err(ReturnCode::from)
             }
+            Err(err) => {
+                output.push_str(&format!("{err}\n"));
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Same as `to_target_path` but returns the error without reporting it
+    pub fn try_to_target_path(&self, output: &mut String) -> Result<path::PathBuf, Error> {
+        let target = self.unexpanded_target_path(output)?;
+        expand_env_vars(&target).map_err(|var| Error::MissingEnvVar {
+            var,
+            path: self.path.clone(),
+        })
+    }
+
+    fn unexpanded_target_path(&self, output: &mut String) -> Result<path::PathBuf, Error> {
+        let dotfiles_dir = get_dotfiles_path(output).map_err(|_| Error::DotfilesNotFound)?;
+        let decrypted_path = DotfileType::Decrypted.dir_in(&dotfiles_dir);
+        let dotfiles_configs_path = if self.path.starts_with(&decrypted_path) {
+            decrypted_path
+        } else {
+            DotfileType::Configs.dir_in(&dotfiles_dir)
         };
+        let configs_relpath =
+            self.path
+                .strip_prefix(dotfiles_configs_path)
+                .map_err(|_| Error::NotADotfile {
+                    path: self.path.clone(),
+                })?;
 
-        if self.targets_root(output) {
-            path::PathBuf::from(path::MAIN_SEPARATOR_STR).join(group_path)
+        // drops the group's directories, comparing components so that it works with either
+        // separator
+        let group_depth = self.group_name.split('/').count();
+        let mut components = configs_relpath.components();
+        let group_path = if configs_relpath.components().count() > group_depth {
+            components.nth(group_depth - 1);
+            components.as_path()
         } else {
-            dirs::home_dir().unwrap().join(group_path)
+            configs_relpath
+        };
+
+        let mut group_path = self.renamed_path(group_path, output);
+        if self.is_template() {
+            group_path.set_extension("");
         }
+        // files are deployed without their target suffix, eg: `config_linux` to `config`
+        if self.path != self.group_path {
+            group_path = strip_target_suffixes(&group_path);
+        }
+        if Config::current(&mut String::new()).is_ok_and(|config| config.dot_prefix) {
+            group_path = expand_dot_prefix(&group_path);
+        }
+        Ok(self
+            .target_base_dir(output)?
+            .join(self.target_prefix(output))
+            .join(group_path))
     }
 
     /// Goes through every file in Configs/<group_dir> and applies the function
-    pub fn map<F>(&self, mut func: F)
+    ///
+    /// It stops at the first file that isn't a dotfile and returns why
+    pub fn map<F>(&self, mut func: F) -> Result<(), Error>
     where
         F: FnMut(Dotfile),
     {
+        let mut result = Ok(());
         fileops::dir_map(self.path.clone(), |p| {
-            let dotfile = Self::try_from(p.to_path_buf()).unwrap();
+            if result.is_err() {
+                return;
+            }
+
+            let dotfile = match Self::try_from(p.to_path_buf()) {
+                Ok(dotfile) => dotfile,
+                Err(err) => {
+                    result = Err(err);
+                    return;
+                }
+            };
+            if dotfile.is_group_metadata() {
+                return;
+            }
+
             func(dotfile);
+        });
+
+        result
+    }
+}
+
+/// Whether two paths point to the same place, comparing their components instead of their
+/// string representation
+///
+/// Ignores repeated separators, `.` components and, on Windows, the mix of `/` and `\\` and
+/// verbatim prefixes like `\\?\C:` that `fs::read_link` might return
+///
+/// Absolute paths that only differ in a symlinked directory, eg: a symlinked $HOME or dotfiles
+/// directory, are the same too. Their last component isn't resolved so that two different
+/// symlinks to the same file aren't
+pub fn same_path(a: &path::Path, b: &path::Path) -> bool {
+    fn normalized(path: &path::Path) -> Vec<std::ffi::OsString> {
+        path.components()
+            .filter(|component| *component != Component::CurDir)
+            .map(|component| match component {
+                Component::Prefix(prefix) => match prefix.kind() {
+                    path::Prefix::Disk(drive) | path::Prefix::VerbatimDisk(drive) => {
+                        format!("{}:", drive.to_ascii_uppercase() as char).into()
+                    }
+                    _ => prefix.as_os_str().to_owned(),
+                },
+                component => component.as_os_str().to_owned(),
+            })
+            .collect()
+    }
+
+    fn resolved(path: &path::Path) -> Option<PathBuf> {
+        let parent = fs::canonicalize(path.parent()?).ok()?;
+        Some(parent.join(path.file_name()?))
+    }
+
+    if normalized(a) == normalized(b) {
+        return true;
+    }
+
+    a.is_absolute() && b.is_absolute() && resolved(a).is_some_and(|a| resolved(b) == Some(a))
+}
+
+/// Replaces every `${VAR}` in a path with the value of the environment variable `VAR`
+///
+/// Variables holding absolute paths replace everything that comes before them, eg:
+/// `${XDG_DATA_HOME}/fonts` is deployed inside of $XDG_DATA_HOME wherever it is.
+/// Returns the name of the first variable that isn't set
+pub fn expand_env_vars(path: &path::Path) -> Result<PathBuf, String> {
+    expand_vars_with(path, |var| env::var(var).ok())
+}
+
+/// Same as `expand_env_vars` but the values of the variables are looked up with `lookup`
+fn expand_vars_with(
+    path: &path::Path,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<PathBuf, String> {
+    let mut expanded = PathBuf::new();
+    for component in path.components() {
+        let Some(mut rest) = component.as_os_str().to_str().filter(|c| c.contains("${")) else {
+            expanded.push(component);
+            continue;
+        };
+
+        let mut component = String::new();
+        while let Some((before, after)) = rest.split_once("${") {
+            let Some((var, after)) = after.split_once('}') else {
+                break;
+            };
+
+            component.push_str(before);
+            component.push_str(&lookup(var).ok_or_else(|| var.to_string())?);
+            rest = after;
+        }
+        component.push_str(rest);
+
+        expanded.push(component);
+    }
+
+    Ok(expanded)
+}
+
+/// Replaces the `dot-` prefix of every component of a path with a `.`, eg: `dot-config/nvim`
+/// becomes `.config/nvim`
+pub fn expand_dot_prefix(path: &path::Path) -> PathBuf {
+    path.components()
+        .map(|component| match component {
+            Component::Normal(name) => match name.to_str().and_then(|n| n.strip_prefix(DOT_PREFIX))
+            {
+                Some(name) => format!(".{name}").into(),
+                None => name.to_os_string(),
+            },
+            component => component.as_os_str().to_os_string(),
         })
+        .collect()
+}
+
+/// Replaces the leading `.` of every hidden component of a path with `dot-`, the reverse of
+/// [`expand_dot_prefix`]
+pub fn collapse_dot_prefix(path: &path::Path) -> PathBuf {
+    path.components()
+        .map(|component| match component {
+            Component::Normal(name) => match name.to_str().and_then(|n| n.strip_prefix('.')) {
+                Some(name) if !name.is_empty() => format!("{DOT_PREFIX}{name}").into(),
+                _ => name.to_os_string(),
+            },
+            component => component.as_os_str().to_os_string(),
+        })
+        .collect()
+}
+
+/// Returns where a group that's deployed somewhere other than $HOME because of its name is
+/// deployed to, or None for every other group
+///
+/// The special groups are Root, for `/`, and XdgData, XdgState and XdgCache, for
+/// `$XDG_DATA_HOME`, `$XDG_STATE_HOME` and `$XDG_CACHE_HOME` or their equivalent on the current
+/// platform. Platforms without a state directory deploy XdgState to the local data directory
+/// instead. Their platform specific groups are special too, eg: `XdgData_linux`
+pub fn special_group_dir(group: &str) -> Option<PathBuf> {
+    match base_group_name(group) {
+        "Root" => Some(get_root_path()),
+        "XdgData" => dirs::data_dir(),
+        "XdgState" => dirs::state_dir().or_else(dirs::data_local_dir),
+        "XdgCache" => dirs::cache_dir(),
+        _ => None,
     }
 }
 
+/// Whether the group is one of the special groups or their platform specific groups, see
+/// `special_group_dir`
+pub fn is_special_group(group: &str) -> bool {
+    matches!(
+        base_group_name(group),
+        "Root" | "XdgData" | "XdgState" | "XdgCache"
+    )
+}
+
+/// Returns the root of the filesystem, where the Root group is deployed to
+///
+/// On Windows this is the root of the system drive, eg: `C:\`
+pub fn get_root_path() -> PathBuf {
+    if cfg!(target_family = "windows") {
+        let system_drive = env::var("SystemDrive").unwrap_or_else(|_| "C:".into());
+        PathBuf::from(system_drive + path::MAIN_SEPARATOR_STR)
+    } else {
+        PathBuf::from(path::MAIN_SEPARATOR_STR)
+    }
+}
+
+/// File inside of the state directory used to keep multiple instances of tuckr from changing
+/// dotfiles at the same time
+pub const LOCK_FILE: &str = "lock";
+
+/// Creates the state directory if it doesn't exist yet, returning its path
+///
+/// The state directory is ignored by git since its files are only true for this machine
+pub fn create_state_dir(dotfiles_dir: &path::Path) -> std::io::Result<PathBuf> {
+    let state_dir = dotfiles_dir.join(STATE_DIR);
+    fs::create_dir_all(&state_dir)?;
+
+    let gitignore = state_dir.join(".gitignore");
+    if !gitignore.exists() {
+        fs::write(gitignore, "*\n")?;
+    }
+
+    Ok(state_dir)
+}
+
+/// Opens the lock file, creating it and the state directory if needed
+fn open_lock_file(output: &mut String) -> Result<(fs::File, PathBuf), process::ExitCode> {
+    let dotfiles_dir = get_dotfiles_path(output)?;
+    let lock_path = dotfiles_dir.join(STATE_DIR).join(LOCK_FILE);
+    match create_state_dir(&dotfiles_dir).and_then(|_| fs::File::create(&lock_path)) {
+        Ok(file) => Ok((file, lock_path)),
+        Err(e) => {
+            output.push_str(&format!("Couldn't create {}: {e}\n", lock_path.display()));
+            Err(ReturnCode::from(e).into())
+        }
+    }
+}
+
+/// Takes an exclusive lock on the dotfiles directory, failing if another instance holds it
+///
+/// The lock is held until the returned file is dropped
+pub fn lock_dotfiles(output: &mut String) -> Result<fs::File, process::ExitCode> {
+    let (lock_file, lock_path) = open_lock_file(output)?;
+
+    match lock_file.try_lock() {
+        Ok(()) => Ok(lock_file),
+        Err(fs::TryLockError::WouldBlock) => {
+            output.push_str(
+                "Another instance of tuckr is changing your dotfiles, wait for it to finish.\n",
+            );
+            Err(process::ExitCode::FAILURE)
+        }
+        Err(fs::TryLockError::Error(e)) => {
+            output.push_str(&format!("Couldn't lock {}: {e}\n", lock_path.display()));
+            Err(ReturnCode::from(e).into())
+        }
+    }
+}
+
+/// Takes an exclusive lock on the dotfiles directory like `lock_dotfiles`, waiting for other
+/// instances to release it instead of failing
+pub fn wait_for_dotfiles_lock(output: &mut String) -> Result<fs::File, process::ExitCode> {
+    let (lock_file, lock_path) = open_lock_file(output)?;
+
+    match lock_file.lock() {
+        Ok(()) => Ok(lock_file),
+        Err(e) => {
+            output.push_str(&format!("Couldn't lock {}: {e}\n", lock_path.display()));
+            Err(ReturnCode::from(e).into())
+        }
+    }
+}
+
+/// Returns where the dotfiles directory is expected to be, whether it exists or not
+pub fn default_dotfiles_path() -> Result<PathBuf, Error> {
+    let home_dir = dirs::home_dir().ok_or(Error::HomeNotFound)?;
+    Ok(home_dir.join(".dotfiles"))
+}
+
+/// Returns the closest of dir and its ancestors that is a dotfiles directory, which is a
+/// directory with either a Configs directory and a config file or all of the Configs, Hooks and
+/// Secrets directories inside of it
+///
+/// A single one of them isn't enough since directories like $HOME can have a `Configs` directory
+/// that has nothing to do with tuckr
+pub fn find_dotfiles_dir(dir: &path::Path) -> Option<PathBuf> {
+    let dtypes = [
+        DotfileType::Configs,
+        DotfileType::Hooks,
+        DotfileType::Secrets,
+    ];
+    let is_dotfiles_dir = |dir: &path::Path| {
+        let has_config = dir.join(CONFIG_FILE).is_file();
+        DotfileType::Configs.dir_in(dir).is_dir()
+            && (has_config || dtypes.iter().all(|dtype| dtype.dir_in(dir).is_dir()))
+    };
+
+    dir.ancestors()
+        .find(|dir| is_dotfiles_dir(dir))
+        .map(path::Path::to_path_buf)
+}
+
 /// Returns an Option<String> with the path to of the tuckr dotfiles directory
 ///
+/// The dotfiles directory the current directory is in is used over the default one, so that
+/// tuckr works from inside of any dotfiles repo
+///
 /// When run on a unit test it returns a temporary directory for testing purposes
 pub fn get_dotfiles_path(output: &mut String) -> Result<path::PathBuf, ReturnCode> {
-    let home_dotfiles = dirs::home_dir().unwrap().join(".dotfiles");
+    // the current directory doesn't change while a command runs, so its ancestors are only
+    // searched once instead of for every dotfile
+    static CWD_DOTFILES: OnceLock<Option<PathBuf>> = OnceLock::new();
 
     if cfg!(test) {
-        Ok(std::env::temp_dir()
+        return Ok(std::env::temp_dir()
             .join(format!("tuckr-{}", std::process::id()))
-            .join("dotfiles"))
-    } else if home_dotfiles.exists() {
+            .join("dotfiles"));
+    }
+
+    let cwd_dotfiles = CWD_DOTFILES.get_or_init(|| {
+        env::current_dir()
+            .ok()
+            .and_then(|cwd| find_dotfiles_dir(&cwd))
+    });
+    if let Some(cwd_dotfiles) = cwd_dotfiles {
+        return Ok(cwd_dotfiles.clone());
+    }
+
+    let home_dotfiles = default_dotfiles_path().map_err(|err| {
+        output.push_str(&format!("{err}\n"));
+        ReturnCode::from(err)
+    })?;
+    if home_dotfiles.exists() {
         Ok(home_dotfiles)
     } else {
         output.push_str(&format!(
@@ -214,9 +1013,15 @@ pub fn get_dotfiles_path(output: &mut String) -> Result<path::PathBuf, ReturnCod
     }
 }
 
-pub fn get_target_basepath(target: &path::Path) -> PathBuf {
-    let home_dir = dirs::home_dir().unwrap();
-    target.strip_prefix(home_dir).unwrap().into()
+/// Returns the path of target relative to $HOME, fails if it's outside of $HOME
+pub fn get_target_basepath(target: &path::Path) -> Result<PathBuf, Error> {
+    let home_dir = dirs::home_dir().ok_or(Error::HomeNotFound)?;
+    target
+        .strip_prefix(home_dir)
+        .map(path::Path::to_path_buf)
+        .map_err(|_| Error::OutsideHome {
+            path: target.to_path_buf(),
+        })
 }
 
 #[derive(Copy, Clone)]
@@ -224,25 +1029,166 @@ pub enum DotfileType {
     Configs,
     Secrets,
     Hooks,
+    /// Secrets decrypted with `decrypt --symlink`, they're symlinked like Configs and never
+    /// committed
+    Decrypted,
+}
+
+impl DotfileType {
+    /// Returns the name of the directory inside of dotfiles that holds this type of dotfile
+    pub fn dir_name(self) -> &'static str {
+        match self {
+            DotfileType::Configs => "Configs",
+            DotfileType::Secrets => "Secrets",
+            DotfileType::Hooks => "Hooks",
+            DotfileType::Decrypted => "Decrypted",
+        }
+    }
+
+    /// Returns the directory inside of dotfiles_dir that holds this type of dotfile, which is
+    /// nested inside of `configs_subdir` if there is one
+    pub fn dir_in(self, dotfiles_dir: &path::Path) -> PathBuf {
+        self.dir_in_subdir(dotfiles_dir, &configs_subdir())
+    }
+
+    /// Returns the directory that holds this type of dotfile when Configs, Hooks and Secrets are
+    /// nested inside of `subdir`
+    pub fn dir_in_subdir(self, dotfiles_dir: &path::Path, subdir: &path::Path) -> PathBuf {
+        dotfiles_dir.join(subdir).join(self.dir_name())
+    }
+}
+
+/// Environment variable with the path, relative to the dotfiles directory, of the directory
+/// holding Configs, Hooks and Secrets, eg: `tuckr` for `~/.dotfiles/tuckr/Configs`
+pub const CONFIGS_SUBDIR_VAR: &str = "TUCKR_CONFIGS_SUBDIR";
+
+/// Returns the directory Configs, Hooks and Secrets are in, relative to the dotfiles directory
+///
+/// It's empty unless `TUCKR_CONFIGS_SUBDIR` is set, paths that would leave the dotfiles
+/// directory are ignored
+pub fn configs_subdir() -> PathBuf {
+    parse_configs_subdir(env::var_os(CONFIGS_SUBDIR_VAR))
+}
+
+/// Returns the value of `TUCKR_CONFIGS_SUBDIR` as a path, which is empty if it's unset or would
+/// leave the dotfiles directory
+fn parse_configs_subdir(subdir: Option<std::ffi::OsString>) -> PathBuf {
+    subdir
+        .map(PathBuf::from)
+        .filter(|subdir| {
+            subdir
+                .components()
+                .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+        })
+        .unwrap_or_default()
 }
 
 /// Returns if a config has been setup for <group> on <dtype>
 pub fn dotfile_contains(dtype: DotfileType, group: &str, output: &mut String) -> bool {
-    let target_dir = match dtype {
-        DotfileType::Configs => "Configs",
-        DotfileType::Secrets => "Secrets",
-        DotfileType::Hooks => "Hooks",
-    };
-
     let Ok(dotfiles_dir) = get_dotfiles_path(output) else {
         return false;
     };
-    let group_src = dotfiles_dir.join(target_dir).join(group);
+    let group_src = dtype.dir_in(&dotfiles_dir).join(group);
     group_src.exists()
 }
 
+/// Returns the sorted names of all groups in dotfiles/{Configs,Hooks,Secrets}
+pub fn list_groups(dtype: DotfileType) -> Result<Vec<String>, ReturnCode> {
+    let groups_dir = dtype.dir_in(&get_dotfiles_path(&mut String::new())?);
+    let Ok(entries) = fs::read_dir(groups_dir) else {
+        return Err(ReturnCode::NoSetupFolder);
+    };
+
+    let mut groups = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            let group = entry.file_name().to_string_lossy().into_owned();
+            groups.extend(expand_group(&group, &[dtype]));
+        }
+    }
+
+    groups.sort();
+    Ok(groups)
+}
+
+/// Whether `recursive_groups` is enabled in the config
+fn recursive_groups() -> bool {
+    Config::current(&mut String::new()).is_ok_and(|config| config.recursive_groups)
+}
+
+/// Returns true if the group directory only holds subgroups, which are the directories inside of
+/// it when it doesn't contain anything else and none of them are hidden, eg: `Configs/editors`
+/// holding `nvim` and `emacs`
+///
+/// Groups only hold subgroups when `recursive_groups` is enabled in the config, which this doesn't
+/// check
+pub fn is_parent_group(group_dir: &path::Path) -> bool {
+    let Ok(entries) = fs::read_dir(group_dir) else {
+        return false;
+    };
+
+    let mut has_subgroups = false;
+    for entry in entries {
+        let Ok(entry) = entry else {
+            return false;
+        };
+        let is_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+        if !is_dir || entry.file_name().to_string_lossy().starts_with('.') {
+            return false;
+        }
+        has_subgroups = true;
+    }
+
+    has_subgroups
+}
+
+/// Returns the groups `group` stands for in any of the directories, which are its subgroups, and
+/// theirs, if it only holds subgroups or the group itself otherwise, eg: `editors/nvim` and
+/// `editors/emacs` for `editors`. Special groups are never split into subgroups
+pub fn expand_group(group: &str, dtypes: &[DotfileType]) -> Vec<String> {
+    let Ok(dotfiles_dir) = get_dotfiles_path(&mut String::new()) else {
+        return vec![group.to_string()];
+    };
+    if !recursive_groups() || is_special_group(group) {
+        return vec![group.to_string()];
+    }
+
+    fn push_subgroups(group_dir: &path::Path, group: &str, subgroups: &mut Vec<String>) {
+        if !is_parent_group(group_dir) {
+            subgroups.push(group.to_string());
+            return;
+        }
+
+        for entry in fs::read_dir(group_dir).into_iter().flatten().flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            push_subgroups(&entry.path(), &format!("{group}/{name}"), subgroups);
+        }
+    }
+
+    let mut subgroups = Vec::new();
+    for dtype in dtypes {
+        let group_dir = dtype.dir_in(&dotfiles_dir).join(group);
+        if is_parent_group(&group_dir) {
+            push_subgroups(&group_dir, group, &mut subgroups);
+        }
+    }
+
+    if subgroups.is_empty() {
+        return vec![group.to_string()];
+    }
+
+    subgroups.sort();
+    subgroups.dedup();
+    subgroups
+}
+
 /// Returns all groups in the slice that don't have a corresponding directory in dotfiles/{Configs,Hooks,Secrets}
-pub fn check_invalid_groups(dtype: DotfileType, groups: &[String], output: &mut String) -> Option<Vec<String>> {
+pub fn check_invalid_groups(
+    dtype: DotfileType,
+    groups: &[String],
+    output: &mut String,
+) -> Option<Vec<String>> {
     let mut invalid_groups = Vec::new();
     for group in groups {
         if !dotfiles::dotfile_contains(dtype, group, output) && group != "*" {
@@ -257,12 +1203,48 @@ pub fn check_invalid_groups(dtype: DotfileType, groups: &[String], output: &mut
     Some(invalid_groups)
 }
 
+/// Serializes tests that create files inside of the test dotfiles directory
+#[cfg(test)]
+pub(crate) fn test_lock() -> std::sync::MutexGuard<'static, ()> {
+    static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::dotfiles::{get_dotfiles_path, Dotfile};
+    use crate::dotfiles::{get_dotfiles_path, Dotfile, ReturnCode};
+
+    #[test]
+    fn specific_groups_override_base_groups() {
+        use super::{base_group_name, is_related_group, overrides_group, target_precedence};
+
+        assert_eq!(base_group_name("alacritty_linux"), "alacritty");
+        assert_eq!(base_group_name("alacritty"), "alacritty");
+        assert!(is_related_group("vim_linux", "vim"));
+        assert!(is_related_group("vim", "vim"));
+        assert!(!is_related_group("vimrc_linux", "vim"));
+        assert!(!is_related_group("vimrc", "vim"));
+        assert!(target_precedence("alacritty") < target_precedence("alacritty_unix"));
+        assert!(target_precedence("alacritty_unix") < target_precedence("alacritty_macos"));
+
+        let os_group = format!("alacritty_{}", std::env::consts::OS);
+        assert!(overrides_group(&os_group, "alacritty"));
+        assert!(!overrides_group("alacritty", &os_group));
+        assert!(!overrides_group(&os_group, &os_group));
+        assert!(!overrides_group(&os_group, "kitty"));
+        assert_eq!(
+            overrides_group("alacritty_unix", "alacritty"),
+            cfg!(target_family = "unix")
+        );
+        assert_eq!(
+            overrides_group("alacritty_windows", "alacritty"),
+            cfg!(target_family = "windows")
+        );
+    }
 
     #[test]
     fn dotfile_to_target_path() {
+        let _lock = super::test_lock();
         let group = get_dotfiles_path(&mut "".into())
             .unwrap()
             .join("Configs")
@@ -270,20 +1252,592 @@ mod tests {
             .join(".zshrc");
 
         assert_eq!(
-            Dotfile::try_from(group).unwrap().to_target_path(&mut "".into()),
+            Dotfile::try_from(group)
+                .unwrap()
+                .to_target_path(&mut "".into())
+                .unwrap(),
             dirs::home_dir().unwrap().join(".zshrc")
         );
     }
 
+    #[test]
+    fn target_path_outside_of_configs() {
+        let _lock = super::test_lock();
+        let group_path = std::env::temp_dir().join("zsh");
+        let dotfile = Dotfile {
+            path: group_path.join(".zshrc"),
+            group_path,
+            group_name: "zsh".into(),
+        };
+
+        let mut output = String::new();
+        assert_eq!(
+            dotfile.to_target_path(&mut output),
+            Err(ReturnCode::NoSuchFileOrDir)
+        );
+        assert!(output.contains("does not belong to the dotfiles directory"));
+    }
+
+    fn assert_same_paths(same: &[(&str, &str)], different: &[(&str, &str)]) {
+        for (a, b) in same {
+            assert!(super::same_path(a.as_ref(), b.as_ref()), "{a} != {b}");
+        }
+        for (a, b) in different {
+            assert!(!super::same_path(a.as_ref(), b.as_ref()), "{a} == {b}");
+        }
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn same_path_unix() {
+        assert_same_paths(
+            &[
+                ("/home/Configs/zsh/.zshrc", "/home/Configs/zsh/.zshrc"),
+                ("/home/Configs/zsh/.zshrc", "/home//Configs/./zsh/.zshrc"),
+                ("/home/.config/", "/home/.config"),
+            ],
+            &[
+                ("/home/Configs/zsh/.zshrc", "/home/Configs/zsh"),
+                ("/home/Configs/zsh", "/home/Configs/zsh_linux"),
+                // backslashes are part of file names on unix
+                ("/home/Configs/zsh", "/home\\Configs\\zsh"),
+            ],
+        );
+    }
+
+    #[cfg(target_family = "windows")]
+    #[test]
+    fn same_path_windows() {
+        assert_same_paths(
+            &[
+                ("C:\\Users\\Configs\\zsh", "C:/Users/Configs/zsh"),
+                ("C:\\Users\\Configs\\zsh", "\\\\?\\C:\\Users\\Configs\\zsh"),
+                ("c:\\Users\\.config\\", "C:\\Users\\.\\.config"),
+            ],
+            &[
+                ("C:\\Users\\Configs\\zsh", "D:\\Users\\Configs\\zsh"),
+                ("C:\\Users\\Configs\\zsh", "C:\\Users\\Configs\\zsh_windows"),
+            ],
+        );
+    }
+
     #[test]
     fn dotfile_targets_root() {
-        let dotfiles_dir = super::get_dotfiles_path(&mut "".into()).unwrap().join("Configs");
+        let _lock = super::test_lock();
+        let dotfiles_dir = super::get_dotfiles_path(&mut "".into())
+            .unwrap()
+            .join("Configs");
 
         let root_dotfile = super::Dotfile::try_from(dotfiles_dir.join("Root")).unwrap();
-        assert!(root_dotfile.targets_root(&mut "".into()));
+        assert!(root_dotfile.targets_root(&mut "".into()).unwrap());
 
         let nonroot_dotfile = super::Dotfile::try_from(dotfiles_dir.join("Zsh")).unwrap();
-        assert!(!nonroot_dotfile.targets_root(&mut "".into()));
+        assert!(!nonroot_dotfile.targets_root(&mut "".into()).unwrap());
+    }
+
+    #[test]
+    fn root_group_target_is_absolute() {
+        let _lock = super::test_lock();
+        let root_file = get_dotfiles_path(&mut "".into())
+            .unwrap()
+            .join("Configs")
+            .join("Root")
+            .join("etc")
+            .join("hosts");
+
+        let target = Dotfile::try_from(root_file)
+            .unwrap()
+            .to_target_path(&mut "".into())
+            .unwrap();
+        assert!(target.is_absolute());
+        assert_eq!(target, super::get_root_path().join("etc").join("hosts"));
+    }
+
+    #[test]
+    fn nested_subgroups() {
+        use super::{expand_group, list_groups, DotfileType};
+        use std::fs;
+
+        let _lock = super::test_lock();
+        crate::fileops::init_cmd();
+        let dotfiles_dir = get_dotfiles_path(&mut "".into()).unwrap();
+        let editors_dir = dotfiles_dir.join("Configs").join("editors");
+        let nvim_dir = editors_dir.join("nvim");
+        fs::create_dir_all(nvim_dir.join(".config")).unwrap();
+        fs::create_dir_all(editors_dir.join("emacs")).unwrap();
+        fs::write(editors_dir.join("emacs").join(".emacs"), "").unwrap();
+        let init_file = nvim_dir.join(".config").join("init.lua");
+        fs::write(&init_file, "").unwrap();
+        // special groups mirror where they're deployed to instead of holding subgroups
+        let root_group = format!("Root_{}", std::env::consts::OS);
+        let root_dir = dotfiles_dir.join("Configs").join(&root_group);
+        fs::create_dir_all(root_dir.join("etc")).unwrap();
+        fs::create_dir_all(root_dir.join("usr")).unwrap();
+        let root_file = root_dir.join("etc").join("tuckr.conf");
+        fs::write(&root_file, "").unwrap();
+
+        // nested directories are only subgroups when enabled
+        let dotfile = Dotfile::try_from(init_file.clone()).unwrap();
+        let flat_group = dotfile.group_name;
+        let flat_expansion = expand_group("editors", &[DotfileType::Configs]);
+
+        fs::write(
+            dotfiles_dir.join(crate::config::CONFIG_FILE),
+            "recursive_groups = true\n",
+        )
+        .unwrap();
+        let dotfile = Dotfile::try_from(init_file).unwrap();
+        let target = dotfile.to_target_path(&mut "".into()).unwrap();
+        let expansion = expand_group("editors", &[DotfileType::Configs]);
+        let groups = list_groups(DotfileType::Configs).unwrap();
+        let root_dotfile = Dotfile::try_from(root_file).unwrap();
+        let root_expansion = expand_group(&root_group, &[DotfileType::Configs]);
+        fs::remove_dir_all(dotfiles_dir).unwrap();
+
+        assert_eq!(flat_group, "editors");
+        assert_eq!(flat_expansion, ["editors"]);
+        assert_eq!(dotfile.group_name, "editors/nvim");
+        assert_eq!(dotfile.group_path, nvim_dir);
+        assert_eq!(
+            target,
+            dirs::home_dir().unwrap().join(".config").join("init.lua")
+        );
+        assert_eq!(expansion, ["editors/emacs", "editors/nvim"]);
+        assert_eq!(groups, [&root_group, "editors/emacs", "editors/nvim"]);
+        assert_eq!(root_dotfile.group_name, root_group);
+        assert_eq!(root_dotfile.group_path, root_dir);
+        assert_eq!(root_expansion, [root_group]);
+    }
+
+    #[test]
+    fn file_target_suffixes() {
+        let _lock = super::test_lock();
+        use super::overrides_file;
+
+        let group_dir = get_dotfiles_path(&mut "".into())
+            .unwrap()
+            .join("Configs")
+            .join("shared");
+        let dotfile = |file: &str| Dotfile::try_from(group_dir.join(file)).unwrap();
+        let current_os = format!("config_{}", std::env::consts::OS);
+
+        assert!(dotfile(&current_os).is_valid_target());
+        assert!(dotfile(&format!("dir_{}/config", std::env::consts::OS)).is_valid_target());
+        assert!(!dotfile("config_none").is_valid_target());
+        assert!(!dotfile("dir_none/config").is_valid_target());
+
+        // suffixes are stripped from every component of the target
+        let home_dir = dirs::home_dir().unwrap();
+        assert_eq!(
+            dotfile(&current_os).to_target_path(&mut "".into()).unwrap(),
+            home_dir.join("config")
+        );
+        assert_eq!(
+            dotfile("dir_unix/config_unix")
+                .to_target_path(&mut "".into())
+                .unwrap(),
+            home_dir.join("dir").join("config")
+        );
+
+        assert!(overrides_file(&dotfile(&current_os), &dotfile("config")));
+        assert!(!overrides_file(&dotfile("config"), &dotfile(&current_os)));
+        assert!(!overrides_file(&dotfile("config_none"), &dotfile("config")));
+    }
+
+    #[test]
+    fn xdg_groups_target_xdg_dirs() {
+        let _lock = super::test_lock();
+        let configs_dir = get_dotfiles_path(&mut "".into()).unwrap().join("Configs");
+        let target = |group: &str| {
+            Dotfile::try_from(configs_dir.join(group).join("tuckr").join("file"))
+                .unwrap()
+                .to_target_path(&mut "".into())
+                .unwrap()
+        };
+        let expected = |dir: Option<std::path::PathBuf>| dir.unwrap().join("tuckr").join("file");
+
+        assert_eq!(target("XdgData"), expected(dirs::data_dir()));
+        assert_eq!(
+            target("XdgState"),
+            expected(dirs::state_dir().or_else(dirs::data_local_dir))
+        );
+        assert_eq!(target("XdgCache"), expected(dirs::cache_dir()));
+
+        // platform specific groups are deployed along with their base group
+        let data_group = format!("XdgData_{}", std::env::consts::OS);
+        assert_eq!(target(&data_group), expected(dirs::data_dir()));
+
+        // only the exact names are special
+        assert_eq!(
+            target("XdgDataFiles"),
+            expected(dirs::home_dir()).parent().unwrap().join("file")
+        );
+    }
+
+    #[cfg(target_family = "windows")]
+    #[test]
+    fn root_group_targets_system_drive() {
+        let system_drive = std::env::var("SystemDrive").unwrap_or_else(|_| "C:".into());
+        assert_eq!(
+            super::get_root_path(),
+            std::path::PathBuf::from(format!("{system_drive}\\"))
+        );
+    }
+
+    #[test]
+    fn dotfile_target_override() {
+        let _lock = super::test_lock();
+        let group_dir = get_dotfiles_path(&mut "".into())
+            .unwrap()
+            .join("Configs")
+            .join("systemd");
+        std::fs::create_dir_all(&group_dir).unwrap();
+
+        let dotfile = Dotfile::try_from(group_dir.join("tuckr.service")).unwrap();
+        let target_file = group_dir.join(super::TARGET_OVERRIDE_FILE);
+
+        std::fs::write(&target_file, "/etc/systemd/system\n").unwrap();
+        assert_eq!(
+            dotfile.to_target_path(&mut "".into()).unwrap(),
+            std::path::PathBuf::from("/etc/systemd/system/tuckr.service")
+        );
+
+        std::fs::write(&target_file, "~/.local/share/systemd").unwrap();
+        assert_eq!(
+            dotfile.to_target_path(&mut "".into()).unwrap(),
+            dirs::home_dir()
+                .unwrap()
+                .join(".local/share/systemd/tuckr.service")
+        );
+
+        // relative paths are rejected in favor of the default target
+        let mut output = String::new();
+        std::fs::write(&target_file, "relative/path").unwrap();
+        assert_eq!(
+            dotfile.to_target_path(&mut output).unwrap(),
+            dirs::home_dir().unwrap().join("tuckr.service")
+        );
+        assert!(!output.is_empty());
+
+        let override_dotfile = Dotfile::try_from(target_file).unwrap();
+        assert!(override_dotfile.is_group_metadata());
+        assert!(!dotfile.is_group_metadata());
+
+        std::fs::remove_dir_all(group_dir).unwrap();
+    }
+
+    #[test]
+    fn dotfile_target_prefix() {
+        let _lock = super::test_lock();
+        let group_dir = get_dotfiles_path(&mut "".into())
+            .unwrap()
+            .join("Configs")
+            .join("myapp");
+        std::fs::create_dir_all(&group_dir).unwrap();
+        let prefix_file = group_dir.join(super::PREFIX_FILE);
+        std::fs::write(&prefix_file, ".config/myapp\n").unwrap();
+
+        let dotfile = Dotfile::try_from(group_dir.join("config.toml")).unwrap();
+        let home_dir = dirs::home_dir().unwrap();
+        assert_eq!(
+            dotfile.to_target_path(&mut "".into()).unwrap(),
+            home_dir.join(".config/myapp/config.toml")
+        );
+
+        // prefixes can't leave the target directory
+        for prefix in ["/etc/myapp", "../myapp"] {
+            let mut output = String::new();
+            std::fs::write(&prefix_file, prefix).unwrap();
+            assert_eq!(
+                dotfile.to_target_path(&mut output).unwrap(),
+                home_dir.join("config.toml")
+            );
+            assert!(!output.is_empty());
+        }
+
+        std::fs::remove_dir_all(group_dir).unwrap();
+    }
+
+    #[test]
+    fn missing_env_var_targets() {
+        let _lock = super::test_lock();
+        let group_dir = get_dotfiles_path(&mut "".into())
+            .unwrap()
+            .join("Configs")
+            .join("unset_var");
+        let dotfile = Dotfile::try_from(group_dir.join("${TUCKR_TEST_UNSET_VAR}/file")).unwrap();
+
+        let mut output = String::new();
+        assert!(dotfile.to_target_path(&mut output).is_err());
+        assert!(output.contains("TUCKR_TEST_UNSET_VAR"));
+
+        // only shown, so it's left unexpanded
+        let mut output = String::new();
+        assert_eq!(
+            dotfile.display_target_path(&mut output).unwrap(),
+            dirs::home_dir()
+                .unwrap()
+                .join("${TUCKR_TEST_UNSET_VAR}/file")
+        );
+        assert!(output.contains("TUCKR_TEST_UNSET_VAR"));
+    }
+
+    #[test]
+    fn dotfile_rename_manifest() {
+        let _lock = super::test_lock();
+        let group_dir = get_dotfiles_path(&mut "".into())
+            .unwrap()
+            .join("Configs")
+            .join("bash");
+        std::fs::create_dir_all(&group_dir).unwrap();
+        std::fs::write(
+            group_dir.join(super::RENAME_FILE),
+            "# deploy without the leading dot in the repo\nbashrc = .bashrc\nconfig = .config/bash\n",
+        )
+        .unwrap();
+
+        let home_dir = dirs::home_dir().unwrap();
+        let target_of = |file: &str| {
+            Dotfile::try_from(group_dir.join(file))
+                .unwrap()
+                .to_target_path(&mut "".into())
+                .unwrap()
+        };
+
+        assert_eq!(target_of("bashrc"), home_dir.join(".bashrc"));
+        assert_eq!(
+            target_of("config/aliases"),
+            home_dir.join(".config/bash/aliases")
+        );
+        assert_eq!(target_of("bash_profile"), home_dir.join("bash_profile"));
+        // only whole path components are renamed
+        assert_eq!(target_of("bashrc.d"), home_dir.join("bashrc.d"));
+
+        std::fs::remove_dir_all(group_dir).unwrap();
+    }
+
+    #[test]
+    fn nested_configs_subdir() {
+        use super::{parse_configs_subdir, DotfileType};
+        use std::path::{Path, PathBuf};
+
+        let dotfiles_dir = get_dotfiles_path(&mut "".into()).unwrap();
+        assert_eq!(
+            DotfileType::Configs.dir_in_subdir(&dotfiles_dir, Path::new("nested/tuckr")),
+            dotfiles_dir.join("nested/tuckr/Configs")
+        );
+        assert_eq!(
+            DotfileType::Hooks.dir_in_subdir(&dotfiles_dir, Path::new("")),
+            dotfiles_dir.join("Hooks")
+        );
+
+        assert_eq!(
+            parse_configs_subdir(Some("nested/tuckr".into())),
+            PathBuf::from("nested/tuckr")
+        );
+        assert_eq!(parse_configs_subdir(None), PathBuf::new());
+        // the subdirectory can't leave the dotfiles directory
+        assert_eq!(
+            parse_configs_subdir(Some("../tuckr".into())),
+            PathBuf::new()
+        );
+        assert_eq!(parse_configs_subdir(Some("/tuckr".into())), PathBuf::new());
+    }
+
+    #[test]
+    fn find_dotfiles_dir_upwards() {
+        let dir = std::env::temp_dir().join(format!("tuckr-find-{}", std::process::id()));
+        let repo = dir.join("repo");
+        let nested = repo.join("Configs").join("zsh").join(".config");
+        std::fs::create_dir_all(&nested).unwrap();
+
+        // a lone Configs directory could be anything
+        assert_eq!(super::find_dotfiles_dir(&nested), None);
+
+        std::fs::write(repo.join(super::CONFIG_FILE), "").unwrap();
+        assert_eq!(super::find_dotfiles_dir(&nested), Some(repo.clone()));
+        assert_eq!(super::find_dotfiles_dir(&repo), Some(repo.clone()));
+        assert_eq!(super::find_dotfiles_dir(&dir), None);
+
+        std::fs::remove_file(repo.join(super::CONFIG_FILE)).unwrap();
+        std::fs::create_dir_all(repo.join("Hooks")).unwrap();
+        assert_eq!(super::find_dotfiles_dir(&nested), None);
+        std::fs::create_dir_all(repo.join("Secrets")).unwrap();
+        assert_eq!(super::find_dotfiles_dir(&nested), Some(repo));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn list_groups_only_returns_dirs() {
+        let _lock = super::test_lock();
+        let hooks_dir = get_dotfiles_path(&mut "".into()).unwrap().join("Hooks");
+        std::fs::create_dir_all(hooks_dir.join("zsh")).unwrap();
+        std::fs::create_dir_all(hooks_dir.join("alacritty")).unwrap();
+        std::fs::write(hooks_dir.join("README.md"), "not a group").unwrap();
+
+        assert_eq!(
+            super::list_groups(super::DotfileType::Hooks).unwrap(),
+            ["alacritty", "zsh"]
+        );
+
+        std::fs::remove_dir_all(hooks_dir).unwrap();
+    }
+
+    #[test]
+    fn dotfiles_lock_is_exclusive() {
+        let _lock = super::test_lock();
+        std::fs::create_dir_all(get_dotfiles_path(&mut "".into()).unwrap()).unwrap();
+
+        let dotfiles_lock = super::lock_dotfiles(&mut "".into()).unwrap();
+        let mut output = String::new();
+        assert!(super::lock_dotfiles(&mut output).is_err());
+        assert!(!output.is_empty());
+
+        drop(dotfiles_lock);
+        assert!(super::lock_dotfiles(&mut "".into()).is_ok());
+
+        // it's kept out of the dotfiles repo along with the rest of the state
+        let dotfiles_dir = get_dotfiles_path(&mut "".into()).unwrap();
+        assert!(dotfiles_dir.join(".tuckr").join("lock").exists());
+        assert!(!dotfiles_dir.join(".tuckr.lock").exists());
+    }
+
+    #[test]
+    fn return_code_from_io_error() {
+        use super::ReturnCode;
+        use std::io::{Error, ErrorKind};
+
+        assert_eq!(
+            ReturnCode::from(Error::from(ErrorKind::NotFound)),
+            ReturnCode::NoSuchFileOrDir
+        );
+        assert_eq!(
+            ReturnCode::from(Error::from(ErrorKind::PermissionDenied)),
+            ReturnCode::FileOperationFailed
+        );
+        assert_eq!(
+            ReturnCode::DecryptionFailed.to_string(),
+            "Decryption Failed"
+        );
+    }
+
+    #[test]
+    fn expand_dot_prefixed_paths() {
+        use super::expand_dot_prefix;
+        use std::path::Path;
+
+        assert_eq!(
+            expand_dot_prefix(Path::new("dot-config/nvim/init.lua")),
+            Path::new(".config/nvim/init.lua")
+        );
+        assert_eq!(
+            expand_dot_prefix(Path::new("dot-zshrc")),
+            Path::new(".zshrc")
+        );
+        // only leading prefixes are replaced
+        assert_eq!(
+            expand_dot_prefix(Path::new("my-dot-file")),
+            Path::new("my-dot-file")
+        );
+        assert_eq!(expand_dot_prefix(Path::new(".zshrc")), Path::new(".zshrc"));
+    }
+
+    #[test]
+    fn collapse_hidden_paths() {
+        use super::{collapse_dot_prefix, expand_dot_prefix};
+        use std::path::Path;
+
+        let hidden = Path::new(".config/nvim/.luarc.json");
+        let collapsed = collapse_dot_prefix(hidden);
+        assert_eq!(collapsed, Path::new("dot-config/nvim/dot-luarc.json"));
+        assert_eq!(expand_dot_prefix(&collapsed), hidden);
+
+        assert_eq!(collapse_dot_prefix(Path::new("zshrc")), Path::new("zshrc"));
+    }
+
+    #[test]
+    fn misspelled_target_suffixes() {
+        use super::{misspelled_target_suffix, warn_misspelled_targets};
+
+        assert_eq!(misspelled_target_suffix("vim_linx"), Some("_linux"));
+        assert_eq!(misspelled_target_suffix("vim_widows"), Some("_windows"));
+        assert_eq!(misspelled_target_suffix("vim_Linux"), Some("_linux"));
+        assert_eq!(misspelled_target_suffix("vim_linux"), None);
+        assert_eq!(misspelled_target_suffix("vim"), None);
+        // suffixes that aren't close to any target are regular group names
+        assert_eq!(misspelled_target_suffix("nvim_config"), None);
+        assert_eq!(misspelled_target_suffix("group_one"), None);
+
+        let mut output = String::new();
+        warn_misspelled_targets(&["vim_linx".into(), "zsh_unix".into()], &mut output);
+        assert!(output.contains("vim_linx"));
+        assert!(output.contains("`_linux`"));
+        assert!(!output.contains("zsh_unix"));
+    }
+
+    #[test]
+    fn expand_env_vars_in_paths() {
+        use std::path::Path;
+
+        let expand = |path: &str| {
+            super::expand_vars_with(Path::new(path), |var| match var {
+                "TUCKR_TEST_APP" => Some("app".into()),
+                "TUCKR_TEST_DATA" => Some("/tmp/data".into()),
+                _ => None,
+            })
+        };
+        assert_eq!(
+            expand("/home/user/.config/${TUCKR_TEST_APP}/config").unwrap(),
+            Path::new("/home/user/.config/app/config")
+        );
+        assert_eq!(
+            expand("/home/${TUCKR_TEST_APP}-${TUCKR_TEST_APP}").unwrap(),
+            Path::new("/home/app-app")
+        );
+        // absolute variables replace what comes before them
+        assert_eq!(
+            expand("/home/user/${TUCKR_TEST_DATA}/fonts").unwrap(),
+            Path::new("/tmp/data/fonts")
+        );
+
+        assert_eq!(
+            expand("/home/user/${TUCKR_TEST_UNSET}/file"),
+            Err("TUCKR_TEST_UNSET".to_string())
+        );
+        // unterminated variables are left as is
+        assert_eq!(
+            expand("/home/user/${oops").unwrap(),
+            Path::new("/home/user/${oops")
+        );
+    }
+
+    #[test]
+    fn errors_carry_offending_path() {
+        let _lock = super::test_lock();
+        use super::{Error, ReturnCode};
+        use std::path::PathBuf;
+
+        let outside = PathBuf::from("/definitely/not/dotfiles/.zshrc");
+        let err = Dotfile::try_from(outside.clone()).unwrap_err();
+        assert!(matches!(&err, Error::NotADotfile { path } if *path == outside));
+        assert!(err.to_string().contains("/definitely/not/dotfiles/.zshrc"));
+        assert_eq!(ReturnCode::from(err), ReturnCode::NoSuchFileOrDir);
+
+        let err = Error::Io {
+            operation: "remove",
+            path: PathBuf::from("/tmp/file"),
+            source: std::io::ErrorKind::PermissionDenied.into(),
+        };
+        assert!(err.to_string().starts_with("failed to remove /tmp/file"));
+        assert_eq!(ReturnCode::from(err), ReturnCode::FileOperationFailed);
+
+        let err = super::get_target_basepath(&outside).unwrap_err();
+        assert!(matches!(&err, Error::OutsideHome { path } if *path == outside));
+        assert_eq!(
+            super::get_target_basepath(&dirs::home_dir().unwrap().join(".zshrc")).unwrap(),
+            PathBuf::from(".zshrc")
+        );
     }
 
     #[test]
@@ -2,35 +2,78 @@
 //!
 //! Contains functions to create the base directories and to convert users from stow to tuckr
 
+use crate::config::Config;
 use crate::dotfiles::{self, ReturnCode};
-use owo_colors::OwoColorize;
-use std::io::{self, Write};
+use crate::hooks;
+use crate::symlinks;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::io;
 use std::path::{Path, PathBuf};
-use std::process::ExitCode;
-use std::{fs, path};
+use std::process::{Command, ExitCode};
+use std::time::{Duration, SystemTime};
+use std::{env, fs, path};
 use tabled::object::Segment;
 use tabled::{Alignment, Modify, Table, Tabled};
+use walkdir::WalkDir;
 
-pub fn dir_map<F>(dir_path: impl AsRef<Path>, mut func: F)
+/// Calls `func` on every file and directory inside of `dir_path`, hidden files included
+///
+/// Entries are visited depth first in name order, a directory always comes right before its
+/// contents
+pub fn dir_map<F>(dir_path: impl AsRef<Path>, func: F)
 where
     F: FnMut(&Path),
+{
+    dir_map_depth(dir_path, None, true, func)
+}
+
+/// Same as `dir_map` but doesn't go deeper than `max_depth` levels into `dir_path`
+///
+/// The entries directly inside of `dir_path` are at depth 1
+///
+/// include_hidden: whether entries starting with a `.` are visited, the contents of hidden
+/// directories are skipped along with them
+pub fn dir_map_depth<F>(
+    dir_path: impl AsRef<Path>,
+    max_depth: Option<usize>,
+    include_hidden: bool,
+    mut func: F,
+) where
+    F: FnMut(&Path),
 {
     let dir_path = dir_path.as_ref();
-    let dir = match fs::read_dir(dir_path) {
-        Ok(f) => f,
-        Err(_) => panic!("{} does not exist", dir_path.to_str().unwrap()),
-    };
+    let read_sorted_dir = |dir: &Path, depth: usize| -> Vec<(path::PathBuf, usize)> {
+        let dir = match fs::read_dir(dir) {
+            Ok(f) => f,
+            Err(_) => panic!("{} does not exist", dir.to_str().unwrap()),
+        };
+
+        let mut entries: Vec<_> = dir
+            .map(|f| f.unwrap().path())
+            .filter(|f| {
+                include_hidden || !f.file_name().unwrap().to_string_lossy().starts_with('.')
+            })
+            .map(|f| (f, depth))
+            .collect();
 
-    let mut queue: Vec<path::PathBuf> = dir.map(|f| f.unwrap().path()).collect();
+        // reversed so that popping from the stack visits entries in name order
+        entries.sort_by(|(a, _), (b, _)| b.cmp(a));
+        entries
+    };
 
-    while let Some(curr_file) = queue.pop() {
+    let mut stack = read_sorted_dir(dir_path, 1);
+    while let Some((curr_file, depth)) = stack.pop() {
         func(&curr_file);
 
+        if max_depth.is_some_and(|max_depth| depth >= max_depth) {
+            continue;
+        }
+
         if curr_file.is_dir() {
-            for dir in fs::read_dir(curr_file).unwrap() {
-                let dir = dir.unwrap();
-                queue.push(dir.path());
-            }
+            stack.extend(read_sorted_dir(&curr_file, depth + 1));
         }
     }
 }
@@ -47,13 +90,16 @@ pub fn from_stow_cmd() -> (String, ExitCode) {
     };
 
     // --- initializing required directory ---
-    let configs_path = dotfiles_dir.join("Configs");
+    let configs_path = dotfiles::DotfileType::Configs.dir_in(&dotfiles_dir);
     fs::create_dir_all(&configs_path).expect("Could not create required directory.");
 
     // --- Moving dotfiles to Configs/ ---
     let cwd = match fs::read_dir(&dotfiles_dir) {
         Ok(dir) => dir,
-        Err(e) => { output.push_str("Could not open current directory"); return (output, ExitCode::FAILURE);}
+        Err(_) => {
+            output.push_str("Could not open current directory\n");
+            return (output, ExitCode::FAILURE);
+        }
     };
 
     for file in cwd {
@@ -69,6 +115,11 @@ pub fn from_stow_cmd() -> (String, ExitCode) {
 
         let path = configs_path.join(&dirname);
 
+        // the directory Configs is nested in, see `dotfiles::configs_subdir`
+        if configs_path.starts_with(dir.path()) {
+            continue;
+        }
+
         if !dirname.ends_with("Configs")
             && !dirname.ends_with("Hooks")
             && !dirname.ends_with("Secrets")
@@ -100,63 +151,304 @@ pub fn init_cmd() -> (String, ExitCode) {
     };
 
     create_dirs!(
-        dotfiles_dir.join("Configs"),
-        dotfiles_dir.join("Hooks"),
-        dotfiles_dir.join("Secrets")
+        dotfiles::DotfileType::Configs.dir_in(&dotfiles_dir),
+        dotfiles::DotfileType::Hooks.dir_in(&dotfiles_dir),
+        dotfiles::DotfileType::Secrets.dir_in(&dotfiles_dir)
     );
 
-    output.push_str( &format!(
-            "A dotfiles directory has been created on `{}`.",
-            dotfiles_dir.to_str().unwrap()
-        )
-    );
+    output.push_str(&format!(
+        "A dotfiles directory has been created on `{}`.\n",
+        dotfiles_dir.to_str().unwrap()
+    ));
 
     (output, ExitCode::SUCCESS)
 }
 
-pub fn push_cmd(group: String, files: &[String]) -> (String, ExitCode) {
+/// Clones a dotfiles repo into the dotfiles directory
+///
+/// init: creates the Configs, Hooks and Secrets directories if the repo doesn't have them
+pub fn clone_cmd(url: &str, init: bool) -> (String, ExitCode) {
     let mut output = "".to_string();
-    let dotfiles_dir = match dotfiles::get_dotfiles_path(&mut output) {
-        Ok(dir) => dir.join("Configs").join(group),
+    let dotfiles_dir = match dotfiles::default_dotfiles_path() {
+        Ok(dir) => dir,
+        Err(err) => {
+            output.push_str(&format!("{err}\n"));
+            return (output, ReturnCode::from(err).into());
+        }
+    };
+
+    if dotfiles_dir.exists() {
+        output.push_str(&format!(
+            "`{}` already exists, remove it before cloning.\n",
+            dotfiles_dir.display()
+        ));
+        return (output, ExitCode::FAILURE);
+    }
+
+    let status = Command::new("git")
+        .arg("clone")
+        .arg(url)
+        .arg(&dotfiles_dir)
+        .status();
+    match status {
+        Ok(status) if status.success() => (),
+        Ok(_) => {
+            output.push_str(&format!("Failed to clone `{url}`.\n"));
+            return (output, ExitCode::FAILURE);
+        }
+        Err(e) => {
+            output.push_str(&format!("Couldn't run git: {e}\n"));
+            return (output, ExitCode::FAILURE);
+        }
+    }
+
+    if init {
+        for dtype in [
+            dotfiles::DotfileType::Configs,
+            dotfiles::DotfileType::Hooks,
+            dotfiles::DotfileType::Secrets,
+        ] {
+            if let Err(e) = fs::create_dir_all(dtype.dir_in(&dotfiles_dir)) {
+                output.push_str(&format!("{e}\n"));
+                return (output, ReturnCode::from(e).into());
+            }
+        }
+    }
+
+    output.push_str(&format!(
+        "Your dotfiles have been cloned to `{}`.\n\n\
+        Next steps:\n\
+        \ttuckr status   # check for conflicts with your existing files\n\
+        \ttuckr add \\*   # symlink all of your dotfiles\n\
+        \ttuckr set \\*   # symlink all of your dotfiles and run their hooks\n",
+        dotfiles_dir.display()
+    ));
+
+    (output, ExitCode::SUCCESS)
+}
+
+/// Returns the ignore rules of the dotfiles repo or None if it's not a git repo
+fn dotfiles_gitignore(dotfiles_dir: &Path) -> Option<Gitignore> {
+    if !dotfiles_dir.join(".git").exists() {
+        return None;
+    }
+
+    let mut builder = GitignoreBuilder::new(dotfiles_dir);
+    // missing ignore files are fine, the repo just doesn't ignore anything through them
+    builder.add(dotfiles_dir.join(".gitignore"));
+    builder.add(dotfiles_dir.join(".git").join("info").join("exclude"));
+    builder.build().ok()
+}
+
+/// Parses durations such as `30s`, `15m`, `2h`, `3d` or `1w`
+pub fn parse_duration(duration: &str) -> Result<Duration, String> {
+    let duration = duration.trim();
+    let unit_start = duration
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(|| format!("`{duration}` is missing a unit (s, m, h, d or w)"))?;
+    let (amount, unit) = duration.split_at(unit_start);
+
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| format!("`{duration}` doesn't start with a number"))?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 60 * 60 * 24,
+        "w" => 60 * 60 * 24 * 7,
+        _ => return Err(format!("`{unit}` is not a valid unit, use s, m, h, d or w")),
+    };
+
+    amount
+        .checked_mul(seconds)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("`{duration}` is too long"))
+}
+
+/// Copies files into a group
+///
+/// since: only copies files modified within this duration
+///
+/// link: replaces the original files with symlinks to their copies
+///
+/// depth: how many levels of a pushed directory are copied, everything is copied if None
+///
+/// parents: pushes the whole directory the files are in up to this ancestor of theirs, which has
+/// to be inside of $HOME, instead of only the files
+pub fn push_cmd(
+    group: String,
+    files: &[String],
+    since: Option<Duration>,
+    link: bool,
+    depth: Option<usize>,
+    parents: Option<&Path>,
+) -> (String, ExitCode) {
+    let mut output = "".to_string();
+    let (dotfiles_dir, gitignore) = match dotfiles::get_dotfiles_path(&mut output) {
+        Ok(dir) => (
+            dotfiles::DotfileType::Configs.dir_in(&dir).join(group),
+            dotfiles_gitignore(&dir),
+        ),
         Err(e) => {
             output.push_str(&e.to_string());
             return (output, ReturnCode::CouldntFindDotfiles.into());
         }
     };
 
+    // files that would be ignored by git once inside of the repo are not copied
+    let is_ignored = |path: &Path, is_dir: bool| {
+        gitignore.as_ref().is_some_and(|gitignore| {
+            gitignore
+                .matched_path_or_any_parents(path, is_dir)
+                .is_ignore()
+        })
+    };
+
+    let dot_prefix = match Config::current(&mut output) {
+        Ok(config) => config.dot_prefix,
+        Err(e) => return (output, e),
+    };
+
+    let modified_since = since.and_then(|since| SystemTime::now().checked_sub(since));
+    let is_outdated = |path: &Path| {
+        modified_since.is_some_and(|modified_since| {
+            fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .is_ok_and(|modified| modified < modified_since)
+        })
+    };
+
+    let parents = parents.map(|parents| path::absolute(parents).unwrap());
+    if let Some(parents) = &parents {
+        let home_dir = dirs::home_dir().unwrap();
+        if !parents.starts_with(&home_dir) || *parents == home_dir {
+            output.push_str(&format!(
+                "{} must be a directory inside of {}.\n",
+                parents.display(),
+                home_dir.display()
+            ));
+            return (output, ReturnCode::NoSuchFileOrDir.into());
+        }
+    }
+
     let mut any_file_failed = false;
+    let mut files_to_push = Vec::new();
+    let mut parents_pushed = false;
     for file in files {
         let file = PathBuf::from(file);
         if !file.exists() {
-            output.push_str(file.to_str().unwrap_or(""));
-            output.push_str(" does not exist.");
+            output.push_str(&format!("{} does not exist.\n", file.display()));
             any_file_failed = true;
             continue;
         }
 
-        let file = path::absolute(file).unwrap();
-        let target_file = dotfiles_dir.join(dotfiles::get_target_basepath(&file));
-        let target_dir = target_file.parent().unwrap();
+        let mut file = path::absolute(file).unwrap();
+        if let Some(parents) = &parents {
+            if !file.starts_with(parents) {
+                output.push_str(&format!(
+                    "{} is not inside of {}.\n",
+                    file.display(),
+                    parents.display()
+                ));
+                any_file_failed = true;
+                continue;
+            }
+
+            // the ancestor is shared by every file so it's only pushed once
+            if parents_pushed {
+                continue;
+            }
+            parents_pushed = true;
+            file = parents.clone();
+        }
+
+        if file.is_file() {
+            files_to_push.push(file);
+        } else {
+            dir_map_depth(file, depth, true, |f| {
+                if !f.is_dir() {
+                    files_to_push.push(path::absolute(f).unwrap());
+                }
+            });
+        }
+    }
+
+    let mut ignored_files = 0;
+    let mut outdated_files = 0;
+    for file in files_to_push {
+        let mut basepath = match dotfiles::get_target_basepath(&file) {
+            Ok(basepath) => basepath,
+            Err(err) => {
+                output.push_str(&format!("Couldn't push {}: {err}\n", file.display()));
+                any_file_failed = true;
+                continue;
+            }
+        };
+        if dot_prefix {
+            basepath = dotfiles::collapse_dot_prefix(&basepath);
+        }
+
+        let target_file = dotfiles_dir.join(basepath);
+        if is_ignored(&target_file, false) {
+            ignored_files += 1;
+            continue;
+        }
+
+        if is_outdated(&file) {
+            outdated_files += 1;
+            continue;
+        }
+
+        // fs::copy also copies the permissions, on windows that's the readonly attribute
+        let copied = fs::create_dir_all(target_file.parent().unwrap())
+            .and_then(|_| write_atomically(&target_file, |temp| fs::copy(&file, temp).map(|_| ())));
+        if let Err(e) = copied {
+            output.push_str(&format!(
+                "Couldn't copy {} to {}: {e}\n",
+                file.display(),
+                target_file.display()
+            ));
+            any_file_failed = true;
+            continue;
+        }
 
-        if !target_file.exists() {
-            fs::create_dir_all(target_dir).unwrap();
+        if link {
+            if let Err(e) = fs::remove_file(&file) {
+                output.push_str(&format!("Couldn't replace {}: {e}\n", file.display()));
+                any_file_failed = true;
+                continue;
+            }
 
-            if cfg!(target_family = "unix") || file.is_file() {
-                fs::copy(file, target_file).unwrap();
-            } else {
-                dir_map(file, |f| {
-                    let file = path::absolute(f).unwrap();
-                    let target_file = dotfiles_dir.join(dotfiles::get_target_basepath(&file));
-                    fs::create_dir_all(target_file.parent().unwrap()).unwrap();
-                    fs::copy(file, target_file).unwrap();
-                });
+            // puts the original file back so that nothing is lost if it can't be symlinked
+            if let Err(err) = symlinks::symlink_file(target_file.clone(), &mut output) {
+                output.push_str(&format!("Couldn't symlink {}: {err}\n", file.display()));
+                let restored =
+                    write_atomically(&file, |temp| fs::copy(&target_file, temp).map(|_| ()));
+                if let Err(e) = restored {
+                    output.push_str(&format!(
+                        "Couldn't restore {} from {}: {e}\n",
+                        file.display(),
+                        target_file.display()
+                    ));
+                }
+                any_file_failed = true;
             }
-        } else {            
-            fs::create_dir_all(target_dir).unwrap();
-            fs::copy(file, target_file).unwrap();
         }
     }
 
+    if ignored_files > 0 {
+        output.push_str(&format!(
+            "Skipped {ignored_files} file(s) ignored by the dotfiles repo.\n"
+        ));
+    }
+
+    if outdated_files > 0 {
+        output.push_str(&format!(
+            "Skipped {outdated_files} file(s) that haven't been modified recently.\n"
+        ));
+    }
+
     if any_file_failed {
         (output, ReturnCode::NoSuchFileOrDir.into())
     } else {
@@ -164,10 +456,324 @@ pub fn push_cmd(group: String, files: &[String]) -> (String, ExitCode) {
     }
 }
 
-pub fn pop_cmd(groups: &[String]) -> (String, ExitCode) {
+/// Renames a group across Configs, Hooks and Secrets
+///
+/// If the group was deployed its symlinks are removed and recreated under the new name
+pub fn rename_cmd(old: &str, new: &str) -> (String, ExitCode) {
     let mut output = "".to_string();
     let dotfiles_dir = match dotfiles::get_dotfiles_path(&mut output) {
-        Ok(dir) => dir.join("Configs"),
+        Ok(dir) => dir,
+        Err(e) => return (output, e.into()),
+    };
+
+    // paths like `..` or `a/b` would move things outside of the groups directories
+    for name in [old, new] {
+        if Path::new(name).components().count() != 1 || Path::new(name).file_name().is_none() {
+            output.push_str(&format!("`{name}` is not a valid group name.\n"));
+            return (output, ExitCode::FAILURE);
+        }
+    }
+
+    let dotfile_dirs = [
+        dotfiles::DotfileType::Configs,
+        dotfiles::DotfileType::Hooks,
+        dotfiles::DotfileType::Secrets,
+    ]
+    .map(|dtype| dtype.dir_in(&dotfiles_dir));
+
+    let existing_dirs: Vec<_> = dotfile_dirs
+        .iter()
+        .filter(|dir| dir.join(old).exists())
+        .collect();
+    if existing_dirs.is_empty() {
+        output.push_str(&format!("{old} does not exist.\n"));
+        return (output, ReturnCode::NoSuchFileOrDir.into());
+    }
+
+    let mut new_exists = false;
+    for dir in dotfile_dirs.iter().filter(|dir| dir.join(new).exists()) {
+        output.push_str(&format!("{} already exists.\n", dir.join(new).display()));
+        new_exists = true;
+    }
+    if new_exists {
+        return (output, ExitCode::FAILURE);
+    }
+
+    let was_deployed = dotfile_dirs[0].join(old).exists()
+        && match symlinks::is_group_symlinked(old, &mut output) {
+            Ok(deployed) => deployed,
+            Err(e) => return (output, e),
+        };
+
+    if was_deployed {
+        let (remove_output, exit_code) = symlinks::remove_cmd(&[old.to_string()], &[], false);
+        output.push_str(&remove_output);
+        if exit_code != ExitCode::SUCCESS {
+            return (output, exit_code);
+        }
+    }
+
+    for dir in existing_dirs {
+        let (old_dir, new_dir) = (dir.join(old), dir.join(new));
+        if let Err(e) = fs::rename(&old_dir, &new_dir) {
+            output.push_str(&format!("Failed to move {}: {e}\n", old_dir.display()));
+            return (output, ReturnCode::from(e).into());
+        }
+
+        output.push_str(&format!(
+            "Moved {} to {}\n",
+            old_dir.display(),
+            new_dir.display()
+        ));
+    }
+
+    if !was_deployed {
+        return (output, ExitCode::SUCCESS);
+    }
+
+    let (add_output, exit_code) =
+        symlinks::add_cmd(&[new.to_string()], &[], symlinks::AddOptions::default());
+    output.push_str(&add_output);
+
+    (output, exit_code)
+}
+
+/// Archives a group into a gzipped tarball
+///
+/// The tarball holds `Configs/<group>` along with `Hooks/<group>` and `Secrets/<group>` when
+/// hooks and secrets are set and the group has them
+pub fn export_cmd(group: &str, archive: &Path, hooks: bool, secrets: bool) -> (String, ExitCode) {
+    let mut output = String::new();
+    let dotfiles_dir = match dotfiles::get_dotfiles_path(&mut output) {
+        Ok(dir) => dir,
+        Err(e) => return (output, e.into()),
+    };
+
+    if !dotfiles::dotfile_contains(dotfiles::DotfileType::Configs, group, &mut output) {
+        output.push_str(&format!("{group} does not exist.\n"));
+        return (output, ReturnCode::NoSuchFileOrDir.into());
+    }
+
+    let mut dtypes = vec![dotfiles::DotfileType::Configs];
+    if hooks {
+        dtypes.push(dotfiles::DotfileType::Hooks);
+    }
+    if secrets {
+        dtypes.push(dotfiles::DotfileType::Secrets);
+    }
+
+    let write_archive = |output: &mut String| -> io::Result<()> {
+        let file = fs::File::create(archive)?;
+        let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+        // symlinks inside of groups are archived as the files they point to since links aren't
+        // imported
+        builder.follow_symlinks(true);
+
+        for dtype in dtypes {
+            let group_dir = dtype.dir_in(&dotfiles_dir).join(group);
+            if !group_dir.exists() {
+                output.push_str(&format!("{group} has no {}.\n", dtype.dir_name()));
+                continue;
+            }
+
+            builder.append_dir_all(Path::new(dtype.dir_name()).join(group), &group_dir)?;
+        }
+
+        builder.into_inner()?.finish()?;
+        Ok(())
+    };
+
+    if let Err(e) = write_archive(&mut output) {
+        output.push_str(&format!("Failed to write {}: {e}\n", archive.display()));
+        return (output, ReturnCode::from(e).into());
+    }
+
+    output.push_str(&format!("Exported {group} to {}.\n", archive.display()));
+    (output, ExitCode::SUCCESS)
+}
+
+/// Unpacks a tarball made by `export_cmd` into the dotfiles directory
+///
+/// Nothing is unpacked if the tarball has anything besides groups, if it has links, which
+/// could point outside of the dotfiles directory, or anything else that isn't a regular file or
+/// a directory, or if any of its groups already exist
+pub fn import_cmd(archive: &Path) -> (String, ExitCode) {
+    let mut output = String::new();
+    let dotfiles_dir = match dotfiles::get_dotfiles_path(&mut output) {
+        Ok(dir) => dir,
+        Err(e) => return (output, e.into()),
+    };
+
+    let open_archive = || -> io::Result<tar::Archive<GzDecoder<fs::File>>> {
+        Ok(tar::Archive::new(GzDecoder::new(fs::File::open(archive)?)))
+    };
+
+    // returns where an entry of the tarball is unpacked to and the group it belongs to
+    let entry_target = |entry_path: &Path| -> Option<(PathBuf, PathBuf)> {
+        let mut components = entry_path.components();
+        let dtype = match components.next()?.as_os_str().to_str()? {
+            "Configs" => dotfiles::DotfileType::Configs,
+            "Hooks" => dotfiles::DotfileType::Hooks,
+            "Secrets" => dotfiles::DotfileType::Secrets,
+            _ => return None,
+        };
+
+        let rest = components.as_path();
+        if !rest
+            .components()
+            .all(|c| matches!(c, path::Component::Normal(_)))
+        {
+            return None;
+        }
+
+        let group_dir = dtype.dir_in(&dotfiles_dir).join(rest.components().next()?);
+        Some((dtype.dir_in(&dotfiles_dir).join(rest), group_dir))
+    };
+
+    // everything is checked before unpacking so that a bad tarball doesn't leave half a group,
+    // returns why the tarball can't be imported if it can't
+    let mut group_dirs = Vec::new();
+    let check_archive = |group_dirs: &mut Vec<PathBuf>| -> io::Result<Option<String>> {
+        for entry in open_archive()?.entries()? {
+            let entry = entry?;
+            let entry_path = entry.path()?.into_owned();
+            let Some((_, group_dir)) = entry_target(&entry_path) else {
+                return Ok(Some(format!(
+                    "{} doesn't belong to a group, is {} a tarball made by `tuckr export`?",
+                    entry_path.display(),
+                    archive.display()
+                )));
+            };
+
+            let entry_type = entry.header().entry_type();
+            if matches!(entry_type, tar::EntryType::Symlink | tar::EntryType::Link) {
+                return Ok(Some(format!(
+                    "{} is a link, links aren't imported since they could point outside of the dotfiles directory",
+                    entry_path.display()
+                )));
+            }
+            // fifos and devices aren't dotfiles
+            if !entry_type.is_file() && !entry_type.is_dir() {
+                return Ok(Some(format!(
+                    "{} isn't a regular file or a directory",
+                    entry_path.display()
+                )));
+            }
+
+            if !group_dirs.contains(&group_dir) {
+                group_dirs.push(group_dir);
+            }
+        }
+
+        Ok(None)
+    };
+
+    match check_archive(&mut group_dirs) {
+        Ok(None) => (),
+        Ok(Some(reason)) => {
+            output.push_str(&format!("{reason}\n"));
+            return (output, ExitCode::FAILURE);
+        }
+        Err(e) => {
+            output.push_str(&format!("Failed to read {}: {e}\n", archive.display()));
+            return (output, ReturnCode::from(e).into());
+        }
+    }
+
+    let existing: Vec<_> = group_dirs.iter().filter(|dir| dir.exists()).collect();
+    if !existing.is_empty() {
+        for dir in existing {
+            output.push_str(&format!("{} already exists.\n", dir.display()));
+        }
+        return (output, ExitCode::FAILURE);
+    }
+
+    let unpack = || -> io::Result<()> {
+        for entry in open_archive()?.entries()? {
+            let mut entry = entry?;
+            let entry_path = entry.path()?.into_owned();
+            let Some((target, _)) = entry_target(&entry_path) else {
+                continue;
+            };
+
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            entry.unpack(&target)?;
+        }
+
+        Ok(())
+    };
+
+    if let Err(e) = unpack() {
+        output.push_str(&format!("Failed to unpack {}: {e}\n", archive.display()));
+        return (output, ReturnCode::from(e).into());
+    }
+
+    for group_dir in group_dirs {
+        output.push_str(&format!("Imported {}\n", group_dir.display()));
+    }
+    (output, ExitCode::SUCCESS)
+}
+
+/// Writes a file through a temporary file next to it that's then renamed into place
+///
+/// An interrupted write never leaves a partial file behind, the target either keeps its old
+/// contents or gets the new ones. Symlinks are written through instead of being replaced
+pub fn write_atomically<F>(path: &Path, write: F) -> io::Result<()>
+where
+    F: FnOnce(&Path) -> io::Result<()>,
+{
+    let path = match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_symlink() => fs::canonicalize(path)?,
+        _ => path.to_path_buf(),
+    };
+
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp_path = path.with_file_name(format!(".{file_name}.tuckr-tmp"));
+
+    let result = write(&temp_path).and_then(|_| fs::rename(&temp_path, &path));
+    if result.is_err() {
+        _ = fs::remove_file(&temp_path);
+    }
+
+    result
+}
+
+/// Deletes a file or directory, symlinks are removed without touching what they point to
+pub fn remove_path(path: &Path) -> io::Result<()> {
+    let metadata = fs::symlink_metadata(path)?;
+    if metadata.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// Moves a path to the OS' trash if `trash` is set, otherwise deletes it
+///
+/// Falls back to deleting the path when the trash isn't available
+pub fn trash_or_remove(path: &Path, trash: bool, output: &mut String) -> io::Result<()> {
+    if trash {
+        match trash::delete(path) {
+            Ok(()) => return Ok(()),
+            Err(e) => output.push_str(&format!(
+                "Couldn't move {} to the trash ({e}), deleting it instead.\n",
+                path.display()
+            )),
+        }
+    }
+
+    remove_path(path)
+}
+
+/// Removes groups from dotfiles/Configs
+///
+/// trash: moves the groups to the trash instead of deleting them
+pub fn pop_cmd(groups: &[String], trash: bool) -> (String, ExitCode) {
+    let mut output = "".to_string();
+    let dotfiles_dir = match dotfiles::get_dotfiles_path(&mut output) {
+        Ok(dir) => dotfiles::DotfileType::Configs.dir_in(&dir),
         Err(e) => {
             output.push_str(&e.to_string());
             return (output, e.into());
@@ -192,30 +798,50 @@ pub fn pop_cmd(groups: &[String]) -> (String, ExitCode) {
 
     if !invalid_groups.is_empty() {
         for group in invalid_groups {
-            output.push_str(group);
-            output.push_str(" does not exist.");
+            output.push_str(&format!("{group} does not exist.\n"));
         }
 
         return (output, ReturnCode::NoSuchFileOrDir.into());
     }
 
-    output.push_str("The following groups will be removed:");
+    output.push_str("The following groups will be removed:\n");
     for group in groups {
-        output.push_str("\t");
-        output.push_str(group);
+        output.push_str(&format!("\t{group}\n"));
     }
 
     for group_path in valid_groups {
-        fs::remove_dir_all(group_path).unwrap();
+        if let Err(e) = trash_or_remove(&group_path, trash, &mut output) {
+            output.push_str(&format!("Failed to remove {}: {e}\n", group_path.display()));
+            return (output, ReturnCode::from(e).into());
+        }
     }
 
     (output, ExitCode::SUCCESS)
 }
 
-pub fn ls_hooks_cmd() -> (String, ExitCode) {
+/// Whether the file has an execute bit set, files are always executable outside of unix
+pub fn is_executable(path: &Path) -> bool {
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::metadata(path).is_ok_and(|metadata| metadata.permissions().mode() & 0o111 != 0)
+    }
+
+    #[cfg(not(target_family = "unix"))]
+    {
+        _ = path;
+        true
+    }
+}
+
+/// Lists which groups have prehooks and posthooks, along with the hooks that can't be run since
+/// they aren't executable
+///
+/// json: prints the hooks as a JSON array instead of a table
+pub fn ls_hooks_cmd(json: bool) -> (String, ExitCode) {
     let mut output = "".to_string();
     let dir = match dotfiles::get_dotfiles_path(&mut output) {
-        Ok(dir) => dir.join("Hooks"),
+        Ok(dir) => dotfiles::DotfileType::Hooks.dir_in(&dir),
         Err(e) => {
             output.push_str(&e.to_string());
             return (output, ReturnCode::CouldntFindDotfiles.into());
@@ -223,52 +849,81 @@ pub fn ls_hooks_cmd() -> (String, ExitCode) {
     };
 
     if !dir.exists() {
-        output.push_str("There's no directory setup for Hooks");
+        output.push_str("There's no directory setup for Hooks\n");
         return (output, ReturnCode::NoSetupFolder.into());
     }
 
-    #[derive(Tabled)]
-    struct ListRow<'a> {
+    fn hook_symbol(has_hook: &bool) -> String {
+        if *has_hook { "✓" } else { "✗" }.to_string()
+    }
+
+    fn list_hooks(hooks: &[String]) -> String {
+        if hooks.is_empty() {
+            "-".into()
+        } else {
+            hooks.join("\n")
+        }
+    }
+
+    #[derive(Tabled, serde::Serialize)]
+    struct ListRow {
         #[tabled(rename = "Group")]
         group: String,
-        #[tabled(rename = "Prehook")]
-        prehook: &'a str,
-        #[tabled(rename = "Posthook")]
-        posthook: &'a str,
+        #[tabled(rename = "Prehook", display_with = "hook_symbol")]
+        #[serde(rename = "has_prehook")]
+        prehook: bool,
+        #[tabled(rename = "Posthook", display_with = "hook_symbol")]
+        #[serde(rename = "has_posthook")]
+        posthook: bool,
+        #[tabled(rename = "Not executable", display_with = "list_hooks")]
+        not_executable: Vec<String>,
     }
 
-    let dir = fs::read_dir(dir).unwrap();
+    let groups = match dotfiles::list_groups(dotfiles::DotfileType::Hooks) {
+        Ok(groups) => groups,
+        Err(e) => return (output, e.into()),
+    };
     let mut rows = Vec::new();
 
-    let true_symbol = "✓".to_string();
-    let false_symbol = "✗".to_string();
-
-    for hook in dir {
-        let hook_dir = hook.unwrap();
-        let hook_name = hook_dir.file_name();
-        let group = hook_name.to_str().unwrap().to_string();
+    for group in groups {
+        let hook_dir = dir.join(&group);
 
         let mut hook_entry = ListRow {
             group,
-            prehook: &false_symbol,
-            posthook: &false_symbol,
+            prehook: false,
+            posthook: false,
+            not_executable: Vec::new(),
         };
 
-        for hook in fs::read_dir(hook_dir.path()).unwrap() {
-            let hook = hook.unwrap().file_name();
+        for hook in fs::read_dir(hook_dir).unwrap() {
+            let hook = hook.unwrap();
+            let hook_path = hook.path();
+            let hook = hook.file_name();
             let hook = hook.to_str().unwrap();
             if hook.starts_with("pre") {
-                hook_entry.prehook = &true_symbol;
+                hook_entry.prehook = true;
             } else if hook.starts_with("post") {
-                hook_entry.posthook = &true_symbol;
+                hook_entry.posthook = true;
+            }
+
+            // they'd only fail once `tuckr set` tries to run them
+            if hooks::is_hook(hook) && hook_path.is_file() && !is_executable(&hook_path) {
+                hook_entry.not_executable.push(hook.to_string());
             }
         }
+        hook_entry.not_executable.sort();
 
         rows.push(hook_entry);
     }
+    let any_not_executable = rows.iter().any(|row| !row.not_executable.is_empty());
+
+    if json {
+        output.push_str(&(serde_json::to_string_pretty(&rows).unwrap() + "\n"));
+        return (output, ExitCode::SUCCESS);
+    }
 
     if rows.is_empty() {
-        output.push_str("No hooks have been set up yet.");
+        output.push_str("No hooks have been set up yet.\n");
         return (output, ExitCode::SUCCESS);
     }
 
@@ -281,25 +936,132 @@ pub fn ls_hooks_cmd() -> (String, ExitCode) {
         .with(Modify::new(Segment::new(1.., 1..)).with(Alignment::center()));
     output.push_str(&hooks_list.to_string());
 
+    if any_not_executable {
+        output.push_str(
+            "\nSome hooks aren't executable and will fail to run, make them executable with `chmod +x`.\n",
+        );
+    }
+
     (output, ExitCode::SUCCESS)
 }
 
-// todo: make ls-secrets command prettier
-pub fn ls_secrets_cmd() -> (String, ExitCode) {
+/// Lists every stored secret, grouped by the group it belongs to
+///
+/// json: prints the secrets as a JSON array instead of a table
+pub fn ls_secrets_cmd(json: bool) -> (String, ExitCode) {
     let mut output = "".to_string();
     let secrets_dir = match dotfiles::get_dotfiles_path(&mut output) {
-        Ok(p) => p.join("Secrets"),
-        Err(e) => { output.push_str(&e.to_string()); return (output, e.into()); },
+        Ok(p) => dotfiles::DotfileType::Secrets.dir_in(&p),
+        Err(e) => {
+            output.push_str(&e.to_string());
+            return (output, e.into());
+        }
     };
 
-    let Ok(secrets) = secrets_dir.read_dir() else {
+    if !secrets_dir.is_dir() {
+        output.push_str("There's no directory setup for Secrets\n");
         return (output, ReturnCode::NoSetupFolder.into());
+    }
+
+    fn list_files(files: &[String]) -> String {
+        files.join("\n")
+    }
+
+    #[derive(Tabled, serde::Serialize)]
+    struct ListRow {
+        #[tabled(rename = "Group")]
+        group: String,
+        #[tabled(rename = "Secrets", display_with = "list_files")]
+        files: Vec<String>,
+    }
+
+    let groups = match dotfiles::list_groups(dotfiles::DotfileType::Secrets) {
+        Ok(groups) => groups,
+        Err(e) => return (output, e.into()),
     };
 
-    for secret in secrets {
-        let secret = secret.unwrap();
-        output.push_str(secret.file_name().to_str().unwrap());
+    let mut rows: Vec<_> = groups
+        .into_iter()
+        .map(|group| {
+            let group_dir = secrets_dir.join(&group);
+            let mut files: Vec<_> = WalkDir::new(&group_dir)
+                .into_iter()
+                .filter_map(|f| f.ok())
+                .filter(|f| f.file_type().is_file())
+                .map(|f| {
+                    f.path()
+                        .strip_prefix(&group_dir)
+                        .unwrap()
+                        .to_string_lossy()
+                        .into_owned()
+                })
+                .collect();
+            files.sort();
+
+            ListRow { group, files }
+        })
+        .collect();
+    rows.sort_by(|a, b| a.group.cmp(&b.group));
+
+    if json {
+        output.push_str(&(serde_json::to_string_pretty(&rows).unwrap() + "\n"));
+        return (output, ExitCode::SUCCESS);
+    }
+
+    if rows.is_empty() {
+        output.push_str("No secrets have been stored yet.\n");
+        return (output, ExitCode::SUCCESS);
+    }
+
+    use tabled::{Margin, Style};
+
+    let mut secrets_list = Table::new(rows);
+    secrets_list
+        .with(Style::rounded())
+        .with(Margin::new(4, 4, 1, 1));
+    output.push_str(&(secrets_list.to_string() + "\n"));
+
+    (output, ExitCode::SUCCESS)
+}
+
+/// Checks that every required group exists, eg: to validate a dotfiles repo on CI
+///
+/// hooks, secrets: also require the groups to have hooks or secrets
+pub fn validate_cmd(required: &[String], hooks: bool, secrets: bool) -> (String, ExitCode) {
+    let mut output = "".to_string();
+    if let Err(e) = dotfiles::get_dotfiles_path(&mut output) {
+        return (output, e.into());
+    }
+
+    let dotfile_types = [
+        (dotfiles::DotfileType::Configs, true),
+        (dotfiles::DotfileType::Hooks, hooks),
+        (dotfiles::DotfileType::Secrets, secrets),
+    ];
+
+    let mut any_missing = false;
+    for (dtype, is_required) in dotfile_types {
+        if !is_required {
+            continue;
+        }
+
+        let Some(missing_groups) = dotfiles::check_invalid_groups(dtype, required, &mut output)
+        else {
+            continue;
+        };
+
+        any_missing = true;
+        output.push_str(&format!("Missing from {}:\n", dtype.dir_name()));
+        for group in missing_groups {
+            output.push_str(&format!("\t{group}\n"));
+        }
+    }
+
+    if any_missing {
+        return (output, ReturnCode::NoSuchFileOrDir.into());
     }
+
+    output.push_str("All required groups exist.\n");
     (output, ExitCode::SUCCESS)
 }
 
@@ -311,44 +1073,40 @@ pub fn groupis_cmd(files: &[String]) -> (String, ExitCode) {
             output.push_str(&e.to_string());
             return (output, ReturnCode::NoSetupFolder.into());
         }
-    }
-    .join("Configs");
+    };
+    let dotfiles_dir = dotfiles::DotfileType::Configs.dir_in(&dotfiles_dir);
 
-    let groups: Vec<_> = dotfiles_dir
-        .read_dir()
-        .unwrap()
-        .filter_map(|f| {
-            let f = f.unwrap();
-            if f.file_type().unwrap().is_dir() {
-                Some(f.file_name().into_string().unwrap())
-            } else {
-                None
-            }
-        })
-        .collect();
+    let groups = match dotfiles::list_groups(dotfiles::DotfileType::Configs) {
+        Ok(groups) => groups,
+        Err(e) => return (output, e.into()),
+    };
 
     'next_file: for file in files {
         let mut file_path = PathBuf::from(file);
         if !file_path.exists() {
-            output.push_str(&file);
-            output.push_str(" does not exist.");
+            output.push_str(&format!("{file} does not exist.\n"));
             continue;
         }
 
         if let Ok(dotfile) = dotfiles::Dotfile::try_from(file_path.clone()) {
-            output.push_str(&dotfile.group_name);
+            output.push_str(&format!("{}\n", dotfile.group_name));
             continue;
         }
 
         while !file_path.is_symlink() {
             if !file_path.pop() {
-                output.push_str(&file);
-                output.push_str(" is not a tuckr dotfile.");
+                output.push_str(&format!("{file} is not a tuckr dotfile.\n"));
                 break 'next_file;
             }
         }
 
-        let basepath = dotfiles::get_target_basepath(&file_path);
+        let basepath = match dotfiles::get_target_basepath(&file_path) {
+            Ok(basepath) => basepath,
+            Err(err) => {
+                output.push_str(&format!("{err}\n"));
+                continue;
+            }
+        };
 
         for group in &groups {
             let dotfile_path = dotfiles_dir.join(group).join(&basepath);
@@ -360,15 +1118,668 @@ pub fn groupis_cmd(files: &[String]) -> (String, ExitCode) {
             let dotfile = match dotfiles::Dotfile::try_from(dotfile_path) {
                 Ok(dotfile) => dotfile,
                 Err(err) => {
-                    output.push_str(&err);
+                    output.push_str(&format!("{err}\n"));
                     continue;
                 }
             };
 
-            output.push_str(&dotfile.group_name);
+            output.push_str(&format!("{}\n", dotfile.group_name));
 
             return (output, ExitCode::SUCCESS);
         }
     }
     (output, ExitCode::SUCCESS)
 }
+
+/// Resolves the `.` and `..` components of an absolute path without following any symlink
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            path::Component::CurDir => (),
+            path::Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    normalized
+}
+
+/// Prints which group each deployed path is symlinked from
+///
+/// Paths inside of symlinked directories belong to the group the directory is symlinked from
+pub fn owns_cmd(paths: &[String]) -> (String, ExitCode) {
+    let mut output: String = "".into();
+    let configs_dir = match dotfiles::get_dotfiles_path(&mut output) {
+        Ok(path) => dotfiles::DotfileType::Configs.dir_in(&path),
+        Err(e) => return (output, e.into()),
+    };
+
+    let mut exit_code = ExitCode::SUCCESS;
+    for path in paths {
+        // `..` is resolved like the shell does so that only the path's own directories are
+        // walked up to find the symlink it's deployed through
+        let deployed = match path::absolute(path) {
+            Ok(deployed) => normalize_path(&deployed),
+            Err(e) => {
+                output.push_str(&format!("Couldn't resolve {path}: {e}\n"));
+                exit_code = ReturnCode::from(e).into();
+                continue;
+            }
+        };
+        if fs::symlink_metadata(&deployed).is_err() {
+            output.push_str(&format!("{path} does not exist.\n"));
+            exit_code = ReturnCode::NoSuchFileOrDir.into();
+            continue;
+        }
+
+        // walks up to the symlink the path is deployed through
+        let mut link = deployed.clone();
+        while !link.is_symlink() && link.pop() {}
+        let Ok(rest) = deployed.strip_prefix(&link) else {
+            output.push_str(&format!("{path} is not managed by tuckr.\n"));
+            exit_code = ExitCode::FAILURE;
+            continue;
+        };
+
+        let owner = fs::read_link(&link)
+            .ok()
+            .map(|target| {
+                let target = link.parent().unwrap_or(&link).join(target);
+                // joining an empty path would add a trailing separator
+                if rest.as_os_str().is_empty() {
+                    target
+                } else {
+                    target.join(rest)
+                }
+            })
+            .filter(|target| target.starts_with(&configs_dir))
+            .and_then(|target| dotfiles::Dotfile::try_from(target).ok());
+
+        match owner {
+            Some(dotfile) => output.push_str(&format!(
+                "{path}: {} ({})\n",
+                dotfile.group_name,
+                dotfile.path.display()
+            )),
+            None => {
+                output.push_str(&format!("{path} is not managed by tuckr.\n"));
+                exit_code = ExitCode::FAILURE;
+            }
+        }
+    }
+
+    (output, exit_code)
+}
+
+/// Prints where the supplied dotfiles would be deployed to
+///
+/// Paths can either be relative to the current directory or to the dotfiles directory
+pub fn target_cmd(files: &[String]) -> (String, ExitCode) {
+    let mut output = String::new();
+    let dotfiles_dir = match dotfiles::get_dotfiles_path(&mut output) {
+        Ok(path) => path,
+        Err(e) => {
+            output.push_str(&e.to_string());
+            return (output, e.into());
+        }
+    };
+
+    let mut any_file_failed = false;
+    for file in files {
+        let file_path = PathBuf::from(file);
+        let file_path = if file_path.exists() {
+            path::absolute(file_path).unwrap()
+        } else {
+            dotfiles_dir.join(file_path)
+        };
+
+        let dotfile = match dotfiles::Dotfile::try_from(file_path) {
+            Ok(dotfile) => dotfile,
+            Err(err) => {
+                output.push_str(&format!("{file}: {err}\n"));
+                any_file_failed = true;
+                continue;
+            }
+        };
+
+        if dotfile.path == dotfile.group_path {
+            output.push_str(&format!("{file} is a group, not a dotfile.\n"));
+            any_file_failed = true;
+            continue;
+        }
+
+        let Ok(target) = dotfile.display_target_path(&mut output) else {
+            any_file_failed = true;
+            continue;
+        };
+        output.push_str(&format!("{}\n", target.display()));
+    }
+
+    if any_file_failed {
+        (output, ReturnCode::NoSuchFileOrDir.into())
+    } else {
+        (output, ExitCode::SUCCESS)
+    }
+}
+
+/// Prints the platform tuckr detected and which group suffixes are deployed on it
+pub fn targets_cmd() -> (String, ExitCode) {
+    let current_target_os = format!("_{}", env::consts::OS);
+    let current_target_family = format!("_{}", env::consts::FAMILY);
+
+    let mut output = format!(
+        "OS: {} ({current_target_os})\nFamily: {} ({current_target_family})\n\nValid targets:\n",
+        env::consts::OS,
+        env::consts::FAMILY,
+    );
+    for target in dotfiles::VALID_TARGETS {
+        if dotfiles::is_valid_target_name(target) {
+            output.push_str(&format!("  {target} (deployed on this platform)\n"));
+        } else {
+            output.push_str(&format!("  {target}\n"));
+        }
+    }
+
+    (output, ExitCode::SUCCESS)
+}
+
+/// Prints what an exit code means, or every exit code tab separated when none is given
+pub fn explain_code_cmd(code: Option<u8>) -> (String, ExitCode) {
+    let Some(code) = code else {
+        let output = ReturnCode::ALL
+            .into_iter()
+            .map(|code| format!("{}\t{}\t{code}\n", code.code(), code.name()))
+            .collect();
+        return (output, ExitCode::SUCCESS);
+    };
+
+    match code {
+        0 => ("0: Success\n".into(), ExitCode::SUCCESS),
+        1 => ("1: Generic failure\n".into(), ExitCode::SUCCESS),
+        _ => match ReturnCode::try_from(code) {
+            Ok(return_code) => (
+                format!("{code}: {} ({return_code})\n", return_code.name()),
+                ExitCode::SUCCESS,
+            ),
+            Err(()) => (
+                format!("{code} is not an exit code used by tuckr\n"),
+                ExitCode::FAILURE,
+            ),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explain_code() {
+        let (output, code) = explain_code_cmd(Some(6));
+        assert_eq!(output, "6: DecryptionFailed (Decryption Failed)\n");
+        assert_eq!(code, ExitCode::SUCCESS);
+
+        let (_, code) = explain_code_cmd(Some(42));
+        assert_eq!(code, ExitCode::FAILURE);
+
+        // every code is listed along with its name and message
+        let (output, _) = explain_code_cmd(None);
+        assert_eq!(output.lines().count(), ReturnCode::ALL.len());
+        assert_eq!(
+            output.lines().next(),
+            Some("2\tCouldntFindDotfiles\tCouldn't Find Dotfiles")
+        );
+    }
+
+    #[test]
+    fn targets_marks_current_platform() {
+        let (output, _) = targets_cmd();
+        let current_os = format!("  _{} (deployed on this platform)", env::consts::OS);
+
+        assert!(output.lines().any(|line| line == current_os));
+        assert_eq!(
+            output
+                .lines()
+                .filter(|line| line.starts_with("  _"))
+                .count(),
+            dotfiles::VALID_TARGETS.len()
+        );
+    }
+
+    #[test]
+    fn push_with_parents() {
+        let _lock = dotfiles::test_lock();
+        init_cmd();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&mut String::new()).unwrap();
+        let home_dir = dirs::home_dir().unwrap();
+        let root = home_dir.join(".tuckr_parents_test");
+        let app = root.join("app");
+        fs::create_dir_all(app.join("sub")).unwrap();
+        fs::write(app.join("sub").join("conf"), "conf").unwrap();
+        fs::write(app.join("other"), "other").unwrap();
+        fs::write(root.join("outside"), "outside").unwrap();
+
+        let conf = app.join("sub").join("conf").display().to_string();
+        let (_, exit_code) = push_cmd(
+            "Parents".into(),
+            std::slice::from_ref(&conf),
+            None,
+            false,
+            None,
+            Some(&app),
+        );
+        assert_eq!(exit_code, ExitCode::SUCCESS);
+
+        let pushed = dotfiles_dir.join("Configs/Parents/.tuckr_parents_test");
+        assert!(pushed.join("app/sub/conf").is_file());
+        assert!(pushed.join("app/other").is_file());
+        assert!(!pushed.join("outside").exists());
+
+        // the ancestor has to be inside of $HOME
+        let (_, exit_code) = push_cmd(
+            "Parents".into(),
+            &[conf],
+            None,
+            false,
+            None,
+            Some(&std::env::temp_dir()),
+        );
+        assert_ne!(exit_code, ExitCode::SUCCESS);
+
+        fs::remove_dir_all(root).unwrap();
+        fs::remove_dir_all(dotfiles_dir).unwrap();
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn normalize_dotted_paths() {
+        assert_eq!(
+            super::normalize_path(Path::new("/home/user/.config/../.zshrc")),
+            PathBuf::from("/home/user/.zshrc")
+        );
+        assert_eq!(
+            super::normalize_path(Path::new("/home/./user/..")),
+            PathBuf::from("/home")
+        );
+        assert_eq!(super::normalize_path(Path::new("/..")), PathBuf::from("/"));
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn owns_only_claims_links_into_configs() {
+        use std::os::unix::fs::symlink;
+
+        let _lock = dotfiles::test_lock();
+        init_cmd();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&mut String::new()).unwrap();
+        let group_dir = dotfiles_dir.join("Configs").join("Owned");
+        fs::create_dir_all(&group_dir).unwrap();
+        fs::write(group_dir.join("owned_file"), "").unwrap();
+
+        let deployed_dir = std::env::temp_dir().join(format!("tuckr-owns-{}", std::process::id()));
+        fs::create_dir_all(&deployed_dir).unwrap();
+        let (owned, foreign, plain) = (
+            deployed_dir.join("owned"),
+            deployed_dir.join("foreign"),
+            deployed_dir.join("plain"),
+        );
+        symlink(group_dir.join("owned_file"), &owned).unwrap();
+        fs::write(&plain, "").unwrap();
+        symlink(&plain, &foreign).unwrap();
+
+        let owned = owned.display().to_string();
+        let (output, exit_code) = owns_cmd(std::slice::from_ref(&owned));
+        assert_eq!(exit_code, ExitCode::SUCCESS);
+        assert_eq!(
+            output,
+            format!(
+                "{owned}: Owned ({})\n",
+                group_dir.join("owned_file").display()
+            )
+        );
+
+        // `..` is resolved before looking for the symlink
+        fs::create_dir_all(deployed_dir.join("sub")).unwrap();
+        let dotted = deployed_dir.join("sub").join("..").join("owned");
+        let dotted = dotted.display().to_string();
+        let (output, exit_code) = owns_cmd(std::slice::from_ref(&dotted));
+        assert_eq!(exit_code, ExitCode::SUCCESS);
+        assert!(output.starts_with(&format!("{dotted}: Owned")));
+
+        for path in [foreign, plain] {
+            let path = path.display().to_string();
+            let (output, exit_code) = owns_cmd(std::slice::from_ref(&path));
+            assert_eq!(exit_code, ExitCode::FAILURE);
+            assert_eq!(output, format!("{path} is not managed by tuckr.\n"));
+        }
+
+        fs::remove_dir_all(deployed_dir).unwrap();
+        fs::remove_dir_all(dotfiles_dir).unwrap();
+    }
+
+    #[test]
+    fn push_skips_ignored_files() {
+        let _lock = dotfiles::test_lock();
+        init_cmd();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&mut String::new()).unwrap();
+        fs::create_dir_all(dotfiles_dir.join(".git")).unwrap();
+        fs::write(dotfiles_dir.join(".gitignore"), "*.log\n").unwrap();
+
+        let app = dirs::home_dir().unwrap().join(".tuckr_ignore_test");
+        fs::create_dir_all(&app).unwrap();
+        fs::write(app.join("conf"), "conf").unwrap();
+        fs::write(app.join("debug.log"), "log").unwrap();
+
+        let (output, exit_code) = push_cmd(
+            "Ignored".into(),
+            &[app.display().to_string()],
+            None,
+            true,
+            None,
+            None,
+        );
+        assert_eq!(exit_code, ExitCode::SUCCESS);
+        assert!(output.contains("Skipped 1 file(s) ignored by the dotfiles repo."));
+
+        // ignored files are neither copied nor replaced with a symlink
+        let pushed = dotfiles_dir.join("Configs/Ignored/.tuckr_ignore_test");
+        assert!(pushed.join("conf").is_file());
+        assert!(!pushed.join("debug.log").exists());
+        assert!(app.join("conf").is_symlink());
+        assert!(!app.join("debug.log").is_symlink());
+
+        fs::remove_dir_all(app).unwrap();
+        fs::remove_dir_all(dotfiles_dir).unwrap();
+    }
+
+    #[test]
+    fn push_reports_files_it_cant_copy() {
+        let _lock = dotfiles::test_lock();
+        init_cmd();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&mut String::new()).unwrap();
+
+        let app = dirs::home_dir().unwrap().join(".tuckr_blocked_test");
+        fs::create_dir_all(&app).unwrap();
+        fs::write(app.join("conf"), "conf").unwrap();
+        // a file where the pushed directory should go keeps it from being copied
+        let group_dir = dotfiles_dir.join("Configs").join("Blocked");
+        fs::create_dir_all(&group_dir).unwrap();
+        fs::write(group_dir.join(".tuckr_blocked_test"), "").unwrap();
+
+        let (output, exit_code) = push_cmd(
+            "Blocked".into(),
+            &[app.display().to_string()],
+            None,
+            true,
+            None,
+            None,
+        );
+        assert_eq!(exit_code, ExitCode::from(ReturnCode::NoSuchFileOrDir));
+        assert!(output.contains("Couldn't copy"));
+        assert!(!app.join("conf").is_symlink());
+
+        fs::remove_dir_all(app).unwrap();
+        fs::remove_dir_all(dotfiles_dir).unwrap();
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn ls_hooks_flags_non_executable_hooks() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let _lock = dotfiles::test_lock();
+        init_cmd();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&mut String::new()).unwrap();
+        let hooks_dir = dotfiles_dir.join("Hooks").join("NotExecutable");
+        fs::create_dir_all(&hooks_dir).unwrap();
+        for (hook, mode) in [("pre.sh", 0o644), ("post.sh", 0o755), ("helper.sh", 0o644)] {
+            let hook = hooks_dir.join(hook);
+            fs::write(&hook, "#!/bin/sh\n").unwrap();
+            fs::set_permissions(&hook, fs::Permissions::from_mode(mode)).unwrap();
+        }
+
+        let (output, _) = ls_hooks_cmd(true);
+        let rows: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(rows[0]["not_executable"], serde_json::json!(["pre.sh"]));
+
+        let (output, _) = ls_hooks_cmd(false);
+        assert!(output.contains("chmod +x"));
+
+        fs::remove_dir_all(dotfiles_dir).unwrap();
+    }
+
+    #[test]
+    fn dir_map_depth_limit() {
+        let dir = std::env::temp_dir().join(format!("tuckr-depth-{}", std::process::id()));
+        fs::create_dir_all(dir.join("a").join("b")).unwrap();
+        fs::write(dir.join("top"), "").unwrap();
+        fs::write(dir.join("a").join("b").join("deep"), "").unwrap();
+
+        let mut shallow = Vec::new();
+        dir_map_depth(&dir, Some(1), true, |f| shallow.push(f.to_path_buf()));
+        shallow.sort();
+        assert_eq!(shallow, [dir.join("a"), dir.join("top")]);
+
+        let mut all = 0;
+        dir_map(&dir, |_| all += 1);
+        assert_eq!(all, 4);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn dir_map_visits_in_order() {
+        let dir = std::env::temp_dir().join(format!("tuckr-order-{}", std::process::id()));
+        fs::create_dir_all(dir.join("b").join("c")).unwrap();
+        fs::create_dir_all(dir.join(".hidden")).unwrap();
+        for file in ["z", "a", "b/y", "b/c/x", ".hidden/file", ".rc"] {
+            fs::write(dir.join(file), "").unwrap();
+        }
+
+        let visited = |include_hidden| {
+            let mut visited = Vec::new();
+            dir_map_depth(&dir, None, include_hidden, |f| {
+                visited.push(f.strip_prefix(&dir).unwrap().to_path_buf())
+            });
+            visited
+        };
+
+        let expected_visible = ["a", "b", "b/c", "b/c/x", "b/y", "z"].map(PathBuf::from);
+        assert_eq!(visited(false), expected_visible);
+        assert_eq!(
+            visited(true),
+            [".hidden", ".hidden/file", ".rc"]
+                .map(PathBuf::from)
+                .into_iter()
+                .chain(expected_visible)
+                .collect::<Vec<_>>()
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn export_import_round_trip() {
+        let _lock = dotfiles::test_lock();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&mut String::new()).unwrap();
+        let configs_dir = dotfiles_dir.join("Configs").join("exported");
+        let hooks_dir = dotfiles_dir.join("Hooks").join("exported");
+        fs::create_dir_all(configs_dir.join(".config")).unwrap();
+        fs::create_dir_all(&hooks_dir).unwrap();
+        fs::write(configs_dir.join(".config").join("file"), "config").unwrap();
+        fs::write(hooks_dir.join("pre.sh"), "echo hook").unwrap();
+
+        let archive =
+            std::env::temp_dir().join(format!("tuckr-export-{}.tar.gz", std::process::id()));
+        let (_, code) = export_cmd("exported", &archive, true, true);
+        assert_eq!(code, ExitCode::SUCCESS);
+        assert_eq!(
+            export_cmd("missing", &archive, false, false).1,
+            ExitCode::from(ReturnCode::NoSuchFileOrDir)
+        );
+
+        // groups that already exist aren't overwritten
+        assert_eq!(import_cmd(&archive).1, ExitCode::FAILURE);
+
+        fs::remove_dir_all(&configs_dir).unwrap();
+        fs::remove_dir_all(&hooks_dir).unwrap();
+        let (_, code) = import_cmd(&archive);
+        assert_eq!(code, ExitCode::SUCCESS);
+        assert_eq!(
+            fs::read_to_string(configs_dir.join(".config").join("file")).unwrap(),
+            "config"
+        );
+        assert_eq!(
+            fs::read_to_string(hooks_dir.join("pre.sh")).unwrap(),
+            "echo hook"
+        );
+
+        fs::remove_dir_all(configs_dir).unwrap();
+        fs::remove_dir_all(hooks_dir).unwrap();
+        fs::remove_file(archive).unwrap();
+    }
+
+    #[test]
+    fn import_rejects_links() {
+        let _lock = dotfiles::test_lock();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&mut String::new()).unwrap();
+        let archive =
+            std::env::temp_dir().join(format!("tuckr-links-{}.tar.gz", std::process::id()));
+
+        // a link followed by a file written through it would land outside of the dotfiles
+        let mut builder = tar::Builder::new(GzEncoder::new(
+            fs::File::create(&archive).unwrap(),
+            Compression::default(),
+        ));
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        builder
+            .append_link(&mut header, "Configs/linked/escape", std::env::temp_dir())
+            .unwrap();
+        let mut header = tar::Header::new_gnu();
+        header.set_size(0);
+        builder
+            .append_data(&mut header, "Configs/linked/escape/file", io::empty())
+            .unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let (output, code) = import_cmd(&archive);
+        assert_eq!(code, ExitCode::FAILURE);
+        assert!(output.contains("is a link"));
+        assert!(!dotfiles_dir.join("Configs").join("linked").exists());
+
+        // neither are fifos or devices
+        for entry_type in [
+            tar::EntryType::Fifo,
+            tar::EntryType::Char,
+            tar::EntryType::Block,
+        ] {
+            let mut builder = tar::Builder::new(GzEncoder::new(
+                fs::File::create(&archive).unwrap(),
+                Compression::default(),
+            ));
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(entry_type);
+            header.set_size(0);
+            builder
+                .append_data(&mut header, "Configs/special/device", io::empty())
+                .unwrap();
+            builder.into_inner().unwrap().finish().unwrap();
+
+            let (output, code) = import_cmd(&archive);
+            assert_eq!(code, ExitCode::FAILURE);
+            assert!(output.contains("isn't a regular file or a directory"));
+            assert!(!dotfiles_dir.join("Configs").join("special").exists());
+        }
+
+        fs::remove_file(archive).unwrap();
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn export_dereferences_links() {
+        let _lock = dotfiles::test_lock();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&mut String::new()).unwrap();
+        let configs_dir = dotfiles_dir.join("Configs").join("with_links");
+        fs::create_dir_all(&configs_dir).unwrap();
+        fs::write(configs_dir.join("file"), "config").unwrap();
+        std::os::unix::fs::symlink("file", configs_dir.join("alias")).unwrap();
+
+        let archive =
+            std::env::temp_dir().join(format!("tuckr-export-links-{}.tar.gz", std::process::id()));
+        assert_eq!(
+            export_cmd("with_links", &archive, false, false).1,
+            ExitCode::SUCCESS
+        );
+
+        fs::remove_dir_all(&configs_dir).unwrap();
+        let (output, code) = import_cmd(&archive);
+        assert_eq!(code, ExitCode::SUCCESS, "{output}");
+        let alias = configs_dir.join("alias");
+        assert!(!alias.is_symlink());
+        assert_eq!(fs::read_to_string(alias).unwrap(), "config");
+
+        fs::remove_dir_all(configs_dir).unwrap();
+        fs::remove_file(archive).unwrap();
+    }
+
+    #[test]
+    fn rename_rejects_paths() {
+        let _lock = dotfiles::test_lock();
+        init_cmd();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&mut String::new()).unwrap();
+
+        for (old, new) in [
+            ("..", "group"),
+            ("group/..", "group"),
+            ("group", "../group"),
+        ] {
+            let (output, code) = rename_cmd(old, new);
+            assert_eq!(code, ExitCode::FAILURE);
+            assert!(output.contains("is not a valid group name"));
+        }
+        assert!(dotfiles_dir.join("Configs").is_dir());
+
+        fs::remove_dir_all(dotfiles_dir).unwrap();
+    }
+
+    #[test]
+    fn parse_human_durations() {
+        assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("2h"), Ok(Duration::from_secs(2 * 60 * 60)));
+        assert_eq!(parse_duration("1d"), Ok(Duration::from_secs(24 * 60 * 60)));
+
+        assert!(parse_duration("3").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("3y").is_err());
+    }
+
+    #[test]
+    fn atomic_writes_keep_old_contents_on_failure() {
+        let temp_dir =
+            std::env::temp_dir().join(format!("tuckr-atomic-write-{}", std::process::id()));
+        _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(&temp_dir).unwrap();
+        let file = temp_dir.join("file");
+        fs::write(&file, "old").unwrap();
+
+        // a write that fails midway leaves neither a partial file nor a temp file behind
+        let result = write_atomically(&file, |temp| {
+            fs::write(temp, "partial")?;
+            Err(io::Error::other("interrupted"))
+        });
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&file).unwrap(), "old");
+        assert_eq!(fs::read_dir(&temp_dir).unwrap().count(), 1);
+
+        write_atomically(&file, |temp| fs::write(temp, "new")).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "new");
+        assert_eq!(fs::read_dir(&temp_dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(&temp_dir).unwrap();
+    }
+}
@@ -4,18 +4,59 @@
 //! Hooking steps:
 //! 1. Setup scripts are run
 //! 2. Dotfiles are symlinked
-//! 3. Post setup scripts are run
+//! 3. Install scripts are run, only the first time the group is set up
+//! 4. Post setup scripts are run
+//!
+//! The hooks in Hooks/_global run only once, before and after all of the groups
 
+use crate::config::{Config, HooksCwd};
 use crate::dotfiles::{self, Dotfile, ReturnCode};
 use crate::symlinks;
-use owo_colors::OwoColorize;
+use std::fmt;
 use std::fs;
-use std::path::PathBuf;
+use std::io;
+use std::path::{Path, PathBuf};
 use std::process::{Command, ExitCode};
+use std::time::{Duration, Instant};
+use tabled::Tabled;
+
+/// Hooks group whose hooks run once per `tuckr set`, before and after every other group
+pub const GLOBAL_HOOKS_GROUP: &str = "_global";
+
+/// Exit code a prehook uses to skip symlinking its group, eg: when it placed the files itself
+///
+/// The group's posthooks still run. It's `EX_TEMPFAIL` from sysexits.h, which hooks are unlikely
+/// to exit with by accident
+pub const SKIP_SYMLINK_EXIT_CODE: i32 = 75;
+
+/// Width used for info boxes when the output isn't going to a terminal
+const DEFAULT_BOX_WIDTH: usize = 80;
+
+/// Narrowest the content of an info box gets, however small the terminal is
+const MIN_CONTENT_WIDTH: usize = 10;
 
 /// Prints a single row info box with title on the left
 /// and content on the right
+///
+/// The box is wrapped to fit the terminal's width
 fn print_info_box(title: &str, content: &str, output: &mut String) -> String {
+    let width = terminal_size::terminal_size()
+        .map(|(terminal_size::Width(width), _)| width as usize)
+        .unwrap_or(DEFAULT_BOX_WIDTH);
+
+    let hook_box = info_box(title, content, width);
+    output.push_str(&hook_box);
+    output.push('\n');
+
+    hook_box
+}
+
+fn info_box(title: &str, content: &str, width: usize) -> String {
+    // only the content is wrapped, the borders and padding take up 6 characters
+    let content_width = width
+        .saturating_sub(title.chars().count() + 6)
+        .max(MIN_CONTENT_WIDTH);
+
     let mut hook_box = tabled::builder::Builder::default()
         .set_columns([title])
         .add_record([content])
@@ -23,17 +64,42 @@ fn print_info_box(title: &str, content: &str, output: &mut String) -> String {
         .build();
     hook_box
         .with(tabled::Rotate::Left)
-        .with(tabled::Style::rounded().off_vertical());
-    output.push_str(&hook_box.to_string());
+        .with(tabled::Style::rounded().off_vertical())
+        .with(
+            tabled::Modify::new(tabled::object::Columns::single(1))
+                .with(tabled::Width::wrap(content_width).keep_words()),
+        );
 
     hook_box.to_string()
 }
 
-#[derive(Debug, PartialEq)]
+/// Restricts which of the deploy steps `set` goes through
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DeployOnly {
+    #[default]
+    Everything,
+    /// Only symlinks the groups without running any hook
+    Symlinks,
+    /// Only runs the hooks without symlinking anything
+    Hooks,
+}
+
+impl DeployOnly {
+    fn includes(self, step: DeployStep) -> bool {
+        match self {
+            DeployOnly::Everything => true,
+            DeployOnly::Symlinks => matches!(step, DeployStep::Initialize | DeployStep::Symlink),
+            DeployOnly::Hooks => step != DeployStep::Symlink,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum DeployStep {
     Initialize, // Default value before starting deployment
     PreHook,
     Symlink,
+    Install,
     PostHook,
 }
 
@@ -60,6 +126,10 @@ impl Iterator for DeployStages {
                 Some(DeployStep::Symlink)
             }
             DeployStep::Symlink => {
+                self.0 = DeployStep::Install;
+                Some(DeployStep::Install)
+            }
+            DeployStep::Install => {
                 self.0 = DeployStep::PostHook;
                 Some(DeployStep::PostHook)
             }
@@ -68,154 +138,725 @@ impl Iterator for DeployStages {
     }
 }
 
-/// Runs hooks of type PreHook or PostHook
-fn run_hook(group: &str, hook_type: DeployStep, output: &mut String) -> Result<String, ExitCode> {
-    let dotfiles_dir = match dotfiles::get_dotfiles_path(output) {
-        Ok(dir) => dir,
-        Err(e) => {
-            output.push_str(&e.to_string());
-            return Err(ReturnCode::CouldntFindDotfiles.into());
+/// Returns true if the hook should run on the current platform
+///
+/// Hooks can be limited to a platform with the same suffixes used by groups, placed before the
+/// file extension, eg: `pre_setup_linux.sh`
+fn is_hook_for_current_platform(filename: &str) -> bool {
+    let stem = Path::new(filename)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(filename);
+    dotfiles::is_valid_target_name(stem)
+}
+
+/// Whether `tuckr set` runs the file as a hook, other files in the hooks directory are left alone
+pub fn is_hook(filename: &str) -> bool {
+    HookPhase::from_filename(filename).is_some()
+}
+
+/// When a hook runs relative to the other hooks of its group
+///
+/// Hooks starting with a number, eg: `10-deps.sh`, run in numeric order. The ones numbered
+/// below the group's symlink phase run before symlinking, along with `pre` hooks, the rest run
+/// after it, along with `post` hooks. `install` hooks only run the first time a group is set up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum HookPhase {
+    Pre,
+    Numbered(u32),
+    Post,
+    Install,
+}
+
+impl HookPhase {
+    fn from_filename(filename: &str) -> Option<Self> {
+        if filename.starts_with("pre") {
+            return Some(HookPhase::Pre);
+        }
+
+        if filename.starts_with("post") {
+            return Some(HookPhase::Post);
+        }
+
+        if filename.starts_with("install") {
+            return Some(HookPhase::Install);
+        }
+
+        let (number, rest) = filename.split_at(filename.find(|c: char| !c.is_ascii_digit())?);
+        if !rest.starts_with(['-', '_']) {
+            return None;
+        }
+
+        number.parse().ok().map(HookPhase::Numbered)
+    }
+
+    /// Returns true if the hook runs on the given step
+    fn runs_on(self, step: &DeployStep, symlink_phase: u32) -> bool {
+        match (self, step) {
+            (HookPhase::Pre, DeployStep::PreHook)
+            | (HookPhase::Post, DeployStep::PostHook)
+            | (HookPhase::Install, DeployStep::Install) => true,
+            (HookPhase::Numbered(phase), DeployStep::PreHook) => phase < symlink_phase,
+            (HookPhase::Numbered(phase), DeployStep::PostHook) => phase >= symlink_phase,
+            _ => false,
+        }
+    }
+}
+
+/// Returns the directory a group's hooks are run from
+///
+/// Targets that don't exist yet, eg: before the group is symlinked, fall back to $HOME, or to the
+/// group's hooks directory when there's no home directory
+fn hook_working_dir(
+    group: &str,
+    hooks_cwd: HooksCwd,
+    output: &mut String,
+) -> Result<PathBuf, ExitCode> {
+    let dotfiles_dir = dotfiles::get_dotfiles_path(output)?;
+    let hooks_dir = dotfiles::DotfileType::Hooks
+        .dir_in(&dotfiles_dir)
+        .join(group);
+
+    let dir = match hooks_cwd {
+        HooksCwd::Hooks => Some(hooks_dir.clone()),
+        HooksCwd::Target => {
+            // groups that only have hooks aren't deployed anywhere
+            let group_dir = dotfiles::DotfileType::Configs
+                .dir_in(&dotfiles_dir)
+                .join(group);
+            match Dotfile::try_from(group_dir.clone()) {
+                Ok(group) if group_dir.is_dir() => match group.target_dir(output) {
+                    Ok(target_dir) => Some(target_dir),
+                    Err(err) => {
+                        output.push_str(&format!("{err}\n"));
+                        return Err(ReturnCode::from(err).into());
+                    }
+                },
+                _ => None,
+            }
         }
     };
 
-    let group_dir = PathBuf::from(&dotfiles_dir).join("Hooks").join(group);
-    let mut str_output = "".to_string();
+    Ok(dir
+        .filter(|dir| dir.is_dir())
+        .or_else(dirs::home_dir)
+        .unwrap_or(hooks_dir))
+}
+
+/// Returns the group's hooks that run on the given step, in phase order
+///
+/// symlink_phase: numbered hooks below it are prehooks and the rest are posthooks
+fn group_hooks(
+    group: &str,
+    hook_type: DeployStep,
+    symlink_phase: u32,
+    output: &mut String,
+) -> Result<Vec<(HookPhase, String, PathBuf)>, ExitCode> {
+    let dotfiles_dir = dotfiles::get_dotfiles_path(output)?;
+
+    let group_dir = dotfiles::DotfileType::Hooks
+        .dir_in(&dotfiles_dir)
+        .join(group);
+    // groups that only have configs don't have any hooks to run
+    if !group_dir.exists() {
+        return Ok(Vec::new());
+    }
+
     let Ok(group_dir) = fs::read_dir(group_dir) else {
-        str_output.push_str("Could not read Hooks, folder may not exist or does not have the appropriate permissions");
+        output.push_str("Could not read Hooks, folder may not exist or does not have the appropriate permissions\n");
         return Err(ReturnCode::NoSetupFolder.into());
     };
 
-    for file in group_dir {
-        let file = file.unwrap().path();
-        let filename = file.file_name().unwrap().to_str().unwrap();
-        let file = file.to_str().unwrap();
+    let mut hooks: Vec<_> = group_dir
+        .filter_map(|file| {
+            let file = file.unwrap().path();
+            let filename = file.file_name()?.to_str()?.to_string();
+            let phase = HookPhase::from_filename(&filename)?;
+            (is_hook_for_current_platform(&filename) && phase.runs_on(&hook_type, symlink_phase))
+                .then_some((phase, filename, file))
+        })
+        .collect();
+    hooks.sort();
+
+    Ok(hooks)
+}
+
+/// Runs hooks of type PreHook, Install or PostHook from `cwd`, in phase order
+///
+/// symlink_phase: numbered hooks below it are prehooks and the rest are posthooks
+///
+/// Returns true if a prehook exited with `SKIP_SYMLINK_EXIT_CODE`
+fn run_hook(
+    group: &str,
+    hook_type: DeployStep,
+    symlink_phase: u32,
+    cwd: &Path,
+    output: &mut String,
+) -> Result<bool, ExitCode> {
+    let hooks = group_hooks(group, hook_type, symlink_phase, output)?;
+
+    let mut skip_symlink = false;
+    for (phase, filename, file) in hooks {
         // make sure it will only run for their specific hooks
-        match hook_type {
-            DeployStep::PreHook => {
-                if !filename.starts_with("pre") {
-                    continue;
-                }
-                print_info_box("Running Prehook", group.to_string().as_str(), output);
+        match phase {
+            HookPhase::Pre => print_info_box("Running Prehook", group, output),
+            HookPhase::Post => print_info_box("Running Posthook", group, output),
+            HookPhase::Install => print_info_box("Running Install hook", group, output),
+            HookPhase::Numbered(_) => {
+                print_info_box("Running Hook", &format!("{group} {filename}"), output)
             }
+        };
 
-            DeployStep::PostHook => {
-                if !filename.starts_with("post") {
-                    continue;
-                }
-                print_info_box("Running Posthook", group.to_string().as_str(), output);
+        tracing::info!(hook = filename, cwd = %cwd.display(), "running hook");
+        let mut child = match Command::new(&file).current_dir(cwd).spawn() {
+            Ok(child) => child,
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+                output.push_str(&format!(
+                    "{} isn't executable, make it executable with `chmod +x`: {e}\n",
+                    file.display()
+                ));
+                return Err(ExitCode::FAILURE);
             }
-            _ => (),
-        }
-
-        let mut output = match Command::new(file).spawn() {
-            Ok(output) => output,
             Err(e) => {
-                str_output.push_str(&e.to_string());
+                output.push_str(&format!("{e}\n"));
                 return Err(ExitCode::FAILURE);
             }
         };
 
-        if !output.wait().unwrap().success() {
+        let status = child.wait().unwrap();
+        tracing::debug!(hook = filename, %status, "hook exited");
+        if hook_type == DeployStep::PreHook && status.code() == Some(SKIP_SYMLINK_EXIT_CODE) {
+            skip_symlink = true;
+            continue;
+        }
+
+        if !status.success() {
             print_info_box(
                 "Failed to hook".to_string().as_str(),
                 format!("{group} {filename}").as_str(),
-                &mut str_output,
+                output,
             );
             return Err(ExitCode::FAILURE);
         }
     }
 
-    Ok(str_output)
+    Ok(skip_symlink)
 }
 
-/// Runs hooks for specified groups
-pub fn set_cmd(
+/// Returns the marker recording that the group's install hooks already ran on this machine
+fn install_marker(group: &str, output: &mut String) -> Result<PathBuf, ExitCode> {
+    let dotfiles_dir = dotfiles::get_dotfiles_path(output)?;
+    Ok(dotfiles_dir
+        .join(dotfiles::STATE_DIR)
+        .join("installed")
+        .join(group))
+}
+
+/// Creates the install marker
+fn mark_installed(marker: &Path) -> io::Result<()> {
+    // markers are kept in <dotfiles>/.tuckr/installed, nested for subgroups
+    let state_dir = marker
+        .ancestors()
+        .find(|dir| dir.ends_with(dotfiles::STATE_DIR))
+        .unwrap();
+    dotfiles::create_state_dir(state_dir.parent().unwrap())?;
+    fs::create_dir_all(marker.parent().unwrap())?;
+
+    fs::write(marker, "")
+}
+
+/// How long each deploy step took for a group
+#[derive(Default, Tabled)]
+struct GroupTimings {
+    #[tabled(rename = "Group")]
+    group: String,
+    #[tabled(rename = "Prehook", display_with = "display_timing")]
+    prehook: Option<Duration>,
+    #[tabled(rename = "Symlink", display_with = "display_timing")]
+    symlink: Option<Duration>,
+    #[tabled(rename = "Install", display_with = "display_timing")]
+    install: Option<Duration>,
+    #[tabled(rename = "Posthook", display_with = "display_timing")]
+    posthook: Option<Duration>,
+}
+
+fn display_timing(timing: &Option<Duration>) -> String {
+    match timing {
+        Some(timing) => format!("{timing:.2?}"),
+        None => "-".into(),
+    }
+}
+
+/// Returns the groups `tuckr set` goes through, in the order they're set up
+///
+/// The wildcard is expanded to every group with either configs or hooks that targets the current
+/// platform, the excluded groups are left out entirely so none of their hooks run either
+fn groups_to_set(
     groups: &[String],
     exclude: &[String],
-    force: bool,
-    adopt: bool,
-) -> (String, ExitCode) {
-    let mut output = "".to_string();
-    if let Some(invalid_groups) =
-        dotfiles::check_invalid_groups(dotfiles::DotfileType::Hooks, groups, &mut output)
-    {
+    config: &Config,
+    output: &mut String,
+) -> Result<Vec<String>, ExitCode> {
+    // groups are only invalid if they have neither configs nor hooks
+    let without_hooks =
+        dotfiles::check_invalid_groups(dotfiles::DotfileType::Hooks, groups, output);
+    let invalid_groups = without_hooks.and_then(|groups| {
+        dotfiles::check_invalid_groups(dotfiles::DotfileType::Configs, &groups, output)
+    });
+    if let Some(invalid_groups) = invalid_groups {
         for group in invalid_groups {
-            output.push_str(&group);
-            output.push_str(" does not exist.");
+            output.push_str(&format!("{group} does not exist.\n"));
+        }
+
+        return Err(ReturnCode::NoSuchFileOrDir.into());
+    }
+
+    // groups holding subgroups stand for all of them
+    let dtypes = [dotfiles::DotfileType::Configs, dotfiles::DotfileType::Hooks];
+    let exclude: Vec<String> = exclude
+        .iter()
+        .flat_map(|group| dotfiles::expand_group(group, &dtypes))
+        .collect();
+
+    let mut groups = if groups.contains(&'*'.to_string()) {
+        let mut all_groups = Vec::new();
+        for dtype in [dotfiles::DotfileType::Configs, dotfiles::DotfileType::Hooks] {
+            match dotfiles::list_groups(dtype) {
+                Ok(groups) => all_groups.extend(groups),
+                // either directory is enough to set groups up
+                Err(ReturnCode::NoSetupFolder) => (),
+                Err(e) => return Err(e.into()),
+            }
         }
+        // groups for other platforms are only an error when they're asked for by name
+        all_groups.retain(|group| dotfiles::is_valid_target_name(group));
+        all_groups.sort();
+        all_groups.dedup();
+        all_groups
+    } else {
+        let mut expanded: Vec<String> = groups
+            .iter()
+            .flat_map(|group| dotfiles::expand_group(group, &dtypes))
+            .collect();
+        expanded.dedup();
+        expanded
+    };
+    groups.retain(|group| group != GLOBAL_HOOKS_GROUP && !exclude.contains(group));
+    config.sort_by_deploy_order(&mut groups);
+    dotfiles::warn_misspelled_targets(&groups, output);
 
-        return (output, ReturnCode::NoSuchFileOrDir.into());
+    Ok(groups)
+}
+
+/// A dotfile that would be symlinked to its target
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedLink {
+    pub dotfile: PathBuf,
+    pub target: PathBuf,
+}
+
+/// What `tuckr set` would do for a group
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GroupPlan {
+    pub group: String,
+    pub prehooks: Vec<PathBuf>,
+    pub links: Vec<PlannedLink>,
+    /// empty when the install hooks already ran on this machine
+    pub install_hooks: Vec<PathBuf>,
+    pub posthooks: Vec<PathBuf>,
+}
+
+/// Everything `tuckr set` would do, in the order it would do it
+///
+/// Prehooks can skip symlinking their group when they run, which a plan can't know about
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DeployPlan {
+    /// hooks from `Hooks/_global` that run before every group
+    pub global_prehooks: Vec<PathBuf>,
+    pub groups: Vec<GroupPlan>,
+    /// hooks from `Hooks/_global` that run after every group
+    pub global_posthooks: Vec<PathBuf>,
+}
+
+impl fmt::Display for DeployPlan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let write_hooks = |f: &mut fmt::Formatter, kind: &str, hooks: &[PathBuf]| {
+            hooks
+                .iter()
+                .try_for_each(|hook| writeln!(f, "\t{kind} {}", hook.display()))
+        };
+
+        if !self.global_prehooks.is_empty() {
+            writeln!(f, "{GLOBAL_HOOKS_GROUP}:")?;
+            write_hooks(f, "run", &self.global_prehooks)?;
+        }
+
+        for group in &self.groups {
+            writeln!(f, "{}:", group.group)?;
+            write_hooks(f, "run", &group.prehooks)?;
+            for link in &group.links {
+                writeln!(
+                    f,
+                    "\tlink {} -> {}",
+                    link.target.display(),
+                    link.dotfile.display()
+                )?;
+            }
+            write_hooks(f, "run once", &group.install_hooks)?;
+            write_hooks(f, "run", &group.posthooks)?;
+        }
+
+        if !self.global_posthooks.is_empty() {
+            writeln!(f, "{GLOBAL_HOOKS_GROUP}:")?;
+            write_hooks(f, "run", &self.global_posthooks)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns what `tuckr set` would do for the groups without running any hook or touching any
+/// file, going through the same steps it does
+pub fn deploy_plan(
+    groups: &[String],
+    exclude: &[String],
+    force: bool,
+    reinstall: bool,
+    only: DeployOnly,
+    output: &mut String,
+) -> Result<DeployPlan, ExitCode> {
+    let config = Config::current(output)?;
+    let groups = groups_to_set(groups, exclude, &config, output)?;
+    let mut pending = symlinks::pending_files(&groups, force, output)?;
+
+    let hook_paths = |group: &str, step: DeployStep, output: &mut String| {
+        if !only.includes(step) {
+            return Ok(Vec::new());
+        }
+
+        let symlink_phase = config.symlink_phase(group);
+        group_hooks(group, step, symlink_phase, output).map(|hooks| {
+            hooks
+                .into_iter()
+                .map(|(_, _, path)| path)
+                .collect::<Vec<_>>()
+        })
+    };
+
+    let mut plan = DeployPlan {
+        global_prehooks: hook_paths(GLOBAL_HOOKS_GROUP, DeployStep::PreHook, output)?,
+        global_posthooks: hook_paths(GLOBAL_HOOKS_GROUP, DeployStep::PostHook, output)?,
+        ..Default::default()
+    };
+
+    for group in groups {
+        if !dotfiles::is_valid_target_name(&group) {
+            output.push_str(&format!("{group} is not a valid target\n"));
+            return Err(ExitCode::FAILURE);
+        }
+
+        let mut group_plan = GroupPlan {
+            group: group.clone(),
+            ..Default::default()
+        };
+        for step in DeployStages::new() {
+            match step {
+                DeployStep::Initialize => (),
+                DeployStep::PreHook => group_plan.prehooks = hook_paths(&group, step, output)?,
+                DeployStep::Symlink if !only.includes(step) => (),
+                DeployStep::Symlink => {
+                    for dotfile in pending.remove(&group).unwrap_or_default() {
+                        let Ok(target) = dotfile.to_target_path(output) else {
+                            continue;
+                        };
+                        group_plan.links.push(PlannedLink {
+                            dotfile: dotfile.path,
+                            target,
+                        });
+                    }
+                }
+                DeployStep::Install => {
+                    if reinstall || !install_marker(&group, output)?.exists() {
+                        group_plan.install_hooks = hook_paths(&group, step, output)?;
+                    }
+                }
+                DeployStep::PostHook => group_plan.posthooks = hook_paths(&group, step, output)?,
+            }
+        }
+        plan.groups.push(group_plan);
+    }
+
+    Ok(plan)
+}
+
+/// How `set_cmd` sets up the groups
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SetOptions {
+    /// Overwrites the files that are in the way of the dotfiles
+    pub force: bool,
+    /// Moves the conflicting files into the groups, replacing their dotfiles
+    pub adopt: bool,
+    /// Sets up the remaining groups when one of them fails instead of stopping
+    pub keep_going: bool,
+    /// Prints how long each step took for every group once done
+    pub timings: bool,
+    /// Runs the install hooks again even if they already ran
+    pub reinstall: bool,
+    /// Prints what would be done instead of doing it, see `deploy_plan`
+    pub dry_run: bool,
+    /// Skips the hooks or the symlinking, eg: to relink a group without running its hooks
+    pub only: DeployOnly,
+}
+
+/// Runs hooks for specified groups
+pub fn set_cmd(groups: &[String], exclude: &[String], options: SetOptions) -> (String, ExitCode) {
+    let SetOptions {
+        force,
+        adopt,
+        keep_going,
+        timings,
+        reinstall,
+        dry_run,
+        only,
+    } = options;
+    let mut output = "".to_string();
+    if dry_run {
+        return match deploy_plan(groups, exclude, force, reinstall, only, &mut output) {
+            Ok(plan) => {
+                output.push_str(&plan.to_string());
+                (output, ExitCode::SUCCESS)
+            }
+            Err(e) => (output, e),
+        };
     }
 
-    let run_deploy_steps = |step: DeployStages, group: Dotfile, output: &mut String| -> Result<(), ExitCode> {
+    let config = match Config::current(&mut output) {
+        Ok(config) => config,
+        Err(e) => return (output, e),
+    };
+
+    let groups = match groups_to_set(groups, exclude, &config, &mut output) {
+        Ok(groups) => groups,
+        Err(e) => return (output, e),
+    };
+
+    let has_dotfiles = |group: &str, output: &mut String| {
+        [
+            dotfiles::DotfileType::Configs,
+            dotfiles::DotfileType::Decrypted,
+        ]
+        .into_iter()
+        .any(|dtype| dotfiles::dotfile_contains(dtype, group, output))
+    };
+
+    let run_deploy_steps = |step: DeployStages,
+                            group: Dotfile,
+                            timings: &mut GroupTimings,
+                            output: &mut String|
+     -> Result<(), ExitCode> {
         if !group.is_valid_target() {
-            output.push_str("Not a valid targit");
+            output.push_str(&format!("{} is not a valid target\n", group.group_name));
             return Err(ExitCode::FAILURE);
         }
 
-        for i in step {
+        let _span = tracing::info_span!("deploy", group = group.group_name).entered();
+        let mut skip_symlink = false;
+        for i in step.filter(|&step| only.includes(step)) {
+            let _step_span = tracing::info_span!("step", step = ?i).entered();
+            let start = Instant::now();
+
             match i {
                 DeployStep::Initialize => return Ok(()),
 
                 DeployStep::PreHook => {
-                    run_hook(&group.group_name, DeployStep::PreHook, output)?;
+                    let cwd = hook_working_dir(&group.group_name, config.hooks_cwd, output)?;
+                    let symlink_phase = config.symlink_phase(&group.group_name);
+                    skip_symlink = run_hook(
+                        &group.group_name,
+                        DeployStep::PreHook,
+                        symlink_phase,
+                        &cwd,
+                        output,
+                    )?;
+                }
+
+                DeployStep::Symlink if skip_symlink => {
+                    print_info_box("Skipping symlinks", &group.group_name, output);
+                    continue;
                 }
 
+                // groups that only have hooks have nothing to symlink
+                DeployStep::Symlink if !has_dotfiles(&group.group_name, output) => continue,
+
                 DeployStep::Symlink => {
                     print_info_box(
                         "Symlinking group",
                         group.group_name.to_string().as_str(),
                         output,
                     );
-                    output.push_str(&symlinks::add_cmd(groups, exclude, force, adopt).0);
+                    let group_name = [group.group_name.clone()];
+                    let (add_output, add_code) = symlinks::add_cmd(
+                        &group_name,
+                        exclude,
+                        symlinks::AddOptions {
+                            on_conflict: if force {
+                                symlinks::ConflictPolicy::Overwrite
+                            } else {
+                                symlinks::ConflictPolicy::Skip
+                            },
+                            adopt,
+                            ..Default::default()
+                        },
+                    );
+                    output.push_str(&add_output);
+                    if add_code != ExitCode::SUCCESS {
+                        return Err(add_code);
+                    }
                 }
 
-                DeployStep::PostHook => { run_hook(&group.group_name, DeployStep::PostHook, output)?; },
+                DeployStep::Install => {
+                    let marker = install_marker(&group.group_name, output)?;
+                    if reinstall && marker.exists() {
+                        if let Err(e) = fs::remove_file(&marker) {
+                            output
+                                .push_str(&format!("Couldn't remove {}: {e}\n", marker.display()));
+                            return Err(ReturnCode::from(e).into());
+                        }
+                    }
+
+                    let symlink_phase = config.symlink_phase(&group.group_name);
+                    let has_install_hooks =
+                        !group_hooks(&group.group_name, i, symlink_phase, output)?.is_empty();
+                    if marker.exists() || !has_install_hooks {
+                        continue;
+                    }
+
+                    let cwd = hook_working_dir(&group.group_name, config.hooks_cwd, output)?;
+                    run_hook(
+                        &group.group_name,
+                        DeployStep::Install,
+                        symlink_phase,
+                        &cwd,
+                        output,
+                    )?;
+
+                    if let Err(e) = mark_installed(&marker) {
+                        output.push_str(&format!("Couldn't create {}: {e}\n", marker.display()));
+                        return Err(ReturnCode::from(e).into());
+                    }
+                }
+
+                DeployStep::PostHook => {
+                    let cwd = hook_working_dir(&group.group_name, config.hooks_cwd, output)?;
+                    let symlink_phase = config.symlink_phase(&group.group_name);
+                    run_hook(
+                        &group.group_name,
+                        DeployStep::PostHook,
+                        symlink_phase,
+                        &cwd,
+                        output,
+                    )?;
+                }
+            }
+
+            let elapsed = Some(start.elapsed());
+            match i {
+                DeployStep::Initialize => (),
+                DeployStep::PreHook => timings.prehook = elapsed,
+                DeployStep::Symlink => timings.symlink = elapsed,
+                DeployStep::Install => timings.install = elapsed,
+                DeployStep::PostHook => timings.posthook = elapsed,
             }
         }
 
         Ok(())
     };
 
-    let hooks_dir = match dotfiles::get_dotfiles_path(&mut output) {
-        Ok(dir) => dir.join("Hooks"),
+    let dotfiles_dir = match dotfiles::get_dotfiles_path(&mut output) {
+        Ok(dir) => dir,
         Err(e) => {
             output.push_str(&e.to_string());
             return (output, ReturnCode::NoSetupFolder.into());
         }
     };
+    let hooks_dir = dotfiles::DotfileType::Hooks.dir_in(&dotfiles_dir);
+
+    // subgroups are only split off inside of the directories that hold them, so each group is
+    // looked up where it exists, eg: `editors/nvim` may only have configs
+    let group_path = |group: &str| {
+        [
+            dotfiles::DotfileType::Configs,
+            dotfiles::DotfileType::Decrypted,
+            dotfiles::DotfileType::Hooks,
+        ]
+        .into_iter()
+        .map(|dtype| dtype.dir_in(&dotfiles_dir).join(group))
+        .find(|path| path.exists())
+        .unwrap_or_else(|| hooks_dir.join(group))
+    };
 
-    if groups.contains(&'*'.to_string()) {
-        for folder in fs::read_dir(hooks_dir).unwrap() {
-            let folder = folder.unwrap().path();
-            let Ok(group) = Dotfile::try_from(folder.clone()) else {
-                output.push_str("Got an invalid group: ");
-                output.push_str(folder.to_str().unwrap_or(""));
-                return (output, ExitCode::FAILURE);
-            };
-            match run_deploy_steps(DeployStages::new(), group, &mut output) {
-                Ok(()) => return (output, ExitCode::SUCCESS),
-                Err(e) => return (output, e),
-            };
+    let run_global_hooks = |hook_type: DeployStep, output: &mut String| -> Result<(), ExitCode> {
+        if !only.includes(hook_type) || !hooks_dir.join(GLOBAL_HOOKS_GROUP).is_dir() {
+            return Ok(());
         }
 
-        return (output, ExitCode::SUCCESS);
+        let cwd = hook_working_dir(GLOBAL_HOOKS_GROUP, config.hooks_cwd, output)?;
+        let symlink_phase = config.symlink_phase(GLOBAL_HOOKS_GROUP);
+        // there's nothing to symlink so asking to skip it doesn't change anything
+        run_hook(GLOBAL_HOOKS_GROUP, hook_type, symlink_phase, &cwd, output).map(|_| ())
+    };
+
+    if let Err(e) = run_global_hooks(DeployStep::PreHook, &mut output) {
+        return (output, e);
     }
 
-    for group in groups {
-        let hook_path = hooks_dir.join(group);
-        let Ok(group) = Dotfile::try_from(hook_path.clone()) else {
-            output.push_str("Got an invalid group: ");
-            output.push_str(hook_path.to_str().unwrap_or(""));
+    let mut failed_groups = Vec::new();
+    let mut group_timings = Vec::new();
+    let mut exit_code = ExitCode::SUCCESS;
+    for group in &groups {
+        let group_path = group_path(group);
+        let Ok(dotfile) = Dotfile::try_from(group_path.clone()) else {
+            output.push_str(&format!("Got an invalid group: {}\n", group_path.display()));
             return (output, ExitCode::FAILURE);
         };
-        match run_deploy_steps(DeployStages::new(), group, &mut output) {
-            Ok(()) => return (output, ExitCode::SUCCESS),
-            Err(e) => return (output, e),
+        let mut timings = GroupTimings {
+            group: group.clone(),
+            ..Default::default()
         };
+        let result = run_deploy_steps(DeployStages::new(), dotfile, &mut timings, &mut output);
+        group_timings.push(timings);
+
+        if let Err(e) = result {
+            if !keep_going {
+                return (output, e);
+            }
+
+            output.push_str(&format!(
+                "Failed to set up {group}, continuing with the remaining groups.\n"
+            ));
+            failed_groups.push(group);
+            exit_code = e;
+        }
+    }
+
+    if let Err(e) = run_global_hooks(DeployStep::PostHook, &mut output) {
+        exit_code = e;
+    }
+
+    if !failed_groups.is_empty() {
+        output.push_str("The following groups failed to be set up:\n");
+        for group in failed_groups {
+            output.push_str(&format!("\t{group}\n"));
+        }
     }
 
-    (output, ExitCode::SUCCESS)
+    if timings && !group_timings.is_empty() {
+        let mut table = tabled::Table::new(group_timings);
+        table.with(tabled::Style::rounded());
+        output.push_str(&format!("{table}\n"));
+    }
+
+    (output, exit_code)
 }
 
 #[cfg(test)]
@@ -231,6 +872,439 @@ mod tests {
         steps.next();
         assert!(steps.0 == DeployStep::Symlink);
         steps.next();
+        assert!(steps.0 == DeployStep::Install);
+        steps.next();
         assert!(steps.0 == DeployStep::PostHook);
     }
+
+    #[test]
+    fn platform_specific_hooks() {
+        assert!(is_hook_for_current_platform("pre_setup.sh"));
+        assert!(is_hook_for_current_platform("post_setup"));
+        assert_eq!(
+            is_hook_for_current_platform("pre_setup_windows.ps1"),
+            cfg!(target_family = "windows")
+        );
+        assert_eq!(
+            is_hook_for_current_platform("pre_setup_linux.sh"),
+            cfg!(target_os = "linux")
+        );
+        assert_eq!(
+            is_hook_for_current_platform("post_setup_unix"),
+            cfg!(target_family = "unix")
+        );
+    }
+
+    #[test]
+    fn set_group_without_hooks() {
+        let _lock = dotfiles::test_lock();
+        crate::fileops::init_cmd();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&mut String::new()).unwrap();
+        let group_dir = dotfiles_dir.join("Configs").join("NoHooks");
+        fs::create_dir_all(&group_dir).unwrap();
+        fs::write(group_dir.join("no_hooks_file"), "dotfile").unwrap();
+        assert!(!dotfiles_dir.join("Hooks").join("NoHooks").exists());
+
+        let (_, exit_code) = set_cmd(&["NoHooks".into()], &[], SetOptions::default());
+        assert_eq!(exit_code, ExitCode::SUCCESS);
+
+        let target = dirs::home_dir().unwrap().join("no_hooks_file");
+        assert_eq!(
+            fs::read_link(&target).unwrap(),
+            group_dir.join("no_hooks_file")
+        );
+
+        symlinks::remove_cmd(&["NoHooks".into()], &[], false);
+        fs::remove_dir_all(dotfiles_dir).unwrap();
+    }
+
+    #[test]
+    fn hooks_run_from_target_dir() {
+        let _lock = dotfiles::test_lock();
+        crate::fileops::init_cmd();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&mut String::new()).unwrap();
+        let group_dir = dotfiles_dir.join("Configs").join("CwdGroup");
+        let target_dir = dotfiles_dir.parent().unwrap().join("cwd_target");
+        fs::create_dir_all(&group_dir).unwrap();
+        fs::create_dir_all(target_dir.join("prefixed")).unwrap();
+        fs::write(
+            group_dir.join(dotfiles::TARGET_OVERRIDE_FILE),
+            target_dir.to_str().unwrap(),
+        )
+        .unwrap();
+        fs::write(group_dir.join(dotfiles::PREFIX_FILE), "prefixed").unwrap();
+
+        let cwd = hook_working_dir("CwdGroup", HooksCwd::Target, &mut String::new());
+        // groups without configs run their hooks from $HOME
+        let hooks_only_cwd = hook_working_dir("HooksOnly", HooksCwd::Target, &mut String::new());
+        fs::remove_dir_all(dotfiles_dir).unwrap();
+        fs::remove_dir_all(&target_dir).unwrap();
+
+        assert_eq!(cwd.unwrap(), target_dir.join("prefixed"));
+        assert_eq!(hooks_only_cwd.unwrap(), dirs::home_dir().unwrap());
+    }
+
+    #[test]
+    fn set_subgroup_without_hooks() {
+        let _lock = dotfiles::test_lock();
+        crate::fileops::init_cmd();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&mut String::new()).unwrap();
+        let editors_dir = dotfiles_dir.join("Configs").join("editors");
+        fs::create_dir_all(editors_dir.join("nvim")).unwrap();
+        fs::create_dir_all(editors_dir.join("emacs")).unwrap();
+        fs::write(editors_dir.join("nvim").join("subgroup_nvim_file"), "").unwrap();
+        fs::write(editors_dir.join("emacs").join("subgroup_emacs_file"), "").unwrap();
+        fs::write(
+            dotfiles_dir.join(crate::config::CONFIG_FILE),
+            "recursive_groups = true\n",
+        )
+        .unwrap();
+        assert!(!dotfiles_dir.join("Hooks").join("editors").exists());
+
+        let (_, exit_code) = set_cmd(&["editors/nvim".into()], &[], SetOptions::default());
+        let home_dir = dirs::home_dir().unwrap();
+        let nvim_linked = home_dir.join("subgroup_nvim_file").is_symlink();
+        let emacs_linked = home_dir.join("subgroup_emacs_file").is_symlink();
+
+        symlinks::remove_cmd(&["editors".into()], &[], false);
+        fs::remove_dir_all(dotfiles_dir).unwrap();
+
+        assert_eq!(exit_code, ExitCode::SUCCESS);
+        assert!(nvim_linked);
+        assert!(!emacs_linked);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn prehook_skips_symlinking() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let _lock = dotfiles::test_lock();
+        crate::fileops::init_cmd();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&mut String::new()).unwrap();
+        let group_dir = dotfiles_dir.join("Configs").join("SkipLink");
+        let hooks_dir = dotfiles_dir.join("Hooks").join("SkipLink");
+        fs::create_dir_all(&group_dir).unwrap();
+        fs::create_dir_all(&hooks_dir).unwrap();
+        fs::write(group_dir.join("skipped_file"), "dotfile").unwrap();
+
+        let hooks = [
+            (
+                "pre.sh",
+                format!("#!/bin/sh\nexit {SKIP_SYMLINK_EXIT_CODE}\n"),
+            ),
+            ("post.sh", "#!/bin/sh\ntouch \"$0.ran\"\n".to_string()),
+        ];
+        for (name, script) in hooks {
+            let hook = hooks_dir.join(name);
+            fs::write(&hook, script).unwrap();
+            fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        let (_, exit_code) = set_cmd(&["SkipLink".into()], &[], SetOptions::default());
+        assert_eq!(exit_code, ExitCode::SUCCESS);
+        assert!(!dirs::home_dir().unwrap().join("skipped_file").exists());
+        assert!(hooks_dir.join("post.sh.ran").exists());
+
+        fs::remove_dir_all(dotfiles_dir).unwrap();
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn excluded_groups_arent_hooked() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let _lock = dotfiles::test_lock();
+        crate::fileops::init_cmd();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&mut String::new()).unwrap();
+        let group_dir = dotfiles_dir.join("Configs").join("Excluded");
+        let hooks_dir = dotfiles_dir.join("Hooks").join("Excluded");
+        fs::create_dir_all(&group_dir).unwrap();
+        fs::create_dir_all(&hooks_dir).unwrap();
+        fs::write(group_dir.join("excluded_file"), "dotfile").unwrap();
+
+        for name in ["pre.sh", "install.sh", "post.sh"] {
+            let hook = hooks_dir.join(name);
+            fs::write(&hook, "#!/bin/sh\ntouch \"$0.ran\"\n").unwrap();
+            fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        let (_, exit_code) = set_cmd(&["*".into()], &["Excluded".into()], SetOptions::default());
+        let linked = dirs::home_dir().unwrap().join("excluded_file").is_symlink();
+        let hooked = ["pre.sh", "install.sh", "post.sh"]
+            .iter()
+            .any(|name| hooks_dir.join(format!("{name}.ran")).exists());
+        fs::remove_dir_all(dotfiles_dir).unwrap();
+
+        assert_eq!(exit_code, ExitCode::SUCCESS);
+        assert!(!linked);
+        assert!(!hooked);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn install_hooks_run_once() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let _lock = dotfiles::test_lock();
+        crate::fileops::init_cmd();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&mut String::new()).unwrap();
+        let hooks_dir = dotfiles_dir.join("Hooks").join("InstallOnce");
+        fs::create_dir_all(&hooks_dir).unwrap();
+
+        // every run appends a line so that the runs can be counted, to a file that doesn't look
+        // like an install hook itself
+        let hook = hooks_dir.join("install.sh");
+        fs::write(&hook, "#!/bin/sh\necho ran >> \"$(dirname \"$0\")/runs\"\n").unwrap();
+        fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).unwrap();
+        let runs = || {
+            fs::read_to_string(hooks_dir.join("runs"))
+                .unwrap_or_default()
+                .lines()
+                .count()
+        };
+
+        let set = |reinstall| {
+            let group = ["InstallOnce".to_string()];
+            let (_, exit_code) = set_cmd(
+                &group,
+                &[],
+                SetOptions {
+                    reinstall,
+                    ..Default::default()
+                },
+            );
+            assert_eq!(exit_code, ExitCode::SUCCESS);
+        };
+
+        set(false);
+        assert_eq!(runs(), 1);
+        assert!(dotfiles_dir.join(".tuckr/installed/InstallOnce").exists());
+        assert_eq!(
+            fs::read_to_string(dotfiles_dir.join(".tuckr/.gitignore")).unwrap(),
+            "*\n"
+        );
+
+        set(false);
+        assert_eq!(runs(), 1);
+
+        set(true);
+        assert_eq!(runs(), 2);
+
+        fs::remove_dir_all(dotfiles_dir).unwrap();
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn set_only_hooks_or_symlinks() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let _lock = dotfiles::test_lock();
+        crate::fileops::init_cmd();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&mut String::new()).unwrap();
+        let group_dir = dotfiles_dir.join("Configs").join("HooksOrLinks");
+        let hooks_dir = dotfiles_dir.join("Hooks").join("HooksOrLinks");
+        fs::create_dir_all(&group_dir).unwrap();
+        fs::create_dir_all(&hooks_dir).unwrap();
+        fs::write(group_dir.join("hooks_or_links_file"), "dotfile").unwrap();
+
+        let hook = hooks_dir.join("pre.sh");
+        fs::write(&hook, "#!/bin/sh\necho ran >> \"$(dirname \"$0\")/runs\"\n").unwrap();
+        fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).unwrap();
+        let runs = || {
+            fs::read_to_string(hooks_dir.join("runs"))
+                .unwrap_or_default()
+                .lines()
+                .count()
+        };
+
+        let group = ["HooksOrLinks".to_string()];
+        let set = |only| {
+            let (_, exit_code) = set_cmd(
+                &group,
+                &[],
+                SetOptions {
+                    only,
+                    ..Default::default()
+                },
+            );
+            assert_eq!(exit_code, ExitCode::SUCCESS);
+        };
+        let target = dirs::home_dir().unwrap().join("hooks_or_links_file");
+
+        set(DeployOnly::Symlinks);
+        let linked_without_hooks = target.is_symlink();
+        let runs_without_hooks = runs();
+        symlinks::remove_cmd(&group, &[], false);
+
+        set(DeployOnly::Hooks);
+        let linked_with_hooks_only = target.is_symlink();
+        let runs_with_hooks_only = runs();
+        symlinks::remove_cmd(&group, &[], false);
+        fs::remove_dir_all(dotfiles_dir).unwrap();
+
+        assert!(linked_without_hooks);
+        assert_eq!(runs_without_hooks, 0);
+        assert!(!linked_with_hooks_only);
+        assert_eq!(runs_with_hooks_only, 1);
+    }
+
+    #[test]
+    fn plan_has_no_side_effects() {
+        let _lock = dotfiles::test_lock();
+        crate::fileops::init_cmd();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&mut String::new()).unwrap();
+        let group_dir = dotfiles_dir.join("Configs").join("Planned");
+        let hooks_dir = dotfiles_dir.join("Hooks").join("Planned");
+        fs::create_dir_all(&group_dir).unwrap();
+        fs::create_dir_all(&hooks_dir).unwrap();
+        fs::write(group_dir.join("planned_file"), "dotfile").unwrap();
+        fs::write(hooks_dir.join("pre.sh"), "").unwrap();
+        fs::write(hooks_dir.join("install.sh"), "").unwrap();
+
+        let plan = deploy_plan(
+            &["Planned".into()],
+            &[],
+            false,
+            false,
+            DeployOnly::Everything,
+            &mut String::new(),
+        )
+        .unwrap();
+        let target = dirs::home_dir().unwrap().join("planned_file");
+        assert_eq!(
+            plan.groups,
+            [GroupPlan {
+                group: "Planned".into(),
+                prehooks: vec![hooks_dir.join("pre.sh")],
+                links: vec![PlannedLink {
+                    dotfile: group_dir.join("planned_file"),
+                    target: target.clone(),
+                }],
+                install_hooks: vec![hooks_dir.join("install.sh")],
+                posthooks: Vec::new(),
+            }]
+        );
+        assert!(!target.exists());
+
+        let (output, exit_code) = set_cmd(
+            &["Planned".into()],
+            &[],
+            SetOptions {
+                dry_run: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(exit_code, ExitCode::SUCCESS);
+        assert!(output.contains(&format!("link {}", target.display())));
+        assert!(!target.exists());
+
+        fs::remove_dir_all(dotfiles_dir).unwrap();
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn wildcard_skips_other_platforms() {
+        let _lock = dotfiles::test_lock();
+        crate::fileops::init_cmd();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&mut String::new()).unwrap();
+        let group_dir = dotfiles_dir.join("Configs").join("OtherPlatform_windows");
+        fs::create_dir_all(&group_dir).unwrap();
+        fs::write(group_dir.join("other_platform_file"), "").unwrap();
+
+        let all = ["*".to_string()];
+        let plan = deploy_plan(
+            &all,
+            &[],
+            false,
+            false,
+            DeployOnly::Everything,
+            &mut String::new(),
+        );
+        let (_, exit_code) = set_cmd(&all, &[], SetOptions::default());
+        let (_, named_exit_code) = set_cmd(
+            &["OtherPlatform_windows".into()],
+            &[],
+            SetOptions::default(),
+        );
+        fs::remove_dir_all(dotfiles_dir).unwrap();
+
+        assert!(plan.unwrap().groups.is_empty());
+        assert_eq!(exit_code, ExitCode::SUCCESS);
+        assert_eq!(named_exit_code, ExitCode::FAILURE);
+    }
+
+    #[test]
+    fn set_fails_when_symlinking_fails() {
+        let _lock = dotfiles::test_lock();
+        crate::fileops::init_cmd();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&mut String::new()).unwrap();
+        let group_dir = dotfiles_dir.join("Configs").join("Unlinkable");
+        fs::create_dir_all(&group_dir).unwrap();
+        // its target can't be known without the variable
+        fs::write(group_dir.join("${TUCKR_TEST_UNSET_VAR}"), "").unwrap();
+
+        let (_, exit_code) = set_cmd(&["Unlinkable".into()], &[], SetOptions::default());
+        assert_eq!(exit_code, ExitCode::from(ReturnCode::CouldntSymlinkFile));
+
+        fs::remove_dir_all(dotfiles_dir).unwrap();
+    }
+
+    #[test]
+    fn info_box_fits_width() {
+        let content = "a_group_with_a_name_that_goes_on hooks/that/keep/going/and/going";
+        let hook_box = super::info_box("Running Hook", content, 40);
+        assert!(hook_box.lines().all(|line| line.chars().count() <= 40));
+        assert!(hook_box.contains("Running Hook"));
+
+        // short content is left as is
+        let hook_box = super::info_box("Running Hook", "zsh", 40);
+        assert_eq!(hook_box.lines().count(), 3);
+        assert!(hook_box.contains("zsh"));
+    }
+
+    #[test]
+    fn hook_phases() {
+        assert_eq!(
+            HookPhase::from_filename("pre_setup.sh"),
+            Some(HookPhase::Pre)
+        );
+        assert_eq!(HookPhase::from_filename("post.sh"), Some(HookPhase::Post));
+        assert_eq!(
+            HookPhase::from_filename("10-deps.sh"),
+            Some(HookPhase::Numbered(10))
+        );
+        assert_eq!(
+            HookPhase::from_filename("90_cleanup"),
+            Some(HookPhase::Numbered(90))
+        );
+        assert_eq!(HookPhase::from_filename("10deps.sh"), None);
+        assert_eq!(HookPhase::from_filename("setup.sh"), None);
+        assert_eq!(
+            HookPhase::from_filename("install_plugins.sh"),
+            Some(HookPhase::Install)
+        );
+
+        let mut phases = vec![
+            HookPhase::Post,
+            HookPhase::Numbered(90),
+            HookPhase::Pre,
+            HookPhase::Numbered(10),
+        ];
+        phases.sort();
+        assert_eq!(
+            phases,
+            [
+                HookPhase::Pre,
+                HookPhase::Numbered(10),
+                HookPhase::Numbered(90),
+                HookPhase::Post
+            ]
+        );
+
+        assert!(HookPhase::Numbered(10).runs_on(&DeployStep::PreHook, 50));
+        assert!(!HookPhase::Numbered(10).runs_on(&DeployStep::PostHook, 50));
+        assert!(HookPhase::Numbered(50).runs_on(&DeployStep::PostHook, 50));
+        assert!(!HookPhase::Pre.runs_on(&DeployStep::PostHook, 50));
+    }
 }
@@ -9,27 +9,109 @@
 //! groups which contains all user scripts, configs and scripts, these are used to label them on tuckr
 //! so you can add or remove them anytime
 
+pub mod config;
 pub mod dotfiles;
 pub mod fileops;
 pub mod hooks;
 pub mod secrets;
 pub mod symlinks;
+pub mod templates;
 
 use clap::Parser;
-use dotfiles::ReturnCode;
 use std::process::ExitCode;
 
 #[derive(Debug, Parser)]
 #[command(about, author, version, propagate_version = true)]
-pub enum Cli {
+pub struct Cli {
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    /// When to use colors, `auto` only uses them when printing to a terminal
+    color: ColorChoice,
+
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    /// Log what tuckr is doing to stderr, repeat it for more detail
+    verbose: u8,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+/// What `add` does with the files that are in the way of dotfiles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ConflictPolicy {
+    /// Leave them and don't symlink their dotfiles
+    Skip,
+    /// Replace them with symlinks
+    Overwrite,
+    /// Move them out of the way, see `--backup-dir`
+    Backup,
+    /// Don't symlink the group and exit with an error
+    Fail,
+}
+
+impl From<ConflictPolicy> for symlinks::ConflictPolicy {
+    fn from(policy: ConflictPolicy) -> Self {
+        match policy {
+            ConflictPolicy::Skip => symlinks::ConflictPolicy::Skip,
+            ConflictPolicy::Overwrite => symlinks::ConflictPolicy::Overwrite,
+            ConflictPolicy::Backup => symlinks::ConflictPolicy::Backup,
+            ConflictPolicy::Fail => symlinks::ConflictPolicy::Fail,
+        }
+    }
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum Command {
     #[command(alias = "s")]
     /// Get dotfiles' symlinking status (alias: s)
     Status {
         #[arg(value_name = "group")]
         groups: Option<Vec<String>>,
+
+        #[arg(long, requires = "groups")]
+        /// Show the status of every file in the groups
+        files: bool,
+
+        #[arg(long, value_name = "group", conflicts_with_all = ["groups", "files"])]
+        /// Show which file is keeping a group from being symlinked
+        explain: Option<String>,
+
+        #[arg(long, conflicts_with_all = ["files", "explain"])]
+        /// Also list every symlink along with the dotfile it points to
+        links: bool,
+
+        #[arg(long, conflicts_with_all = ["files", "explain"])]
+        /// Also show groups that target another platform
+        all: bool,
+
+        #[arg(long, conflicts_with_all = ["files", "explain"])]
+        /// Show how conflicting files differ from the dotfiles that would replace them
+        diff: bool,
+
+        #[arg(long, conflicts_with_all = ["files", "explain", "links", "diff"])]
+        /// Print nothing and only exit successfully if everything is symlinked
+        check: bool,
+
+        #[arg(long, conflicts_with_all = ["files", "explain", "links", "diff", "check"])]
+        /// Show the configs, hooks and secrets of every group in a single table
+        full: bool,
+
+        #[arg(
+            long,
+            conflicts_with_all = ["groups", "files", "explain", "links", "all", "diff", "check", "full"]
+        )]
+        /// Only check the groups that changed since dotfiles were last added or removed
+        fast: bool,
     },
 
     #[command(alias = "a")]
+    #[command(group = clap::ArgGroup::new("conflict_handling").args(["force", "on_conflict"]))]
     /// Deploy dotfiles for the supplied groups (alias: a)
     Add {
         #[arg(required = true, value_name = "group")]
@@ -39,13 +121,47 @@ pub enum Cli {
         /// Exclude certain groups from being added
         exclude: Vec<String>,
 
-        #[arg(short, long)]
-        /// Override conflicting dotfiles
+        #[arg(short, long, conflicts_with = "on_conflict")]
+        /// Override conflicting dotfiles, same as `--on-conflict overwrite`
         force: bool,
 
+        #[arg(long, value_enum, value_name = "policy")]
+        /// What to do with the files that are in the way of dotfiles
+        on_conflict: Option<ConflictPolicy>,
+
         #[arg(short, long)]
         /// Adopt conflicting dotfiles
         adopt: bool,
+
+        #[arg(short, long, conflicts_with_all = ["force", "adopt", "on_conflict"])]
+        /// Ask what to do with each conflicting dotfile
+        interactive: bool,
+
+        #[arg(long, conflicts_with = "only_dirs")]
+        /// Only symlink files, directories are never symlinked as a whole
+        only_files: bool,
+
+        #[arg(long)]
+        /// Only symlink directories
+        only_dirs: bool,
+
+        #[arg(long, value_name = "pattern")]
+        /// Don't symlink the files matching this gitignore style pattern, eg: `init.lua`
+        exclude_file: Vec<String>,
+
+        #[arg(long, value_name = "dir")]
+        /// Move backed up files into this directory, keeping their path relative to $HOME
+        backup_dir: Option<std::path::PathBuf>,
+
+        #[arg(last = true, value_name = "file", requires = "conflict_handling")]
+        /// Only handle these conflicting files with --force or --on-conflict, eg: `tuckr add -f nvim -- ~/.vimrc`
+        force_files: Vec<std::path::PathBuf>,
+    },
+
+    /// Watch groups, symlinking files as they're added and removing the symlinks of deleted ones
+    Watch {
+        #[arg(required = true, value_name = "group")]
+        groups: Vec<String>,
     },
 
     /// Remove dotfiles for the supplied groups
@@ -56,13 +172,27 @@ pub enum Cli {
         #[arg(short, long, value_name = "group", use_value_delimiter = true)]
         /// Exclude certain groups from being removed
         exclude: Vec<String>,
+
+        #[arg(long)]
+        /// Move the symlinks to the trash instead of deleting them
+        trash: bool,
+    },
+
+    /// Remove the symlinks to files that no longer exist and symlink new files
+    Sync {
+        #[arg(required = true, value_name = "group")]
+        groups: Vec<String>,
     },
 
     /// Setup groups and run their hooks
     Set {
-        #[arg(required = true, value_name = "group")]
+        #[arg(required_unless_present = "profile", value_name = "group")]
         groups: Vec<String>,
 
+        #[arg(short, long)]
+        /// Setup the groups listed in a profile from tuckr.toml
+        profile: Option<String>,
+
         #[arg(short, long, value_name = "group", use_value_delimiter = true)]
         /// Exclude certain groups from being added and hooked
         exclude: Vec<String>,
@@ -74,14 +204,71 @@ pub enum Cli {
         #[arg(short, long)]
         /// Adopt conflicting dotfiles
         adopt: bool,
+
+        #[arg(short, long)]
+        /// Keep setting up the remaining groups when one of them fails
+        keep_going: bool,
+
+        #[arg(long)]
+        /// Print how long the hooks and symlinking took for each group
+        timings: bool,
+
+        #[arg(long)]
+        /// Run the install hooks again, even for groups that were set up before
+        reinstall: bool,
+
+        #[arg(short = 'n', long, conflicts_with_all = ["adopt", "keep_going", "timings"])]
+        /// List the hooks that would run and the files that would be symlinked without doing it
+        dry_run: bool,
+
+        #[arg(long, conflicts_with_all = ["hooks_only", "reinstall"])]
+        /// Only symlink the groups without running any of their hooks
+        no_hooks: bool,
+
+        #[arg(long, conflicts_with_all = ["force", "adopt"])]
+        /// Only run the hooks of the groups without symlinking them
+        hooks_only: bool,
     },
 
     #[command(alias = "e")]
     /// Encrypt files and move them to dotfiles/Secrets (alias: e)
     Encrypt {
         group: String,
-        #[arg(required = true, value_name = "FILE")]
+        #[arg(required_unless_present = "group_configs", value_name = "FILE")]
         dotfiles: Vec<String>,
+
+        #[arg(short, long)]
+        /// Store files relative to `/` instead of $HOME, eg: for files in /etc
+        root: bool,
+
+        #[arg(long)]
+        /// Read the password from the first line of stdin
+        password_stdin: bool,
+
+        #[arg(long)]
+        /// Only encrypt files whose content differs from their stored secret
+        if_changed: bool,
+
+        #[arg(long, conflicts_with = "password_stdin")]
+        /// Encrypt stdin instead, FILE is the path it will be decrypted to
+        stdin: bool,
+
+        #[arg(long, conflicts_with = "password_stdin")]
+        /// Read the password from the OS keyring, offering to save it there if it isn't yet
+        keyring: bool,
+
+        #[arg(long)]
+        /// Derive nonces from the files so that identical files always encrypt the same way.
+        /// This weakens the encryption and is only meant for tests
+        deterministic_nonce: bool,
+
+        #[arg(long, conflicts_with_all = ["dotfiles", "root", "if_changed", "stdin"])]
+        /// Encrypt every file of the group's Configs into its Secrets, deleting the plaintext
+        group_configs: bool,
+
+        #[arg(short, long, requires = "group_configs")]
+        /// Don't ask for confirmation before deleting the plaintext of --group-configs
+        yes: bool,
     },
 
     #[command(alias = "d")]
@@ -91,6 +278,38 @@ pub enum Cli {
         groups: Vec<String>,
         #[arg(short, long, value_name = "group", use_value_delimiter = true)]
         exclude: Vec<String>,
+
+        #[arg(short, long)]
+        /// How many files to decrypt at the same time, defaults to the number of CPUs
+        jobs: Option<std::num::NonZeroUsize>,
+
+        #[arg(long)]
+        /// Show how many files have been decrypted
+        progress: bool,
+
+        #[arg(long)]
+        /// Read the password from the first line of stdin
+        password_stdin: bool,
+
+        #[arg(long, conflicts_with = "password_stdin")]
+        /// Read the password from the OS keyring, offering to save it there if it isn't yet
+        keyring: bool,
+
+        #[arg(long, conflicts_with_all = ["exclude", "jobs", "progress"])]
+        /// Write a single secret to stdout, eg: `tuckr decrypt --stdout ssh/.ssh/id_rsa`
+        stdout: bool,
+
+        #[arg(short = 'n', long, conflicts_with_all = ["stdout", "jobs", "progress", "password_stdin", "keyring"])]
+        /// List where every secret would be decrypted to without writing anything
+        dry_run: bool,
+
+        #[arg(short, long, conflicts_with = "stdout")]
+        /// Decrypt secrets again even if they were already decrypted
+        force: bool,
+
+        #[arg(long, conflicts_with = "stdout")]
+        /// Decrypt into the dotfiles' Decrypted directory and symlink the secrets from there
+        symlink: bool,
     },
 
     /// Copy files into groups
@@ -98,61 +317,522 @@ pub enum Cli {
         group: String,
         #[arg(required = true)]
         files: Vec<String>,
+
+        #[arg(long, value_name = "duration", value_parser = fileops::parse_duration)]
+        /// Only copy files modified within this duration, eg: 2h, 3d
+        since: Option<std::time::Duration>,
+
+        #[arg(short, long)]
+        /// Replace the original files with symlinks to their copies
+        link: bool,
+
+        #[arg(long, value_name = "N")]
+        /// Only copy up to N levels deep when pushing directories
+        depth: Option<std::num::NonZeroUsize>,
+
+        #[arg(long, value_name = "dir")]
+        /// Push the whole directory the files are in up to this ancestor of theirs
+        parents: Option<std::path::PathBuf>,
     },
 
     /// Remove groups from dotfiles/Configs
     #[command(arg_required_else_help = true)]
-    Pop { groups: Vec<String> },
+    Pop {
+        groups: Vec<String>,
+
+        #[arg(long)]
+        /// Move the groups to the trash instead of deleting them
+        trash: bool,
+    },
+
+    /// Rename a group and redeploy it under its new name
+    Rename { old: String, new: String },
+
+    /// Bundle a group into a tarball, eg: `tuckr export vim vim.tar.gz`
+    Export {
+        group: String,
+        archive: std::path::PathBuf,
+
+        #[arg(long)]
+        /// Include the group's hooks
+        hooks: bool,
+
+        #[arg(long)]
+        /// Include the group's encrypted secrets
+        secrets: bool,
+    },
+
+    /// Unpack a tarball made by `tuckr export` into the dotfiles directory
+    Import { archive: std::path::PathBuf },
 
     /// List available hooks
-    LsHooks,
+    LsHooks {
+        #[arg(long)]
+        /// Print the hooks as JSON
+        json: bool,
+    },
 
     /// List stored secrets
-    LsSecrets,
+    LsSecrets {
+        #[arg(long)]
+        /// Print the secrets as JSON
+        json: bool,
+    },
 
     /// Initialize dotfile directory
     ///
     /// Creates files necessary to use Tuckr
     Init,
 
+    /// Clone a dotfiles repo into the dotfiles directory
+    Clone {
+        #[arg(value_name = "git-url")]
+        url: String,
+
+        #[arg(long)]
+        /// Create the Configs, Hooks and Secrets directories if the repo lacks them
+        init: bool,
+    },
+
     /// Convert a GNU Stow repo into Tuckr
     FromStow,
 
+    /// Check that the dotfiles repo contains the required groups
+    Validate {
+        #[arg(
+            long,
+            required = true,
+            value_name = "group",
+            use_value_delimiter = true
+        )]
+        /// Groups that must exist in dotfiles/Configs
+        require: Vec<String>,
+
+        #[arg(long)]
+        /// Also require the groups to have hooks
+        hooks: bool,
+
+        #[arg(long)]
+        /// Also require the groups to have secrets
+        secrets: bool,
+    },
+
+    /// Delete the backups made while resolving conflicts, eg: `tuckr clean-backups --older-than 30d`
+    CleanBackups {
+        #[arg(long, value_name = "duration", value_parser = fileops::parse_duration)]
+        /// Only delete backups older than this, eg: 12h, 30d
+        older_than: std::time::Duration,
+
+        #[arg(long, value_name = "dir")]
+        /// Also delete old backups inside of this directory, see `add --backup-dir`
+        backup_dir: Vec<std::path::PathBuf>,
+    },
+
+    /// List the files that are in the way of dotfiles, one per line
+    Conflicts {
+        #[arg(long)]
+        /// Print the conflicts as JSON
+        json: bool,
+    },
+
+    /// Check that symlinked groups haven't drifted, exits with an error if they have
+    Verify {
+        #[arg(value_name = "group")]
+        groups: Option<Vec<String>>,
+    },
+
+    /// Show everything about a group: its files, status, hooks and secrets
+    #[command(arg_required_else_help = true)]
+    Info { group: String },
+
+    /// Prints where dotfiles would be deployed to
+    #[command(arg_required_else_help = true)]
+    Target { files: Vec<String> },
+
+    /// Prints the detected OS and family along with every group suffix tuckr recognizes
+    Targets,
+
+    /// Explains what an exit code means, lists every exit code when none is given
+    ExplainCode { code: Option<u8> },
+
+    /// Returns the group deployed files are symlinked from, eg: `tuckr owns ~/.zshrc`
+    #[command(arg_required_else_help = true)]
+    Owns { paths: Vec<String> },
+
     /// Returns the group the files belongs to
     #[command(name = "groupis", arg_required_else_help = true)]
     GroupIs { files: Vec<String> },
+
+    /// Use a single secret without decrypting it to a file
+    #[command(subcommand)]
+    Secret(SecretCommand),
 }
 
+#[derive(Debug, clap::Subcommand)]
+pub enum SecretCommand {
+    /// Write a secret to stdout only, eg: `TOKEN=$(tuckr secret get api/token)` in a hook
+    #[command(arg_required_else_help = true)]
+    Get {
+        /// Path of the secret inside of dotfiles/Secrets, eg: `api/token`
+        secret: String,
+
+        #[arg(long)]
+        /// Read the password from the first line of stdin
+        password_stdin: bool,
+
+        #[arg(long, conflicts_with = "password_stdin")]
+        /// Read the password from the OS keyring, offering to save it there if it isn't yet
+        keyring: bool,
+    },
+}
+
+impl Command {
+    /// Returns true for commands that change the dotfiles directory or deploy dotfiles
+    ///
+    /// `watch` isn't one of them since it runs until it's stopped, it locks the dotfiles itself
+    /// whenever it changes them
+    fn mutates_dotfiles(&self) -> bool {
+        !matches!(
+            self,
+            Command::Status { .. }
+                | Command::Watch { .. }
+                | Command::Set { dry_run: true, .. }
+                | Command::Decrypt { dry_run: true, .. }
+                | Command::Decrypt { stdout: true, .. }
+                | Command::LsHooks { .. }
+                | Command::LsSecrets { .. }
+                | Command::Init
+                | Command::Clone { .. }
+                | Command::Target { .. }
+                | Command::Targets
+                | Command::ExplainCode { .. }
+                | Command::Validate { .. }
+                | Command::Verify { .. }
+                | Command::Conflicts { .. }
+                | Command::CleanBackups { .. }
+                | Command::Info { .. }
+                | Command::GroupIs { .. }
+                | Command::Owns { .. }
+                | Command::Export { .. }
+                | Command::Secret(_)
+        )
+    }
+}
+
+// main.rs doubles as the library root, so `main` is unused when building the lib target
+#[allow(dead_code)]
 fn main() -> ExitCode {
     let cli = Cli::parse();
+    match cli.color {
+        // detected from the terminal and NO_COLOR when colors are first used
+        ColorChoice::Auto => (),
+        ColorChoice::Always => owo_colors::set_override(true),
+        ColorChoice::Never => owo_colors::set_override(false),
+    }
+
+    // RUST_LOG takes precedence over -v, eg: `RUST_LOG=tuckr::hooks=trace`
+    let log_level = match cli.verbose {
+        0 => "warn",
+        1 => "info",
+        2 => "debug",
+        _ => "trace",
+    };
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(log_level)),
+        )
+        .with_writer(std::io::stderr)
+        .with_ansi(match cli.color {
+            ColorChoice::Auto => {
+                std::io::IsTerminal::is_terminal(&std::io::stderr())
+                    && std::env::var_os("NO_COLOR").is_none()
+            }
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        })
+        .without_time()
+        .init();
 
-    let exit_code: (String, ExitCode) = match cli {
-        Cli::Set {
+    // held until the command is done so that concurrent runs don't race each other
+    let _lock = if cli.command.mutates_dotfiles() {
+        let mut output = String::new();
+        match dotfiles::lock_dotfiles(&mut output) {
+            Ok(lock) => Some(lock),
+            Err(e) => {
+                print!("{output}");
+                return e;
+            }
+        }
+    } else {
+        None
+    };
+
+    // the config is loaded once for the whole command instead of once for every dotfile
+    if !matches!(
+        cli.command,
+        Command::Init | Command::Clone { .. } | Command::ExplainCode { .. }
+    ) {
+        let mut output = String::new();
+        if let Err(e) = config::Config::load_for_command(&mut output) {
+            print!("{output}");
+            return e;
+        }
+    }
+
+    // commands writing data to stdout print their messages to stderr so that they can be piped
+    let writes_to_stdout = matches!(
+        cli.command,
+        Command::Secret(_) | Command::Decrypt { stdout: true, .. }
+    );
+
+    let (output, exit_code) = match cli.command {
+        Command::Set {
+            mut groups,
+            mut exclude,
+            profile,
+            force,
+            adopt,
+            keep_going,
+            timings,
+            reinstall,
+            dry_run,
+            no_hooks,
+            hooks_only,
+        } => {
+            let only = if no_hooks {
+                hooks::DeployOnly::Symlinks
+            } else if hooks_only {
+                hooks::DeployOnly::Hooks
+            } else {
+                hooks::DeployOnly::Everything
+            };
+
+            let mut output = String::new();
+            match profile
+                .map(|p| config::expand_profile(&p, &mut groups, &mut exclude, &mut output))
+            {
+                Some(Err(e)) => (output, e),
+                _ => hooks::set_cmd(
+                    &groups,
+                    &exclude,
+                    hooks::SetOptions {
+                        force,
+                        adopt,
+                        keep_going,
+                        timings,
+                        reinstall,
+                        dry_run,
+                        only,
+                    },
+                ),
+            }
+        }
+
+        Command::Add {
             groups,
             exclude,
             force,
+            on_conflict,
             adopt,
-        } => hooks::set_cmd(&groups, &exclude, force, adopt),
+            interactive,
+            only_files,
+            only_dirs,
+            exclude_file,
+            backup_dir,
+            force_files,
+        } => {
+            let only = if only_files {
+                symlinks::LinkOnly::Files
+            } else if only_dirs {
+                symlinks::LinkOnly::Dirs
+            } else {
+                symlinks::LinkOnly::Everything
+            };
+
+            let on_conflict = match on_conflict {
+                Some(policy) => policy.into(),
+                None if force => symlinks::ConflictPolicy::Overwrite,
+                None => symlinks::ConflictPolicy::Skip,
+            };
 
-        Cli::Add {
+            symlinks::add_cmd(
+                &groups,
+                &exclude,
+                symlinks::AddOptions {
+                    on_conflict,
+                    adopt,
+                    interactive,
+                    only,
+                    exclude_files: &exclude_file,
+                    backup_dir: backup_dir.as_deref(),
+                    force_files: &force_files,
+                },
+            )
+        }
+
+        Command::Rm {
+            groups,
+            exclude,
+            trash,
+        } => symlinks::remove_cmd(&groups, &exclude, trash),
+        Command::Sync { groups } => symlinks::sync_cmd(&groups),
+        Command::Status {
+            explain: Some(group),
+            ..
+        } => symlinks::status_explain_cmd(&group),
+        Command::Status {
+            groups,
+            all,
+            check: true,
+            ..
+        } => symlinks::status_check_cmd(groups, all),
+        Command::Status {
+            groups: Some(groups),
+            files: true,
+            ..
+        } => symlinks::status_files_cmd(&groups),
+        Command::Status {
+            groups,
+            all,
+            full: true,
+            ..
+        } => symlinks::status_full_cmd(groups, all),
+        Command::Status { fast: true, .. } => symlinks::status_fast_cmd(),
+        Command::Status {
+            groups,
+            links,
+            all,
+            diff,
+            ..
+        } => symlinks::status_cmd(groups, links, all, diff),
+        Command::Watch { groups } => symlinks::watch_cmd(&groups),
+        Command::Encrypt {
+            group,
+            dotfiles,
+            root,
+            password_stdin,
+            if_changed,
+            stdin,
+            keyring,
+            deterministic_nonce,
+            group_configs,
+            yes,
+        } => {
+            let password = secrets::PasswordSource::from_flags(password_stdin, keyring);
+            if group_configs {
+                secrets::encrypt_group_configs_cmd(&group, password, yes, deterministic_nonce)
+            } else if stdin {
+                secrets::encrypt_stdin_cmd(
+                    &group,
+                    &dotfiles,
+                    root,
+                    password,
+                    if_changed,
+                    deterministic_nonce,
+                )
+            } else {
+                secrets::encrypt_cmd(
+                    &group,
+                    &dotfiles,
+                    root,
+                    password,
+                    if_changed,
+                    deterministic_nonce,
+                )
+            }
+        }
+        Command::Decrypt {
             groups,
             exclude,
+            jobs,
+            progress,
+            password_stdin,
+            keyring,
+            stdout,
+            dry_run,
             force,
-            adopt,
-        } => symlinks::add_cmd(&groups, &exclude, force, adopt),
-
-        Cli::Rm { groups, exclude } => symlinks::remove_cmd(&groups, &exclude),
-        Cli::Status { groups } => symlinks::status_cmd(groups),
-        Cli::Encrypt { group, dotfiles } => secrets::encrypt_cmd(&group, &dotfiles),
-        Cli::Decrypt { groups, exclude } => secrets::decrypt_cmd(&groups, &exclude),
-        Cli::FromStow => fileops::from_stow_cmd(),
-        Cli::Init => fileops::init_cmd(),
-        Cli::LsHooks => fileops::ls_hooks_cmd(),
-        Cli::LsSecrets => fileops::ls_secrets_cmd(),
-        Cli::Push { group, files } => fileops::push_cmd(group, &files),
-        Cli::Pop { groups } => fileops::pop_cmd(&groups),
-        Cli::GroupIs { files } => fileops::groupis_cmd(&files),
+            symlink,
+        } => {
+            let password = secrets::PasswordSource::from_flags(password_stdin, keyring);
+            if stdout {
+                secrets::decrypt_stdout_cmd(&groups, password)
+            } else {
+                secrets::decrypt_cmd(
+                    &groups,
+                    &exclude,
+                    secrets::DecryptOptions {
+                        jobs,
+                        progress,
+                        password,
+                        dry_run,
+                        force,
+                        symlink,
+                    },
+                )
+            }
+        }
+        Command::FromStow => fileops::from_stow_cmd(),
+        Command::Init => fileops::init_cmd(),
+        Command::Clone { url, init } => fileops::clone_cmd(&url, init),
+        Command::LsHooks { json } => fileops::ls_hooks_cmd(json),
+        Command::LsSecrets { json } => fileops::ls_secrets_cmd(json),
+        Command::Push {
+            group,
+            files,
+            since,
+            link,
+            depth,
+            parents,
+        } => fileops::push_cmd(
+            group,
+            &files,
+            since,
+            link,
+            depth.map(|d| d.get()),
+            parents.as_deref(),
+        ),
+        Command::Pop { groups, trash } => fileops::pop_cmd(&groups, trash),
+        Command::Rename { old, new } => fileops::rename_cmd(&old, &new),
+        Command::Export {
+            group,
+            archive,
+            hooks,
+            secrets,
+        } => fileops::export_cmd(&group, &archive, hooks, secrets),
+        Command::Import { archive } => fileops::import_cmd(&archive),
+        Command::GroupIs { files } => fileops::groupis_cmd(&files),
+        Command::Owns { paths } => fileops::owns_cmd(&paths),
+        Command::Target { files } => fileops::target_cmd(&files),
+        Command::Targets => fileops::targets_cmd(),
+        Command::ExplainCode { code } => fileops::explain_code_cmd(code),
+        Command::Info { group } => symlinks::info_cmd(&group),
+        Command::Verify { groups } => symlinks::verify_cmd(groups),
+        Command::Conflicts { json } => symlinks::conflicts_cmd(json),
+        Command::CleanBackups {
+            older_than,
+            backup_dir,
+        } => symlinks::clean_backups_cmd(older_than, &backup_dir),
+        Command::Validate {
+            require,
+            hooks,
+            secrets,
+        } => fileops::validate_cmd(&require, hooks, secrets),
+        Command::Secret(SecretCommand::Get {
+            secret,
+            password_stdin,
+            keyring,
+        }) => {
+            let password = secrets::PasswordSource::from_flags(password_stdin, keyring);
+            secrets::decrypt_stdout_cmd(&[secret], password)
+        }
     };
 
-    exit_code.1
+    if writes_to_stdout {
+        eprint!("{output}");
+    } else if !output.is_empty() {
+        print!("{output}");
+    }
+
+    exit_code
 }
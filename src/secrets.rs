@@ -1,195 +1,1566 @@
 //! Manages encrypted files
 //!
 //! Encrypts files into dotfiles/Secrets using the chacha20poly1305 algorithm
+//!
+//! Encrypted files are laid out as follows:
+//! - header: `TUCKR` magic bytes, a version byte, a flags byte and, if the file had any, its unix
+//!   permissions as 4 little endian bytes
+//! - nonce: 24 bytes
+//! - the encrypted contents, the header is authenticated alongside them
+//!
+//! Files created by older versions of tuckr have no header and always belong to $HOME
 
 use crate::dotfiles::{self, Dotfile, ReturnCode};
-use chacha20poly1305::{aead::Aead, AeadCore, KeyInit, XChaCha20Poly1305};
-use owo_colors::OwoColorize;
+use crate::fileops;
+use crate::symlinks;
+use chacha20poly1305::{
+    aead::{Aead, Payload},
+    AeadCore, KeyInit, XChaCha20Poly1305,
+};
+use hmac::{Hmac, Mac};
 use rand::rngs;
 use sha2::{Digest, Sha256};
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::io::{self, IsTerminal, Read, Write};
+use std::num::NonZeroUsize;
+use std::path::{Component, Path, PathBuf};
 use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
 use walkdir::WalkDir;
 
+const HEADER_MAGIC: &[u8] = b"TUCKR";
+const HEADER_VERSION: u8 = 1;
+const HEADER_LEN: usize = HEADER_MAGIC.len() + 2;
+/// Length of the permissions that can follow the header's flags
+const MODE_LEN: usize = 4;
+const NONCE_LEN: usize = 24;
+/// Length of the authentication tag appended to the encrypted contents
+const TAG_LEN: usize = 16;
+
+/// Header flag set when a secret is restored relative to `/` instead of $HOME
+const FLAG_TARGETS_ROOT: u8 = 0b1;
+/// Header flag set when the permissions of the encrypted file follow the flags
+const FLAG_MODE: u8 = 0b10;
+/// Permissions secrets are decrypted with when they weren't encrypted from a file, eg: from stdin
+const DEFAULT_MODE: u32 = 0o600;
+
+/// Metadata stored at the start of every encrypted file
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct SecretHeader {
+    targets_root: bool,
+    /// unix permissions the secret is decrypted with
+    mode: Option<u32>,
+}
+
+impl SecretHeader {
+    fn to_bytes(self) -> Vec<u8> {
+        let mut flags = 0;
+        if self.targets_root {
+            flags |= FLAG_TARGETS_ROOT;
+        }
+        if self.mode.is_some() {
+            flags |= FLAG_MODE;
+        }
+
+        let mut header = HEADER_MAGIC.to_vec();
+        header.push(HEADER_VERSION);
+        header.push(flags);
+        if let Some(mode) = self.mode {
+            header.extend_from_slice(&mode.to_le_bytes());
+        }
+        header
+    }
+
+    /// Splits an encrypted file into its header, the raw header bytes and the remaining content
+    fn split(file: &[u8]) -> (Self, &[u8], &[u8]) {
+        if file.len() < HEADER_LEN || !file.starts_with(HEADER_MAGIC) {
+            return (Self::default(), &[], file);
+        }
+
+        let flags = file[HEADER_LEN - 1];
+        let header_len = if flags & FLAG_MODE != 0 {
+            HEADER_LEN + MODE_LEN
+        } else {
+            HEADER_LEN
+        };
+        if file.len() < header_len {
+            return (Self::default(), &[], file);
+        }
+
+        let (header_bytes, rest) = file.split_at(header_len);
+        let header = SecretHeader {
+            targets_root: flags & FLAG_TARGETS_ROOT != 0,
+            mode: header_bytes
+                .get(HEADER_LEN..)
+                .and_then(|mode| mode.try_into().ok())
+                .map(u32::from_le_bytes),
+        };
+
+        (header, header_bytes, rest)
+    }
+
+    /// Reads only the header of an encrypted file and returns it along with its length, without
+    /// decrypting the file
+    fn read_with_len(path: &Path) -> io::Result<(Self, usize)> {
+        let mut header_bytes = Vec::with_capacity(HEADER_LEN + MODE_LEN);
+        fs::File::open(path)?
+            .take((HEADER_LEN + MODE_LEN) as u64)
+            .read_to_end(&mut header_bytes)?;
+        let (header, header_bytes, _) = Self::split(&header_bytes);
+        Ok((header, header_bytes.len()))
+    }
+
+    /// Reads only the header of an encrypted file, without decrypting it
+    fn read(path: &Path) -> io::Result<Self> {
+        Self::read_with_len(path).map(|(header, _)| header)
+    }
+
+    /// Returns how long the decrypted contents of an encrypted file are, without decrypting it
+    fn decrypted_len(path: &Path) -> io::Result<u64> {
+        let (_, header_len) = Self::read_with_len(path)?;
+        let overhead = header_len + NONCE_LEN + TAG_LEN;
+        Ok(fs::metadata(path)?.len().saturating_sub(overhead as u64))
+    }
+
+    /// Returns the directory the secret's path is relative to
+    fn target_base_dir(self) -> PathBuf {
+        if self.targets_root {
+            dotfiles::get_root_path()
+        } else {
+            dirs::home_dir().unwrap()
+        }
+    }
+}
+
+/// Returns the permissions a file is decrypted with, only unix files have them
+fn file_mode(metadata: &fs::Metadata) -> Option<u32> {
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        Some(metadata.permissions().mode() & 0o7777)
+    }
+
+    #[cfg(not(target_family = "unix"))]
+    {
+        _ = metadata;
+        None
+    }
+}
+
+/// Writes a decrypted secret, giving it the permissions it was encrypted with or `DEFAULT_MODE`
+///
+/// New files are created with those permissions so that they're never readable by anyone else,
+/// even for a moment, and are only moved into place once they've been fully written
+fn write_decrypted(target: &Path, contents: &[u8], mode: Option<u32>) -> io::Result<()> {
+    let mode = mode.unwrap_or(DEFAULT_MODE);
+    fileops::write_atomically(target, |temp| write_file_with_mode(temp, contents, mode))
+}
+
+fn write_file_with_mode(target: &Path, contents: &[u8], mode: u32) -> io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(mode);
+    }
+
+    options.open(target)?.write_all(contents)?;
+
+    // existing files and the umask can leave the file with other permissions
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(target, fs::Permissions::from_mode(mode))?;
+    }
+    #[cfg(not(target_family = "unix"))]
+    let _ = mode;
+
+    Ok(())
+}
+
+/// Creates the directory secrets are decrypted into with `decrypt --symlink`
+///
+/// Only the owner can access it and it ignores itself so that its plaintext is never committed
+fn create_decrypted_dir(dotfiles_dir: &Path) -> io::Result<PathBuf> {
+    let decrypted_dir = dotfiles::DotfileType::Decrypted.dir_in(dotfiles_dir);
+    fs::create_dir_all(&decrypted_dir)?;
+
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&decrypted_dir, fs::Permissions::from_mode(0o700))?;
+    }
+
+    let gitignore = decrypted_dir.join(".gitignore");
+    if !gitignore.exists() {
+        fs::write(gitignore, "*\n")?;
+    }
+
+    Ok(decrypted_dir)
+}
+
+/// Where the password used for the secrets comes from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PasswordSource {
+    /// Prompts for the password, unless it's set in `TUCKR_PASSWORD`
+    #[default]
+    Prompt,
+    /// The first line of stdin
+    Stdin,
+    /// The OS keyring, prompting and offering to save it when it isn't stored yet
+    Keyring,
+}
+
+impl PasswordSource {
+    /// Picks the source from the command line flags, prompting when none are passed
+    pub fn from_flags(password_stdin: bool, keyring: bool) -> Self {
+        if password_stdin {
+            PasswordSource::Stdin
+        } else if keyring {
+            PasswordSource::Keyring
+        } else {
+            PasswordSource::Prompt
+        }
+    }
+}
+
+/// Environment variable that's used as the password instead of prompting for it when it's set,
+/// eg: for hooks that run `tuckr secret get` without a terminal
+pub const PASSWORD_ENV_VAR: &str = "TUCKR_PASSWORD";
+
+/// Name of the keyring entry the password is stored under
+const KEYRING_SERVICE: &str = "tuckr-secrets";
+
+/// Asks for the password without echoing it
+///
+/// It fails without a terminal to ask on, eg: in a script
+fn prompt_password(output: &mut String) -> Result<String, ExitCode> {
+    // the prompt goes to the terminal so that it doesn't end up in piped output
+    rpassword::prompt_password("Password: ").map_err(|e| {
+        output.push_str(&format!(
+            "Couldn't read the password: {e}\nSet {PASSWORD_ENV_VAR} or use --password-stdin when there's no terminal.\n"
+        ));
+        ExitCode::FAILURE
+    })
+}
+
+/// Reads the password from the OS keyring, prompting for it and offering to save it if it's not
+/// stored yet and stdin is a terminal
+fn keyring_password(output: &mut String) -> Result<String, ExitCode> {
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "tuckr".into());
+    let entry = match keyring::Entry::new(KEYRING_SERVICE, &user) {
+        Ok(entry) => entry,
+        Err(e) => {
+            output.push_str(&format!("Couldn't access the keyring: {e}\n"));
+            return prompt_password(output);
+        }
+    };
+
+    match entry.get_password() {
+        Ok(password) => return Ok(password),
+        Err(keyring::Error::NoEntry) => (),
+        Err(e) => {
+            output.push_str(&format!(
+                "Couldn't read the password from the keyring: {e}\n"
+            ));
+            return prompt_password(output);
+        }
+    }
+
+    let password = prompt_password(output)?;
+    // stdin can carry something else, eg: a secret for `encrypt --stdin`, which answering would
+    // eat into
+    if !io::stdin().is_terminal() {
+        return Ok(password);
+    }
+
+    eprint!("Save the password to the keyring? (y/N): ");
+    _ = io::stderr().flush();
+
+    let mut answer = String::new();
+    _ = io::stdin().read_line(&mut answer);
+    // anything other than a yes keeps it out of the keyring
+    if matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+        if let Err(e) = entry.set_password(&password) {
+            output.push_str(&format!("Couldn't save the password to the keyring: {e}\n"));
+        }
+    }
+
+    Ok(password)
+}
+
 struct SecretsHandler {
     dotfiles_dir: PathBuf,
     key: chacha20poly1305::Key,
-    nonce: chacha20poly1305::XNonce,
+    // derives each file's nonce from its content so that it always encrypts the same way
+    deterministic_nonce: bool,
 }
 
 impl SecretsHandler {
-    fn try_new(output: &mut String) -> Result<Self, ExitCode> {
-        let dotfiles_dir = match dotfiles::get_dotfiles_path(output) {
-            Ok(path) => path,
-            Err(e) => {
-                output.push_str(&e.to_string());
-                return Err(ReturnCode::CouldntFindDotfiles.into());
+    /// password: where the password is read from
+    fn try_new(password: PasswordSource, output: &mut String) -> Result<Self, ExitCode> {
+        let dotfiles_dir = dotfiles::get_dotfiles_path(output)?;
+
+        let input_key = match password {
+            PasswordSource::Prompt => match std::env::var(PASSWORD_ENV_VAR) {
+                Ok(password) => password,
+                Err(_) => prompt_password(output)?,
+            },
+            PasswordSource::Keyring => keyring_password(output)?,
+            PasswordSource::Stdin => {
+                let mut password = String::new();
+                if let Err(e) = io::stdin().read_line(&mut password) {
+                    output.push_str(&format!("Couldn't read the password from stdin: {e}\n"));
+                    return Err(ExitCode::FAILURE);
+                }
+
+                password.trim_end_matches(['\n', '\r']).to_string()
             }
         };
 
         // makes a hash of the password so that it can fit on the 256 bit buffer used by the
         // algorithm
-        let input_key = rpassword::prompt_password("Password: ").unwrap();
         let input_hash = Sha256::digest(input_key);
 
         Ok(SecretsHandler {
             dotfiles_dir,
             key: input_hash,
-            nonce: XChaCha20Poly1305::generate_nonce(&mut rngs::OsRng),
+            deterministic_nonce: false,
         })
     }
 
-    /// takes a path to a file and returns its encrypted content
-    fn encrypt(&self, dotfile: &str, output: &mut String) -> Result<Vec<u8>, ExitCode> {
+    /// takes the contents of a file and returns the contents of its encrypted file
+    fn encrypt(
+        &self,
+        dotfile: &[u8],
+        header: SecretHeader,
+        output: &mut String,
+    ) -> Result<Vec<u8>, ExitCode> {
         let cipher = XChaCha20Poly1305::new(&self.key);
-        let Ok(dotfile) = fs::read(dotfile) else {
-            output.push_str(&format!("{} {}", "No such file or directory: ", dotfile));
-            return Err(ReturnCode::NoSuchFileOrDir.into());
+        let mut encrypted_file = header.to_bytes();
+        let payload = Payload {
+            msg: dotfile,
+            aad: &encrypted_file,
         };
 
-        match cipher.encrypt(&self.nonce, dotfile.as_slice()) {
-            Ok(f) => Ok(f),
+        // every file gets its own nonce since reusing one with the same key leaks the plaintexts
+        let nonce = if self.deterministic_nonce {
+            self.derive_nonce(&encrypted_file, dotfile)
+        } else {
+            XChaCha20Poly1305::generate_nonce(&mut rngs::OsRng)
+        };
+
+        match cipher.encrypt(&nonce, payload) {
+            Ok(mut encrypted) => {
+                encrypted_file.extend_from_slice(&nonce);
+                encrypted_file.append(&mut encrypted);
+                Ok(encrypted_file)
+            }
             Err(e) => {
-                output.push_str(&e.to_string());
+                output.push_str(&format!("{e}\n"));
                 Err(ReturnCode::EncryptionFailed.into())
             }
         }
     }
 
-    /// takes a path to a file and returns its decrypted content
-    fn decrypt(&self, dotfile: &str, output: &mut String) -> Result<Vec<u8>, ExitCode> {
+    /// Makes every file derive its nonce from its contents, warning that it weakens the encryption
+    fn use_deterministic_nonce(&mut self, deterministic_nonce: bool, output: &mut String) {
+        if deterministic_nonce {
+            output.push_str("Warning: --deterministic-nonce makes identical files encrypt to identical secrets, which weakens the encryption. Only use it for tests.\n");
+        }
+
+        self.deterministic_nonce = deterministic_nonce;
+    }
+
+    /// Derives a nonce from an HMAC of the header and the file's contents
+    ///
+    /// Identical files end up with identical secrets, which tells anyone looking at them that
+    /// they're the same, so this is only meant for tests and reproducible builds
+    fn derive_nonce(&self, header: &[u8], dotfile: &[u8]) -> chacha20poly1305::XNonce {
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&self.key).unwrap();
+        mac.update(header);
+        mac.update(dotfile);
+        let digest = mac.finalize().into_bytes();
+        *chacha20poly1305::XNonce::from_slice(&digest[..NONCE_LEN])
+    }
+
+    /// takes the contents of an encrypted file and returns its header and decrypted content
+    ///
+    /// name: what the secret is called in error messages, usually its path
+    fn decrypt(
+        &self,
+        name: &str,
+        dotfile: &[u8],
+        output: &mut String,
+    ) -> Result<(SecretHeader, Vec<u8>), ExitCode> {
         let cipher = XChaCha20Poly1305::new(&self.key);
-        let dotfile = fs::read(dotfile).expect("Couldn't read dotfile");
+        let (header, header_bytes, dotfile) = SecretHeader::split(dotfile);
+        if dotfile.len() < NONCE_LEN {
+            output.push_str(&format!("{name} is not a valid tuckr secret.\n"));
+            return Err(ReturnCode::DecryptionFailed.into());
+        }
 
-        // extracts the nonce from the first 24 bytes in the file
-        let (nonce, contents) = dotfile.split_at(24);
+        // extracts the nonce from the first 24 bytes after the header
+        let (nonce, contents) = dotfile.split_at(NONCE_LEN);
+        let payload = Payload {
+            msg: contents,
+            aad: header_bytes,
+        };
 
-        match cipher.decrypt(nonce.into(), contents) {
-            Ok(f) => Ok(f),
+        match cipher.decrypt(nonce.into(), payload) {
+            Ok(f) => Ok((header, f)),
             Err(_) => {
-                output.push_str("Wrong password.");
+                output.push_str("Wrong password.\n");
                 Err(ReturnCode::DecryptionFailed.into())
             }
         }
     }
+
+    /// Returns true if `secret` already holds `dotfile` encrypted with `header`
+    ///
+    /// Secrets that can't be decrypted, eg: because they use another password, count as changed
+    fn is_unchanged(&self, secret: &Path, dotfile: &[u8], header: SecretHeader) -> bool {
+        let Ok(secret_contents) = fs::read(secret) else {
+            return false;
+        };
+
+        let Ok((secret_header, decrypted)) = self.decrypt(
+            &secret.display().to_string(),
+            &secret_contents,
+            &mut String::new(),
+        ) else {
+            return false;
+        };
+
+        secret_header == header && dotfile == decrypted
+    }
 }
 
 /// Encrypts secrets
-pub fn encrypt_cmd(group: &str, dotfiles: &[String]) -> (String, ExitCode) {
+///
+/// root: whether the files are stored relative to `/` rather than $HOME
+///
+/// password: where the password is read from
+///
+/// if_changed: leaves secrets whose content hasn't changed untouched so that they don't show up
+/// on diffs
+///
+/// deterministic_nonce: encrypts identical files to identical secrets, only meant for tests
+pub fn encrypt_cmd(
+    group: &str,
+    dotfiles: &[String],
+    root: bool,
+    password: PasswordSource,
+    if_changed: bool,
+    deterministic_nonce: bool,
+) -> (String, ExitCode) {
     let mut output = "".into();
-    let handler = match SecretsHandler::try_new(&mut output) {
+    let mut handler = match SecretsHandler::try_new(password, &mut output) {
         Ok(h) => h,
         Err(e) => return (output, e),
     };
+    handler.use_deterministic_nonce(deterministic_nonce, &mut output);
 
-    let dest_dir = handler.dotfiles_dir.join("Secrets").join(group);
-    if !dest_dir.exists() {
-        fs::create_dir_all(&dest_dir).unwrap();
+    let dest_dir = dotfiles::DotfileType::Secrets
+        .dir_in(&handler.dotfiles_dir)
+        .join(group);
+    if let Err(e) = fs::create_dir_all(&dest_dir) {
+        output.push_str(&format!("Couldn't create {}: {e}\n", dest_dir.display()));
+        return (output, ReturnCode::from(e).into());
     }
 
     // canonicalizing the home_dir so that it can work with
     // windows' NT UNC paths (the paths used by fs::canonicalize on windows)
-    let home_dir = dirs::home_dir().unwrap().canonicalize().unwrap();
+    let home_dir = match dirs::home_dir().map(|home| home.canonicalize()) {
+        Some(Ok(home_dir)) => home_dir,
+        Some(Err(e)) => {
+            output.push_str(&format!("Couldn't find your home directory: {e}\n"));
+            return (output, ReturnCode::from(e).into());
+        }
+        None => {
+            let err = dotfiles::Error::HomeNotFound;
+            output.push_str(&format!("{err}\n"));
+            return (output, ReturnCode::from(err).into());
+        }
+    };
 
     for dotfile in dotfiles {
-        let target_file = Path::new(dotfile).canonicalize().unwrap();
-        let target_file = target_file.strip_prefix(&home_dir).unwrap();
+        let Ok(target_file) = Path::new(dotfile).canonicalize() else {
+            output.push_str(&format!("{} {}\n", "No such file or directory:", dotfile));
+            return (output, ReturnCode::NoSuchFileOrDir.into());
+        };
+
+        let target_file = if root {
+            // drops the root and drive prefix to get a path relative to `/`
+            target_file
+                .components()
+                .filter(|component| matches!(component, Component::Normal(_)))
+                .collect::<PathBuf>()
+        } else {
+            let Ok(target_file) = target_file.strip_prefix(&home_dir) else {
+                output.push_str(&format!(
+                    "{} is outside of your home directory, use `--root` to encrypt it.\n",
+                    target_file.display()
+                ));
+                return (output, ReturnCode::EncryptionFailed.into());
+            };
+            target_file.to_path_buf()
+        };
 
         let dir_path = {
-            let mut tf = target_file.to_path_buf();
+            let mut tf = target_file.clone();
             tf.pop();
             tf
         };
 
-        let mut encrypted = match handler.encrypt(dotfile, &mut output) {
+        let (Ok(contents), Ok(metadata)) = (fs::read(dotfile), fs::metadata(dotfile)) else {
+            output.push_str(&format!("{} {}\n", "No such file or directory:", dotfile));
+            return (output, ReturnCode::NoSuchFileOrDir.into());
+        };
+
+        let header = SecretHeader {
+            targets_root: root,
+            mode: file_mode(&metadata),
+        };
+        if if_changed && handler.is_unchanged(&dest_dir.join(&target_file), &contents, header) {
+            output.push_str(&format!("{dotfile} is unchanged, skipping.\n"));
+            continue;
+        }
+
+        let encrypted_file = match handler.encrypt(&contents, header, &mut output) {
             Ok(b) => b,
             Err(e) => return (output, e),
         };
-        let mut encrypted_file = handler.nonce.to_vec();
 
         // makes sure all parent directories of the dotfile are created
-        fs::create_dir_all(dest_dir.join(dir_path)).unwrap();
+        let secret = dest_dir.join(target_file);
+        let written = fs::create_dir_all(dest_dir.join(dir_path))
+            .and_then(|_| fs::write(&secret, encrypted_file));
+        if let Err(e) = written {
+            output.push_str(&format!("Couldn't write {}: {e}\n", secret.display()));
+            return (output, ReturnCode::from(e).into());
+        }
+    }
+
+    (output, ExitCode::SUCCESS)
+}
+
+/// Encrypts the contents of stdin into a secret
+///
+/// name: the path relative to $HOME (or `/` with `root`) the secret will be decrypted to
+///
+/// password: where the password is read from, it can't be stdin since that's being encrypted
+///
+/// deterministic_nonce: encrypts identical contents to identical secrets, only meant for tests
+pub fn encrypt_stdin_cmd(
+    group: &str,
+    name: &[String],
+    root: bool,
+    password: PasswordSource,
+    if_changed: bool,
+    deterministic_nonce: bool,
+) -> (String, ExitCode) {
+    let mut output = String::new();
+    let [name] = name else {
+        output.push_str("Exactly one name is needed when encrypting stdin.\n");
+        return (output, ReturnCode::EncryptionFailed.into());
+    };
+
+    let target_file = Path::new(name);
+    if !target_file
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        output.push_str(&format!(
+            "{name} must be a relative path without `..`, eg: .ssh/id_rsa\n"
+        ));
+        return (output, ReturnCode::EncryptionFailed.into());
+    }
 
-        // appends a 24 byte nonce to the beginning of the file
-        encrypted_file.append(&mut encrypted);
-        fs::write(dest_dir.join(target_file), encrypted_file).unwrap();
+    let mut handler = match SecretsHandler::try_new(password, &mut output) {
+        Ok(h) => h,
+        Err(e) => return (output, e),
+    };
+    handler.use_deterministic_nonce(deterministic_nonce, &mut output);
+
+    let mut contents = Vec::new();
+    if let Err(e) = io::stdin().read_to_end(&mut contents) {
+        output.push_str(&format!("Couldn't read stdin: {e}\n"));
+        return (output, ExitCode::FAILURE);
     }
 
+    let secret = dotfiles::DotfileType::Secrets
+        .dir_in(&handler.dotfiles_dir)
+        .join(group)
+        .join(target_file);
+    let header = SecretHeader {
+        targets_root: root,
+        mode: None,
+    };
+    if if_changed && handler.is_unchanged(&secret, &contents, header) {
+        output.push_str(&format!("{name} is unchanged, skipping.\n"));
+        return (output, ExitCode::SUCCESS);
+    }
+
+    let encrypted_file = match handler.encrypt(&contents, header, &mut output) {
+        Ok(b) => b,
+        Err(e) => return (output, e),
+    };
+
+    fs::create_dir_all(secret.parent().unwrap()).unwrap();
+    fs::write(secret, encrypted_file).unwrap();
+
     (output, ExitCode::SUCCESS)
 }
 
-/// Decrypts secrets
-pub fn decrypt_cmd(groups: &[String], exclude: &[String]) -> (String, ExitCode) {
-    let mut output: String = "".into();
-    let handler = match SecretsHandler::try_new(&mut output) {
+/// Encrypts every file of `Configs/<group>` into `Secrets/<group>` and deletes them from Configs
+///
+/// Symlinks to the group's files are removed so that decrypting them doesn't write through them
+/// and back into Configs
+///
+/// Returns how many files were encrypted
+fn encrypt_group_configs(
+    handler: &SecretsHandler,
+    group: &str,
+    output: &mut String,
+) -> Result<usize, ExitCode> {
+    let configs_dir = dotfiles::DotfileType::Configs
+        .dir_in(&handler.dotfiles_dir)
+        .join(group);
+    let secrets_dir = dotfiles::DotfileType::Secrets
+        .dir_in(&handler.dotfiles_dir)
+        .join(group);
+    let home_dir = dirs::home_dir().unwrap();
+
+    let Ok(group_dotfile) = Dotfile::try_from(configs_dir.clone()) else {
+        output.push_str(&format!("{group} does not exist.\n"));
+        return Err(ReturnCode::NoSetupFolder.into());
+    };
+
+    let mut dotfiles = Vec::new();
+    if let Err(err) = group_dotfile.map(|dotfile| dotfiles.push(dotfile)) {
+        output.push_str(&format!("{err}\n"));
+        return Err(ReturnCode::from(err).into());
+    }
+
+    // everything is encrypted before anything is written or removed so that a failure leaves the
+    // group as it was
+    let mut targets = Vec::new();
+    let mut secrets = Vec::new();
+    for dotfile in dotfiles {
+        let target = dotfile.to_target_path(output)?;
+        if dotfile.path.is_dir() {
+            targets.push((target, dotfile.path));
+            continue;
+        }
+
+        // secrets are stored relative to $HOME when possible, like `encrypt` does
+        let (relative_target, targets_root) = match target.strip_prefix(&home_dir) {
+            Ok(relative_target) => (relative_target.to_path_buf(), false),
+            Err(_) => (
+                target
+                    .components()
+                    .filter(|component| matches!(component, Component::Normal(_)))
+                    .collect(),
+                true,
+            ),
+        };
+
+        let (Ok(contents), Ok(metadata)) = (fs::read(&dotfile.path), fs::metadata(&dotfile.path))
+        else {
+            output.push_str(&format!("Couldn't read {}\n", dotfile.path.display()));
+            return Err(ReturnCode::NoSuchFileOrDir.into());
+        };
+        let header = SecretHeader {
+            targets_root,
+            mode: file_mode(&metadata),
+        };
+        let encrypted_file = handler.encrypt(&contents, header, output)?;
+
+        secrets.push((secrets_dir.join(relative_target), encrypted_file));
+        targets.push((target, dotfile.path));
+    }
+
+    for (secret, encrypted_file) in &secrets {
+        if let Err(e) = fs::create_dir_all(secret.parent().unwrap())
+            .and_then(|_| fs::write(secret, encrypted_file))
+        {
+            output.push_str(&format!("Couldn't write {}: {e}\n", secret.display()));
+            return Err(ReturnCode::from(e).into());
+        }
+    }
+
+    // the plaintext is only unlinked and deleted once every file was encrypted
+    for (target, dotfile) in &targets {
+        if fs::read_link(target).is_ok_and(|link| dotfiles::same_path(&link, dotfile)) {
+            if let Err(e) = fs::remove_file(target) {
+                output.push_str(&format!("Couldn't remove {}: {e}\n", target.display()));
+                return Err(ReturnCode::from(e).into());
+            }
+        }
+    }
+    for (_, file) in targets.iter().filter(|(_, dotfile)| !dotfile.is_dir()) {
+        if let Err(e) = fs::remove_file(file) {
+            output.push_str(&format!("Couldn't remove {}: {e}\n", file.display()));
+        }
+    }
+    // directories that were left empty are removed as well, non empty ones fail to be removed
+    for dir in WalkDir::new(&configs_dir)
+        .contents_first(true)
+        .into_iter()
+        .flatten()
+    {
+        if dir.file_type().is_dir() {
+            _ = fs::remove_dir(dir.path());
+        }
+    }
+
+    Ok(secrets.len())
+}
+
+/// Moves a group's configs into its secrets, encrypting them and deleting the plaintext
+///
+/// yes: doesn't ask for confirmation before deleting the plaintext
+///
+/// deterministic_nonce: encrypts identical files to identical secrets, only meant for tests
+pub fn encrypt_group_configs_cmd(
+    group: &str,
+    password: PasswordSource,
+    yes: bool,
+    deterministic_nonce: bool,
+) -> (String, ExitCode) {
+    let mut output = String::new();
+
+    if !yes {
+        eprint!("This deletes the plaintext of every file in Configs/{group} after encrypting it into Secrets/{group}. Continue? (y/N): ");
+        _ = io::stderr().flush();
+
+        let mut answer = String::new();
+        _ = io::stdin().read_line(&mut answer);
+        if !matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+            output.push_str("Nothing was encrypted.\n");
+            return (output, ExitCode::FAILURE);
+        }
+    }
+
+    let mut handler = match SecretsHandler::try_new(password, &mut output) {
         Ok(h) => h,
         Err(e) => return (output, e),
     };
+    handler.use_deterministic_nonce(deterministic_nonce, &mut output);
+
+    match encrypt_group_configs(&handler, group, &mut output) {
+        Ok(count) => {
+            output.push_str(&format!(
+                "Encrypted {count} file(s) from Configs/{group} into Secrets/{group}.\n"
+            ));
+            (output, ExitCode::SUCCESS)
+        }
+        Err(e) => (output, e),
+    }
+}
+
+type DecryptResult = Result<(SecretHeader, Vec<u8>), (String, ExitCode)>;
+
+/// Decrypts files using up to `jobs` threads, stopping early once any of them fails
+///
+/// Results are returned in the same order as `files`
+fn decrypt_files(
+    handler: &SecretsHandler,
+    files: &[PathBuf],
+    jobs: NonZeroUsize,
+    progress: bool,
+) -> Vec<DecryptResult> {
+    let decrypted_count = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
 
+    let decrypt_chunk = |chunk: &[PathBuf]| -> Vec<DecryptResult> {
+        let mut results = Vec::new();
+        for file in chunk {
+            if failed.load(Ordering::Relaxed) {
+                break;
+            }
+
+            let mut output = String::new();
+            let result = match fs::read(file) {
+                Ok(contents) => handler
+                    .decrypt(&file.display().to_string(), &contents, &mut output)
+                    .map_err(|e| (output, e)),
+                Err(e) => Err((
+                    format!("Couldn't read {}: {e}\n", file.display()),
+                    ReturnCode::from(e).into(),
+                )),
+            };
+            if result.is_err() {
+                failed.store(true, Ordering::Relaxed);
+            }
+            results.push(result);
+
+            let count = decrypted_count.fetch_add(1, Ordering::Relaxed) + 1;
+            if progress {
+                // progress goes to stderr so that it doesn't get mixed with the command's output
+                eprint!("\rDecrypting {count}/{}", files.len());
+                _ = io::stderr().flush();
+            }
+        }
+
+        results
+    };
+
+    let chunk_size = files.len().div_ceil(jobs.get()).max(1);
+    let results = thread::scope(|s| {
+        let handles: Vec<_> = files
+            .chunks(chunk_size)
+            .map(|chunk| s.spawn(|| decrypt_chunk(chunk)))
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    });
+
+    if progress && !files.is_empty() {
+        eprintln!();
+    }
+
+    results
+}
+
+/// Whether the secret's target is a file as big as the decrypted secret
+///
+/// The secret can't be compared with its target without the password so it only tells whether
+/// the secret looks decrypted, `decrypt_cmd` compares their contents instead
+fn looks_decrypted(secret: &Path, group_dir: &Path) -> bool {
+    let Ok(header) = SecretHeader::read(secret) else {
+        return false;
+    };
+    let target = header
+        .target_base_dir()
+        .join(secret.strip_prefix(group_dir).unwrap());
+
+    fs::metadata(&target).is_ok_and(|target_meta| {
+        target_meta.is_file()
+            && SecretHeader::decrypted_len(secret).is_ok_and(|len| len == target_meta.len())
+    })
+}
+
+/// Counts how many of the secrets in a group's directory look decrypted already, along with
+/// how many secrets there are
+pub fn decrypted_count(group_dir: &Path) -> (usize, usize) {
+    let secrets: Vec<_> = WalkDir::new(group_dir)
+        .into_iter()
+        .filter_map(|secret| secret.ok())
+        .filter(|secret| secret.file_type().is_file())
+        .collect();
+    let decrypted = secrets
+        .iter()
+        .filter(|secret| looks_decrypted(secret.path(), group_dir))
+        .count();
+
+    (decrypted, secrets.len())
+}
+
+/// How `decrypt_cmd` decrypts the groups' secrets
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DecryptOptions {
+    /// How many files are decrypted at the same time, defaults to the number of CPUs
+    pub jobs: Option<NonZeroUsize>,
+    /// Logs how many files have been decrypted so far
+    pub progress: bool,
+    /// Where the password is read from
+    pub password: PasswordSource,
+    /// Lists where every secret would be decrypted to without asking for the password
+    pub dry_run: bool,
+    /// Writes secrets again even if their targets already hold their decrypted contents
+    pub force: bool,
+    /// Decrypts secrets into `Decrypted/<group>` and symlinks them to their targets like configs,
+    /// secrets relative to `/` are still written to their targets directly
+    pub symlink: bool,
+}
+
+/// Decrypts secrets
+pub fn decrypt_cmd(
+    groups: &[String],
+    exclude: &[String],
+    options: DecryptOptions,
+) -> (String, ExitCode) {
+    let DecryptOptions {
+        jobs,
+        progress,
+        password,
+        dry_run,
+        force,
+        symlink,
+    } = options;
+    let mut output: String = "".into();
+    let dotfiles_dir = match dotfiles::get_dotfiles_path(&mut output) {
+        Ok(dir) => dir,
+        Err(e) => return (output, e.into()),
+    };
+
+    // typos are caught before asking for the password or creating anything
     if let Some(invalid_groups) =
         dotfiles::check_invalid_groups(dotfiles::DotfileType::Secrets, groups, &mut output)
     {
         for group in invalid_groups {
-            output.push_str(&group);
-            output.push_str("does not exist.");
+            output.push_str(&format!("{group} does not exist.\n"));
         }
         return (output, ReturnCode::DecryptionFailed.into());
     }
 
-    let dest_dir = std::env::current_dir().unwrap();
+    let handler = if dry_run {
+        None
+    } else {
+        match SecretsHandler::try_new(password, &mut output) {
+            Ok(h) => Some(h),
+            Err(e) => return (output, e),
+        }
+    };
+
+    let decrypted_dir = if symlink && !dry_run {
+        match create_decrypted_dir(&dotfiles_dir) {
+            Ok(dir) => Some(dir),
+            Err(e) => {
+                output.push_str(&format!("Couldn't create the Decrypted directory: {e}\n"));
+                return (output, ReturnCode::from(e).into());
+            }
+        }
+    } else {
+        None
+    };
+
+    let jobs = jobs
+        .or_else(|| thread::available_parallelism().ok())
+        .unwrap_or(NonZeroUsize::MIN);
 
-    let decrypt_group = |group: Dotfile, output: &mut String| -> Result<(), ExitCode> {
+    // returns how many files the group has and how many of them were already decrypted, or
+    // None if the group was skipped
+    type DecryptCount = Option<(usize, usize)>;
+    let decrypt_group = |group: &Dotfile, output: &mut String| -> Result<DecryptCount, ExitCode> {
         if exclude.contains(&group.group_name) || !group.is_valid_target() {
-            return Ok(());
+            return Ok(None);
         }
 
-        let group_dir = handler.dotfiles_dir.join("Secrets").join(&group.group_path);
-        for secret in WalkDir::new(group_dir) {
+        let group_dir = dotfiles::DotfileType::Secrets
+            .dir_in(&dotfiles_dir)
+            .join(&group.group_path);
+        let mut secrets = Vec::new();
+        for secret in WalkDir::new(&group_dir) {
             let Ok(secret) = secret else {
-                output.push_str(&(group.group_name + " does not exist."));
+                output.push_str(&format!("{} does not exist.\n", group.group_name));
                 return Err(ReturnCode::NoSetupFolder.into());
             };
 
-            if secret.file_type().is_dir() {
-                continue;
+            if !secret.file_type().is_dir() {
+                secrets.push(secret.into_path());
             }
+        }
 
-            let decrypted = handler.decrypt(secret.path().to_str().unwrap(), output)?;
+        let Some(handler) = &handler else {
+            // the target only depends on the header so there's no need to decrypt anything
+            for secret in &secrets {
+                let header = match SecretHeader::read(secret) {
+                    Ok(header) => header,
+                    Err(e) => {
+                        output.push_str(&format!("Couldn't read {}: {e}\n", secret.display()));
+                        return Err(ReturnCode::from(e).into());
+                    }
+                };
 
-            fs::write(dest_dir.join(secret.file_name()), decrypted).unwrap();
-        }
+                let relative_path = secret.strip_prefix(&group_dir).unwrap();
+                output.push_str(&format!(
+                    "{} -> {}\n",
+                    secret.display(),
+                    header.target_base_dir().join(relative_path).display()
+                ));
+            }
 
-        Ok(())
-    };
+            return Ok(Some((secrets.len(), 0)));
+        };
 
-    if groups.contains(&"*".to_string()) {
-        let groups_dir = handler.dotfiles_dir.join("Secrets");
-        for group in fs::read_dir(groups_dir).unwrap() {
-            let Ok(group) = Dotfile::try_from(group.unwrap().path()) else {
-                output.push_str("Received an invalid group path.");
-                return (output, ExitCode::FAILURE);
-            };
-            match decrypt_group(group, &mut output) {
-                Ok(_) => (),
-                Err(_) => return (output, ExitCode::FAILURE),
+        // files are decrypted in parallel but only written once all of them are done so that
+        // output always comes out in the same order
+        let decrypted_secrets = decrypt_files(handler, &secrets, jobs, progress);
+        let mut already_decrypted = 0;
+        for (secret, decrypted) in secrets.iter().zip(decrypted_secrets) {
+            let (header, decrypted) = match decrypted {
+                Ok(decrypted) => decrypted,
+                Err((decrypt_output, e)) => {
+                    output.push_str(&decrypt_output);
+                    return Err(e);
+                }
             };
+
+            // secrets are restored to the path they were encrypted from
+            let relative_path = secret.strip_prefix(&group_dir).unwrap();
+            let target = header.target_base_dir().join(relative_path);
+            // an interrupted run can be resumed without writing everything again
+            let holds_secret =
+                |path: &Path| !force && fs::read(path).is_ok_and(|contents| contents == decrypted);
+
+            if let Some(decrypted_dir) = decrypted_dir.as_ref().filter(|_| !header.targets_root) {
+                let staged = decrypted_dir.join(&group.group_name).join(relative_path);
+                let is_linked =
+                    fs::read_link(&target).is_ok_and(|link| dotfiles::same_path(&link, &staged));
+                if is_linked && holds_secret(&staged) {
+                    already_decrypted += 1;
+                    continue;
+                }
+
+                let staged_dir = staged.parent().unwrap();
+                if let Err(e) = fs::create_dir_all(staged_dir) {
+                    output.push_str(&format!("Couldn't create {}: {e}\n", staged_dir.display()));
+                    return Err(ReturnCode::from(e).into());
+                }
+                tracing::debug!(secret = %secret.display(), staged = %staged.display(), "decrypting");
+                if let Err(e) = write_decrypted(&staged, &decrypted, header.mode) {
+                    output.push_str(&format!("Failed to write {}: {e}\n", staged.display()));
+                    return Err(ReturnCode::from(e).into());
+                }
+
+                if !is_linked {
+                    if let Err(err) = symlinks::symlink_file(staged, output) {
+                        output.push_str(&format!("Couldn't symlink {}: {err}\n", target.display()));
+                        return Err(ReturnCode::CouldntSymlinkFile.into());
+                    }
+                }
+                continue;
+            }
+
+            if holds_secret(&target) {
+                already_decrypted += 1;
+                continue;
+            }
+
+            if let Some(parent) = target.parent() {
+                if let Err(e) = fs::create_dir_all(parent) {
+                    output.push_str(&format!("Couldn't create {}: {e}\n", parent.display()));
+                    return Err(ReturnCode::from(e).into());
+                }
+            }
+
+            tracing::debug!(secret = %secret.display(), target = %target.display(), "decrypting");
+            if let Err(e) = write_decrypted(&target, &decrypted, header.mode) {
+                output.push_str(&format!("Failed to write {}: {e}\n", target.display()));
+                return Err(ReturnCode::from(e).into());
+            }
         }
 
-        return (output, ExitCode::SUCCESS);
-    }
+        Ok(Some((secrets.len() - already_decrypted, already_decrypted)))
+    };
 
+    let all_groups;
+    let groups = if groups.contains(&"*".to_string()) {
+        all_groups = match dotfiles::list_groups(dotfiles::DotfileType::Secrets) {
+            Ok(groups) => groups,
+            Err(e) => return (output, e.into()),
+        };
+        &all_groups
+    } else {
+        groups
+    };
+
+    let mut summary = Vec::new();
     for group in groups {
-        let group = handler.dotfiles_dir.join("Secrets").join(group);
+        let group = dotfiles::DotfileType::Secrets
+            .dir_in(&dotfiles_dir)
+            .join(group);
         let Ok(group) = Dotfile::try_from(group) else {
-            output.push_str("Received an invalid group path.");
+            output.push_str("Received an invalid group path.\n");
             return (output, ExitCode::FAILURE);
         };
-        match decrypt_group(group, &mut output) {
-            Ok(_) => (),
-            Err(_) => return (output, ExitCode::FAILURE),
+        match decrypt_group(&group, &mut output) {
+            Ok(Some((count, already_decrypted))) => {
+                summary.push((group.group_name, count, already_decrypted))
+            }
+            Ok(None) => (),
+            Err(e) => return (output, e),
         };
     }
 
+    let verb = if dry_run {
+        "Would decrypt"
+    } else {
+        "Decrypted"
+    };
+    for (group, count, already_decrypted) in summary {
+        if count == 0 && already_decrypted == 0 {
+            output.push_str(&format!("Warning: {group} has no secrets to decrypt.\n"));
+        } else if already_decrypted == 0 {
+            output.push_str(&format!("{verb} {count} file(s) from {group}.\n"));
+        } else {
+            output.push_str(&format!(
+                "{verb} {count} file(s) from {group}, {already_decrypted} were already decrypted.\n"
+            ));
+        }
+    }
+
+    (output, ExitCode::SUCCESS)
+}
+
+/// Decrypts a single secret and writes it to stdout
+///
+/// secret: path to the secret relative to dotfiles/Secrets, eg: ssh/.ssh/id_rsa
+///
+/// password: where the password is read from
+pub fn decrypt_stdout_cmd(secret: &[String], password: PasswordSource) -> (String, ExitCode) {
+    let mut output = String::new();
+    let [secret] = secret else {
+        output.push_str("Exactly one secret can be written to stdout.\n");
+        return (output, ReturnCode::DecryptionFailed.into());
+    };
+
+    let handler = match SecretsHandler::try_new(password, &mut output) {
+        Ok(h) => h,
+        Err(e) => return (output, e),
+    };
+
+    let secret_path = dotfiles::DotfileType::Secrets
+        .dir_in(&handler.dotfiles_dir)
+        .join(secret);
+    if !secret_path.is_file() {
+        output.push_str(&format!("{secret} is not a secret.\n"));
+        return (output, ReturnCode::NoSuchFileOrDir.into());
+    }
+
+    let contents = match fs::read(&secret_path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            output.push_str(&format!("Couldn't read {secret}: {e}\n"));
+            return (output, ReturnCode::from(e).into());
+        }
+    };
+
+    let decrypted = match handler.decrypt(secret, &contents, &mut output) {
+        Ok((_, decrypted)) => decrypted,
+        Err(e) => return (output, e),
+    };
+
+    let mut stdout = io::stdout().lock();
+    if let Err(e) = stdout.write_all(&decrypted).and_then(|_| stdout.flush()) {
+        output.push_str(&format!("Couldn't write to stdout: {e}\n"));
+        return (output, ExitCode::FAILURE);
+    }
+
     (output, ExitCode::SUCCESS)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_handler() -> SecretsHandler {
+        SecretsHandler {
+            dotfiles_dir: PathBuf::new(),
+            key: Sha256::digest("password"),
+            deterministic_nonce: false,
+        }
+    }
+
+    #[test]
+    fn header_round_trip() {
+        let header = SecretHeader {
+            targets_root: true,
+            mode: None,
+        };
+        let mut file = header.to_bytes();
+        file.extend_from_slice(b"rest");
+
+        let (parsed, header_bytes, rest) = SecretHeader::split(&file);
+        assert_eq!(parsed, header);
+        assert_eq!(header_bytes, header.to_bytes().as_slice());
+        assert_eq!(rest, b"rest");
+
+        // files without a header were made before headers existed and target $HOME
+        let (parsed, header_bytes, rest) = SecretHeader::split(b"no header here");
+        assert_eq!(parsed, SecretHeader::default());
+        assert!(header_bytes.is_empty());
+        assert_eq!(rest, b"no header here");
+    }
+
+    #[test]
+    fn read_header_without_decrypting() {
+        let handler = test_handler();
+        let secret = std::env::temp_dir().join(format!("tuckr-header-{}", std::process::id()));
+
+        let header = SecretHeader {
+            targets_root: true,
+            mode: None,
+        };
+        let encrypted = handler
+            .encrypt(b"super secret", header, &mut String::new())
+            .unwrap();
+        fs::write(&secret, encrypted).unwrap();
+        assert_eq!(SecretHeader::read(&secret).unwrap(), header);
+
+        fs::write(&secret, "legacy").unwrap();
+        assert_eq!(
+            SecretHeader::read(&secret).unwrap(),
+            SecretHeader::default()
+        );
+
+        fs::remove_file(secret).unwrap();
+    }
+
+    #[test]
+    fn decrypted_len_without_decrypting() {
+        let handler = test_handler();
+        let secret = std::env::temp_dir().join(format!("tuckr-len-{}", std::process::id()));
+
+        let encrypted = handler
+            .encrypt(b"super secret", SecretHeader::default(), &mut String::new())
+            .unwrap();
+        fs::write(&secret, &encrypted).unwrap();
+        assert_eq!(SecretHeader::decrypted_len(&secret).unwrap(), 12);
+
+        // legacy secrets don't have a header
+        fs::write(&secret, &encrypted[HEADER_LEN..]).unwrap();
+        assert_eq!(SecretHeader::decrypted_len(&secret).unwrap(), 12);
+
+        fs::remove_file(secret).unwrap();
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn mode_round_trip() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let handler = test_handler();
+        let temp_dir = std::env::temp_dir().join(format!("tuckr-mode-{}", std::process::id()));
+        fs::create_dir_all(&temp_dir).unwrap();
+        let (plain_file, decrypted_file) = (temp_dir.join("id_rsa"), temp_dir.join("decrypted"));
+        fs::write(&plain_file, "super secret").unwrap();
+        fs::set_permissions(&plain_file, fs::Permissions::from_mode(0o600)).unwrap();
+
+        let header = SecretHeader {
+            targets_root: false,
+            mode: file_mode(&fs::metadata(&plain_file).unwrap()),
+        };
+        let encrypted = handler
+            .encrypt(b"super secret", header, &mut String::new())
+            .unwrap();
+        let (decrypted_header, decrypted) = handler
+            .decrypt("secret", &encrypted, &mut String::new())
+            .unwrap();
+        assert_eq!(decrypted_header.mode, Some(0o600));
+
+        // files that were already there get their permissions replaced as well
+        for existing in [false, true] {
+            if existing {
+                fs::set_permissions(&decrypted_file, fs::Permissions::from_mode(0o644)).unwrap();
+            }
+
+            write_decrypted(&decrypted_file, &decrypted, decrypted_header.mode).unwrap();
+            let mode = fs::metadata(&decrypted_file).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // secrets without permissions, like the ones encrypted from stdin, are only readable by
+        // their owner
+        fs::set_permissions(&decrypted_file, fs::Permissions::from_mode(0o644)).unwrap();
+        write_decrypted(&decrypted_file, &decrypted, None).unwrap();
+        let mode = fs::metadata(&decrypted_file).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, DEFAULT_MODE);
+
+        // the permissions make the header longer
+        fs::write(&plain_file, &encrypted).unwrap();
+        assert_eq!(SecretHeader::decrypted_len(&plain_file).unwrap(), 12);
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn encrypt_group_configs_into_secrets() {
+        let _lock = dotfiles::test_lock();
+        let mut handler = test_handler();
+        handler.dotfiles_dir = dotfiles::get_dotfiles_path(&mut String::new()).unwrap();
+
+        let configs_dir = dotfiles::DotfileType::Configs
+            .dir_in(&handler.dotfiles_dir)
+            .join("vault");
+        let secrets_dir = dotfiles::DotfileType::Secrets
+            .dir_in(&handler.dotfiles_dir)
+            .join("vault");
+        fs::create_dir_all(configs_dir.join(".ssh")).unwrap();
+        fs::write(configs_dir.join(".ssh").join("id_rsa"), "super secret").unwrap();
+
+        // the plaintext is kept if a secret can't be written
+        fs::create_dir_all(&secrets_dir).unwrap();
+        fs::write(secrets_dir.join(".ssh"), "in the way").unwrap();
+        assert!(encrypt_group_configs(&handler, "vault", &mut String::new()).is_err());
+        assert!(configs_dir.join(".ssh").join("id_rsa").exists());
+        fs::remove_file(secrets_dir.join(".ssh")).unwrap();
+
+        let encrypted = encrypt_group_configs(&handler, "vault", &mut String::new()).unwrap();
+        assert_eq!(encrypted, 1);
+        assert!(!configs_dir.exists());
+
+        let secret = fs::read(secrets_dir.join(".ssh").join("id_rsa")).unwrap();
+        let (header, decrypted) = handler
+            .decrypt("secret", &secret, &mut String::new())
+            .unwrap();
+        assert!(!header.targets_root);
+        assert_eq!(decrypted, b"super secret");
+
+        fs::remove_dir_all(secrets_dir).unwrap();
+    }
+
+    #[test]
+    fn decrypt_skips_targets_holding_the_secret() {
+        let _lock = dotfiles::test_lock();
+        let mut handler = test_handler();
+        handler.dotfiles_dir = dotfiles::get_dotfiles_path(&mut String::new()).unwrap();
+        let configs_dir = dotfiles::DotfileType::Configs
+            .dir_in(&handler.dotfiles_dir)
+            .join("resumed");
+        fs::create_dir_all(&configs_dir).unwrap();
+        fs::write(configs_dir.join("tuckr_resumed_secret"), "super secret").unwrap();
+        encrypt_group_configs(&handler, "resumed", &mut String::new()).unwrap();
+
+        std::env::set_var(PASSWORD_ENV_VAR, "password");
+        let decrypt = || {
+            let (output, exit_code) =
+                decrypt_cmd(&["resumed".into()], &[], DecryptOptions::default());
+            assert_eq!(exit_code, ExitCode::SUCCESS);
+            output
+        };
+
+        let target = dirs::home_dir().unwrap().join("tuckr_resumed_secret");
+        assert!(decrypt().contains("Decrypted 1 file(s) from resumed."));
+        assert!(decrypt().contains("Decrypted 0 file(s) from resumed, 1 were already decrypted."));
+
+        // a target as big as the secret is still written again if its contents differ
+        fs::write(&target, "super sekret").unwrap();
+        assert!(decrypt().contains("Decrypted 1 file(s) from resumed."));
+        assert_eq!(fs::read_to_string(&target).unwrap(), "super secret");
+        std::env::remove_var(PASSWORD_ENV_VAR);
+
+        fs::remove_file(target).unwrap();
+        fs::remove_dir_all(handler.dotfiles_dir).unwrap();
+    }
+
+    #[test]
+    fn decrypt_symlink_fails_on_conflicts() {
+        let _lock = dotfiles::test_lock();
+        let mut handler = test_handler();
+        handler.dotfiles_dir = dotfiles::get_dotfiles_path(&mut String::new()).unwrap();
+        let configs_dir = dotfiles::DotfileType::Configs
+            .dir_in(&handler.dotfiles_dir)
+            .join("staged");
+        fs::create_dir_all(&configs_dir).unwrap();
+        fs::write(configs_dir.join("tuckr_staged_secret"), "super secret").unwrap();
+        encrypt_group_configs(&handler, "staged", &mut String::new()).unwrap();
+
+        // a file that's in the way of the symlink keeps the secret from being deployed
+        let target = dirs::home_dir().unwrap().join("tuckr_staged_secret");
+        fs::write(&target, "conflict").unwrap();
+
+        std::env::set_var(PASSWORD_ENV_VAR, "password");
+        let (_, exit_code) = decrypt_cmd(
+            &["staged".into()],
+            &[],
+            DecryptOptions {
+                symlink: true,
+                ..Default::default()
+            },
+        );
+        std::env::remove_var(PASSWORD_ENV_VAR);
+        assert_eq!(exit_code, ExitCode::from(ReturnCode::CouldntSymlinkFile));
+        assert_eq!(fs::read_to_string(&target).unwrap(), "conflict");
+
+        fs::remove_file(target).unwrap();
+        fs::remove_dir_all(handler.dotfiles_dir).unwrap();
+    }
+
+    #[test]
+    fn decrypt_checks_groups_first() {
+        let _lock = dotfiles::test_lock();
+        crate::fileops::init_cmd();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&mut String::new()).unwrap();
+
+        // it fails without asking for the password or creating Decrypted
+        let (output, exit_code) = decrypt_cmd(
+            &["missing".into()],
+            &[],
+            DecryptOptions {
+                symlink: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(exit_code, ExitCode::from(ReturnCode::DecryptionFailed));
+        assert!(output.contains("missing does not exist"));
+        assert!(!dotfiles::DotfileType::Decrypted
+            .dir_in(&dotfiles_dir)
+            .exists());
+
+        fs::remove_dir_all(dotfiles_dir).unwrap();
+    }
+
+    #[test]
+    fn password_from_env_var() {
+        let _lock = dotfiles::test_lock();
+        crate::fileops::init_cmd();
+        std::env::set_var(PASSWORD_ENV_VAR, "password");
+        let handler = SecretsHandler::try_new(PasswordSource::Prompt, &mut String::new()).unwrap();
+        std::env::remove_var(PASSWORD_ENV_VAR);
+
+        assert_eq!(handler.key, test_handler().key);
+        fs::remove_dir_all(handler.dotfiles_dir).unwrap();
+    }
+
+    #[test]
+    fn deterministic_nonce() {
+        let mut handler = test_handler();
+        handler.use_deterministic_nonce(true, &mut String::new());
+
+        let header = SecretHeader::default();
+        let first = handler
+            .encrypt(b"super secret", header, &mut String::new())
+            .unwrap();
+        let second = handler
+            .encrypt(b"super secret", header, &mut String::new())
+            .unwrap();
+        let other = handler
+            .encrypt(b"other secret", header, &mut String::new())
+            .unwrap();
+        assert_eq!(first, second);
+        assert_ne!(
+            first[..HEADER_LEN + NONCE_LEN],
+            other[..HEADER_LEN + NONCE_LEN]
+        );
+
+        let (_, decrypted) = handler
+            .decrypt("secret", &first, &mut String::new())
+            .unwrap();
+        assert_eq!(decrypted, b"super secret");
+    }
+
+    #[test]
+    fn every_secret_gets_its_own_nonce() {
+        let handler = test_handler();
+        let header = SecretHeader::default();
+        let nonce_range = HEADER_LEN..HEADER_LEN + NONCE_LEN;
+
+        let first = handler
+            .encrypt(b"super secret", header, &mut String::new())
+            .unwrap();
+        let second = handler
+            .encrypt(b"other secret", header, &mut String::new())
+            .unwrap();
+        assert_ne!(first[nonce_range.clone()], second[nonce_range]);
+    }
+
+    #[test]
+    fn encrypt_decrypt_round_trip() {
+        let handler = test_handler();
+        let plain_file = std::env::temp_dir().join(format!("tuckr-secret-{}", std::process::id()));
+        fs::write(&plain_file, "super secret").unwrap();
+
+        let header = SecretHeader {
+            targets_root: true,
+            mode: None,
+        };
+        let encrypted = handler
+            .encrypt(&fs::read(&plain_file).unwrap(), header, &mut String::new())
+            .unwrap();
+        fs::write(&plain_file, encrypted).unwrap();
+
+        let (decrypted_header, decrypted) = handler
+            .decrypt(
+                "secret",
+                &fs::read(&plain_file).unwrap(),
+                &mut String::new(),
+            )
+            .unwrap();
+        assert_eq!(decrypted_header, header);
+        assert_eq!(decrypted, b"super secret");
+
+        // tampering with the header makes decryption fail
+        let mut tampered = fs::read(&plain_file).unwrap();
+        tampered[HEADER_LEN - 1] = 0;
+        assert!(handler
+            .decrypt("secret", &tampered, &mut String::new())
+            .is_err());
+
+        fs::remove_file(plain_file).unwrap();
+    }
+
+    #[test]
+    fn detect_unchanged_secrets() {
+        let handler = test_handler();
+        let temp_dir = std::env::temp_dir().join(format!("tuckr-unchanged-{}", std::process::id()));
+        fs::create_dir_all(&temp_dir).unwrap();
+        let secret = temp_dir.join("secret");
+        let plain = b"super secret";
+
+        assert!(!handler.is_unchanged(&secret, plain, SecretHeader::default()));
+
+        let encrypted = handler
+            .encrypt(plain, SecretHeader::default(), &mut String::new())
+            .unwrap();
+        fs::write(&secret, encrypted).unwrap();
+        assert!(handler.is_unchanged(&secret, plain, SecretHeader::default()));
+        assert!(!handler.is_unchanged(
+            &secret,
+            plain,
+            SecretHeader {
+                targets_root: true,
+                mode: None,
+            }
+        ));
+        assert!(!handler.is_unchanged(&secret, b"new secret", SecretHeader::default()));
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn decrypt_rejects_truncated_files() {
+        let handler = test_handler();
+
+        for contents in [&b"short"[..], &SecretHeader::default().to_bytes()] {
+            let mut output = String::new();
+            assert_eq!(
+                handler.decrypt("truncated", contents, &mut output),
+                Err(ReturnCode::DecryptionFailed.into())
+            );
+            assert!(output.contains("truncated is not a valid tuckr secret"));
+        }
+    }
+
+    #[test]
+    fn parallel_decryption_keeps_order() {
+        let handler = test_handler();
+        let secrets_dir =
+            std::env::temp_dir().join(format!("tuckr-secrets-{}", std::process::id()));
+        fs::create_dir_all(&secrets_dir).unwrap();
+
+        let files: Vec<_> = (0..5)
+            .map(|i| {
+                let file = secrets_dir.join(i.to_string());
+                let encrypted = handler
+                    .encrypt(
+                        i.to_string().as_bytes(),
+                        SecretHeader::default(),
+                        &mut String::new(),
+                    )
+                    .unwrap();
+                fs::write(&file, encrypted).unwrap();
+                file
+            })
+            .collect();
+
+        let results = decrypt_files(&handler, &files, NonZeroUsize::new(2).unwrap(), false);
+        let decrypted: Vec<_> = results
+            .into_iter()
+            .map(|result| String::from_utf8(result.unwrap().1).unwrap())
+            .collect();
+        assert_eq!(decrypted, ["0", "1", "2", "3", "4"]);
+
+        fs::remove_dir_all(secrets_dir).unwrap();
+    }
+}
@@ -11,90 +11,223 @@
 //! $HOME equivalents are pointing to them and categorizing them accordingly.
 
 use crate::dotfiles::{self, Dotfile, DotfileType, ReturnCode};
-use owo_colors::OwoColorize;
-use std::collections::{HashMap, HashSet};
+use crate::fileops;
+use crate::secrets;
+use crate::templates;
+use ignore::gitignore::GitignoreBuilder;
+use owo_colors::{OwoColorize, Stream};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs;
-use std::io::Write;
-use std::path::PathBuf;
-use std::process::ExitCode;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, ExitCode};
+use std::sync::mpsc;
+use std::time::{Duration, SystemTime};
 use tabled::{Table, Tabled};
 
-fn symlink_file(f: PathBuf, output: &mut String) -> Result<(), ReturnCode> {
-    match Dotfile::try_from(f.clone()) {
-        Ok(group) => {
-            let target_path = group.to_target_path(output);
-            if target_path.exists() {
-                return Err(ReturnCode::CouldntSymlinkFile);
-            }
+/// How long `watch` waits for file events to settle before redeploying
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(500);
 
-            #[cfg(target_family = "unix")]
-            {
-                if let Err(err) = std::os::unix::fs::symlink(f, target_path) {
-                    output.push_str(&format!(
-                        "failed to symlink group `{}`: {}",
-                        group.group_name,
-                        err,
-                    ));
-                    return Ok(());
-                } else { Err(ReturnCode::CouldntSymlinkFile) }
-            }
+/// Symlinks a file from dotfiles/Configs to where it should be deployed
+///
+/// Files listed in their group's `.tuckr-links` are also symlinked to each of their extra targets,
+/// the first error is returned after trying all of them
+pub fn symlink_file(f: PathBuf, output: &mut String) -> Result<(), dotfiles::Error> {
+    let group = Dotfile::try_from(f.clone())?;
+    let mut target_paths = vec![group.try_to_target_path(output)?];
+    target_paths.extend(group.extra_target_paths(output)?);
+
+    // templates get a rendered copy instead of a symlink
+    let rendered = if group.is_template() {
+        Some(templates::render_file(&group, output)?)
+    } else {
+        None
+    };
 
-            #[cfg(target_family = "windows")]
-            {
-                let result = if f.is_dir() {
-                    std::os::windows::fs::symlink_dir(f, target_path)
-                } else {
-                    std::os::windows::fs::symlink_file(f, target_path)
-                };
+    let mut result = Ok(());
+    for target_path in target_paths {
+        tracing::debug!(dotfile = %f.display(), target = %target_path.display(), "symlinking");
+        let target_result = match &rendered {
+            Some(content) => write_rendered(content, target_path),
+            None => symlink_to(&f, target_path),
+        };
+        if let Err(err) = target_result {
+            tracing::debug!(dotfile = %f.display(), "couldn't symlink: {err}");
+            result = result.and(Err(err));
+        }
+    }
 
-                if let Err(err) = result {
-                    output.push_str(format!(
-                        "failed to symlink group `{}`: {}",
-                        group.group_name,
-                        err,
-                    ));
-                }
-            }
+    result
+}
+
+/// Makes sure nothing is in the way of target_path and that its parent directory exists
+fn prepare_target(target_path: &Path) -> Result<(), dotfiles::Error> {
+    if target_path.exists() {
+        return Err(dotfiles::Error::AlreadyExists {
+            path: target_path.to_path_buf(),
+        });
+    }
+
+    // targets can be nested inside of directories that don't exist yet, eg: on a fresh machine
+    if let Some(parent) = target_path.parent() {
+        fs::create_dir_all(parent).map_err(|source| dotfiles::Error::Io {
+            operation: "create",
+            path: parent.to_path_buf(),
+            source,
+        })?;
+    }
+
+    Ok(())
+}
+
+fn write_rendered(content: &str, target_path: PathBuf) -> Result<(), dotfiles::Error> {
+    prepare_target(&target_path)?;
+    fs::write(&target_path, content).map_err(|source| dotfiles::Error::Io {
+        operation: "write",
+        path: target_path,
+        source,
+    })
+}
+
+fn symlink_to(f: &Path, target_path: PathBuf) -> Result<(), dotfiles::Error> {
+    prepare_target(&target_path)?;
+
+    #[cfg(target_family = "unix")]
+    let result = std::os::unix::fs::symlink(f, &target_path);
+
+    #[cfg(target_family = "windows")]
+    let result = if f.is_dir() {
+        std::os::windows::fs::symlink_dir(f, &target_path)
+    } else {
+        std::os::windows::fs::symlink_file(f, &target_path)
+    };
+
+    result.map_err(|source| dotfiles::Error::Symlink {
+        path: target_path,
+        source,
+    })
+}
+
+/// Reports a failed symlink, conflicts are left out since they're shown by `tuckr status`
+///
+/// Returns whether the symlink failed for any other reason than a conflict
+fn report_symlink_error(err: dotfiles::Error, output: &mut String) -> bool {
+    if matches!(err, dotfiles::Error::AlreadyExists { .. }) {
+        return false;
+    }
+
+    output.push_str(&format!("{err}\n"));
+    true
+}
+
+/// Reports an error to `output`, returning the code it exits with
+fn report_error(err: dotfiles::Error, output: &mut String) -> ReturnCode {
+    output.push_str(&format!("{err}\n"));
+    err.into()
+}
+
+type HashCache = HashMap<String, HashSet<Dotfile>>;
+
+/// What is already at a dotfile's target path when it can't be symlinked
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConflictKind {
+    File,
+    Dir,
+    /// A symlink that points outside of the dotfile's group
+    Symlink(PathBuf),
+}
+
+impl ConflictKind {
+    /// Short name used when listing conflicts
+    fn label(&self) -> &'static str {
+        match self {
+            ConflictKind::File => "file",
+            ConflictKind::Dir => "directory",
+            ConflictKind::Symlink(_) => "foreign symlink",
         }
+    }
+}
 
-        Err(err) => {
-            output.push_str(&err);
-            output.push_str(&format!("Failed to link {}.", f.to_str().unwrap()));
-            Ok(())
+impl std::fmt::Display for ConflictKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConflictKind::File => write!(f, "already exists"),
+            ConflictKind::Dir => write!(f, "already exists as a directory"),
+            ConflictKind::Symlink(target) => write!(f, "symlinks to {}", target.display()),
         }
     }
 }
 
-type HashCache = HashMap<String, HashSet<Dotfile>>;
+/// Restricts which kind of dotfiles get symlinked
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LinkOnly {
+    #[default]
+    Everything,
+    Files,
+    Dirs,
+}
+
+impl LinkOnly {
+    fn matches(self, path: &Path) -> bool {
+        match self {
+            LinkOnly::Everything => true,
+            LinkOnly::Files => !path.is_dir(),
+            LinkOnly::Dirs => path.is_dir(),
+        }
+    }
+}
 
 /// Handles dotfile symlinking and their current status
 struct SymlinkHandler {
-    dotfiles_dir: PathBuf,    // path to the dotfiles directory
-    symlinked: HashCache,     // dotfiles that have been symlinked from Dotfiles/Configs
+    dotfiles_dir: PathBuf,                      // path to the dotfiles directory
+    symlinked: HashCache, // dotfiles that have been symlinked from Dotfiles/Configs
     not_symlinked: HashCache, // dotfiles that haven't been symlinked to $HOME yet
     not_owned: HashCache, // dotfiles that are symlinks but points somewhere outside of their respective Dotfiles/Configs's group dir
+    first_offenders: HashMap<PathBuf, PathBuf>, // group path -> first file keeping the group from being symlinked
+    conflict_kinds: HashMap<PathBuf, ConflictKind>, // dotfile path -> what's in the way of its target
+    link_targets: HashMap<PathBuf, PathBuf>,        // dotfile path -> symlink that resolves to it
+    // target -> groups that all have a file for it
+    duplicate_targets: BTreeMap<PathBuf, Vec<String>>,
 }
 
 impl SymlinkHandler {
     /// Initializes SymlinkHandler and fills it dotfiles' status information
     fn try_new(output: &mut String) -> Result<Self, ExitCode> {
-        let dotfiles_dir = match dotfiles::get_dotfiles_path(output) {
-            Ok(dir) => dir,
-            Err(e) => {
-                output.push_str(&e.to_string());
-                return Err(ReturnCode::CouldntFindDotfiles.into());
-            }
-        };
+        Self::try_new_with(true, None, output)
+    }
+
+    /// Initializes SymlinkHandler like `try_new`, but without scanning groups that target
+    /// another platform
+    fn try_new_supported(output: &mut String) -> Result<Self, ExitCode> {
+        Self::try_new_with(false, None, output)
+    }
+
+    /// Initializes SymlinkHandler like `try_new_supported`, but only scanning the supplied groups
+    /// along with their platform specific groups
+    fn try_new_for(groups: &[String], output: &mut String) -> Result<Self, ExitCode> {
+        Self::try_new_with(false, Some(groups), output)
+    }
+
+    fn try_new_with(
+        include_unsupported: bool,
+        only: Option<&[String]>,
+        output: &mut String,
+    ) -> Result<Self, ExitCode> {
+        let dotfiles_dir = dotfiles::get_dotfiles_path(output)?;
 
         let symlinker = SymlinkHandler {
             dotfiles_dir,
             symlinked: HashCache::new(),
             not_symlinked: HashCache::new(),
             not_owned: HashCache::new(),
+            first_offenders: HashMap::new(),
+            conflict_kinds: HashMap::new(),
+            link_targets: HashMap::new(),
+            duplicate_targets: BTreeMap::new(),
         };
 
         // this fills the symlinker with dotfile status information
-        symlinker.validate(output)
+        symlinker.validate(include_unsupported, only, output)
     }
 
     /// **This function should not be used outside this scope**
@@ -102,25 +235,68 @@ impl SymlinkHandler {
     /// Checks which dotfiles are or are not symlinked and registers their Configs/$group path
     /// into the struct
     ///
+    /// include_unsupported: also scans groups that aren't valid targets on this platform
+    /// only: the groups that are scanned, every group is if None
+    ///
     /// Returns a copy of self with all the fields set accordingly
-    fn validate(mut self, output: &mut String) -> Result<Self, ExitCode> {
-        let configs_dir = Dotfile::try_from(self.dotfiles_dir.join("Configs")).unwrap();
+    fn validate(
+        mut self,
+        include_unsupported: bool,
+        only: Option<&[String]>,
+        output: &mut String,
+    ) -> Result<Self, ExitCode> {
+        let configs_dir =
+            Dotfile::try_from(DotfileType::Configs.dir_in(&self.dotfiles_dir)).unwrap();
+        // decrypted secrets are symlinked just like configs
+        let decrypted_dir = DotfileType::Decrypted.dir_in(&self.dotfiles_dir);
+        let decrypted_dir = decrypted_dir
+            .is_dir()
+            .then(|| Dotfile::try_from(decrypted_dir).unwrap());
 
         let mut symlinked = HashCache::new();
         let mut not_symlinked = HashCache::new();
         let mut not_owned = HashCache::new();
+        let mut conflict_kinds = HashMap::new();
+        let mut link_targets = HashMap::new();
+        let mut target_groups: HashMap<PathBuf, BTreeSet<String>> = HashMap::new();
 
         // iterates over every file inside dotfiles/Config and determines their symlink status
-        configs_dir.map(|f| {
+        let mut validate_file = |f: Dotfile| {
             // skip group directories otherwise it would try to link dotfiles/Configs/Groups to the users home
             if f.path == f.group_path {
                 return;
             }
 
-            let target = f.to_target_path(output);
+            // files targeting another platform inside of a group are never deployed, unlike
+            // groups targeting another platform which can still be shown
+            if !f.file_targets_platform() || (!include_unsupported && !f.is_valid_target()) {
+                return;
+            }
+
+            let base_group = dotfiles::base_group_name(&f.group_name);
+            if only.is_some_and(|groups| !groups.iter().any(|group| group == base_group)) {
+                return;
+            }
+
+            // files without a target can't be deployed, which is reported to `output`
+            let Ok(target) = f.to_target_path(output) else {
+                not_symlinked
+                    .entry(f.group_name.clone())
+                    .or_default()
+                    .insert(f);
+                return;
+            };
+
+            // directories are merged, so only files can be deployed to the same target twice
+            if !f.path.is_dir() {
+                target_groups
+                    .entry(target.clone())
+                    .or_default()
+                    .insert(f.group_name.clone());
+            }
 
             if target.is_symlink() {
-                let link = match fs::read_link(target) {
+                let link = match fs::read_link(&target) {
                     Ok(link) => link,
                     Err(err) => {
                         output.push_str(&err.to_string());
@@ -128,20 +304,43 @@ impl SymlinkHandler {
                     }
                 };
 
-                if link == f.path {
+                // files replaced by a more specific group or file, eg: `alacritty_linux` for
+                // `alacritty`, are as deployed as they're going to get
+                let overridden = Dotfile::try_from(link.clone()).is_ok_and(|linked| {
+                    dotfiles::overrides_group(&linked.group_name, &f.group_name)
+                        || dotfiles::overrides_file(&linked, &f)
+                });
+
+                let is_own_link = dotfiles::same_path(&link, &f.path);
+                if is_own_link || overridden {
+                    if is_own_link {
+                        link_targets.insert(f.path.clone(), target);
+                    }
                     symlinked.entry(f.group_name.clone()).or_default();
 
                     let group = symlinked.get_mut(&f.group_name).unwrap();
                     group.insert(f);
                 } else {
+                    conflict_kinds.insert(f.path.clone(), ConflictKind::Symlink(link));
                     not_owned.entry(f.group_name.clone()).or_default();
 
                     let group = not_owned.get_mut(&f.group_name).unwrap();
                     group.insert(f);
                 }
+            } else if f.is_template() && templates::is_rendered(&f, &target, output) {
+                symlinked.entry(f.group_name.clone()).or_default();
+
+                let group = symlinked.get_mut(&f.group_name).unwrap();
+                group.insert(f);
             } else {
                 if target.is_dir() {
-                    return;
+                    // directories are merged with the existing ones, only files can conflict with them
+                    if f.path.is_dir() {
+                        return;
+                    }
+                    conflict_kinds.insert(f.path.clone(), ConflictKind::Dir);
+                } else if target.exists() {
+                    conflict_kinds.insert(f.path.clone(), ConflictKind::File);
                 }
 
                 not_symlinked.entry(f.group_name.clone()).or_default();
@@ -149,7 +348,17 @@ impl SymlinkHandler {
                 let group = not_symlinked.get_mut(&f.group_name).unwrap();
                 group.insert(f);
             }
-        });
+        };
+        let scanned = configs_dir
+            .map(&mut validate_file)
+            .and_then(|()| match decrypted_dir {
+                Some(decrypted_dir) => decrypted_dir.map(&mut validate_file),
+                None => Ok(()),
+            });
+        if let Err(err) = scanned {
+            output.push_str(&format!("{err}\n"));
+            return Err(ReturnCode::from(err).into());
+        }
 
         fn remove_empty_groups(group_type: HashCache) -> HashCache {
             group_type
@@ -204,6 +413,37 @@ impl SymlinkHandler {
         self.symlinked = remove_empty_groups(symlinked);
         self.not_symlinked = remove_empty_groups(not_symlinked);
         self.not_owned = remove_empty_groups(not_owned);
+        self.conflict_kinds = conflict_kinds;
+        self.link_targets = link_targets;
+
+        // platform specific groups replacing the files of their base group aren't duplicates
+        self.duplicate_targets = target_groups
+            .into_iter()
+            .filter(|(_, groups)| {
+                let base_groups: HashSet<_> = groups
+                    .iter()
+                    .map(|group| dotfiles::base_group_name(group))
+                    .collect();
+                base_groups.len() > 1
+            })
+            .map(|(target, groups)| (target, groups.into_iter().collect()))
+            .collect();
+
+        // records the first file, in path order, that keeps each group from being fully symlinked
+        for file in self
+            .not_symlinked
+            .values()
+            .chain(self.not_owned.values())
+            .flatten()
+        {
+            let offender = self
+                .first_offenders
+                .entry(file.group_path.clone())
+                .or_insert_with(|| file.path.clone());
+            if file.path < *offender {
+                *offender = file.path.clone();
+            }
+        }
 
         Ok(self)
     }
@@ -236,8 +476,8 @@ impl SymlinkHandler {
                 // any file in this group is in the same target so just pick any file to check
                 let file = files.iter().next().unwrap();
 
-                group.starts_with(target_group)
-                    && dotfiles::group_ends_with_target_name(&file.group_name)
+                *group != target_group
+                    && dotfiles::is_related_group(group, target_group)
                     && file.is_valid_target()
             })
             .map(|(group, _)| group.clone())
@@ -249,66 +489,315 @@ impl SymlinkHandler {
     }
 
     /// Symlinks all the files of a group to the user's $HOME
-    fn add<'a>(&self, group: &'a str, output: &mut String) {
+    ///
+    /// exclude_files: gitignore style patterns relative to the group of files that aren't symlinked
+    ///
+    /// Fails if any file couldn't be symlinked for another reason than a conflict
+    fn add(
+        &self,
+        group: &str,
+        only: LinkOnly,
+        exclude_files: &[String],
+        output: &mut String,
+    ) -> Result<usize, ReturnCode> {
+        let _span = tracing::info_span!("add", group).entered();
         let Some(groups) = self.get_related_conditional_groups(group, false) else {
-            output.push_str("No target_group or any of its conditional groups are valid on the current platform");
-            return;
+            if self.get_related_conditional_groups(group, true).is_some() {
+                output.push_str(&format!("{group} is already symlinked.\n"));
+            } else {
+                output.push_str("No target_group or any of its conditional groups are valid on the current platform\n");
+            }
+            return Ok(0);
         };
 
-        for group in groups {
-            let group = Dotfile::try_from(self.dotfiles_dir.join("Configs").join(&group)).unwrap();
+        // decrypted secrets are added along with the configs of the group with the same name
+        let group_dirs = groups.iter().flat_map(|group| {
+            let dirs: Vec<_> = [DotfileType::Configs, DotfileType::Decrypted]
+                .map(|dtype| dtype.dir_in(&self.dotfiles_dir).join(group))
+                .into_iter()
+                .filter(|dir| dir.exists())
+                .collect();
+            if dirs.is_empty() {
+                vec![DotfileType::Configs.dir_in(&self.dotfiles_dir).join(group)]
+            } else {
+                dirs
+            }
+        });
+
+        let all_groups = dotfiles::list_groups(DotfileType::Configs).unwrap_or_default();
+        let mut any_failed = false;
+        let mut total_linked = 0;
+        for group in group_dirs {
+            let group = Dotfile::try_from(group).unwrap();
             if group.path.exists() {
+                // files that a more specific group also has are left for that group to deploy,
+                // directories containing them are deployed file by file instead
+                let mut overridden = Vec::new();
+                for overriding in all_groups
+                    .iter()
+                    .filter(|name| dotfiles::overrides_group(name, &group.group_name))
+                {
+                    let overriding = Dotfile::try_from(
+                        DotfileType::Configs
+                            .dir_in(&self.dotfiles_dir)
+                            .join(overriding),
+                    )
+                    .unwrap();
+                    overriding
+                        .map(|f| overridden.extend(f.to_target_path(output)))
+                        .map_err(|err| report_error(err, output))?;
+                }
+
+                // directories containing excluded files are deployed file by file instead
+                let mut excluded = Vec::new();
+                if !exclude_files.is_empty() {
+                    let mut builder = GitignoreBuilder::new(&group.path);
+                    for pattern in exclude_files {
+                        if let Err(e) = builder.add_line(None, pattern) {
+                            output.push_str(&format!("Invalid file exclusion `{pattern}`: {e}\n"));
+                        }
+                    }
+
+                    if let Ok(matcher) = builder.build() {
+                        let mapped = group.map(|f| {
+                            if matcher
+                                .matched_path_or_any_parents(&f.path, f.path.is_dir())
+                                .is_ignore()
+                            {
+                                excluded.push(f.path);
+                            }
+                        });
+                        if let Err(err) = mapped {
+                            return Err(report_error(err, output));
+                        }
+                    }
+                }
+
+                // directories containing templates are deployed file by file as well so that
+                // the templates can be rendered
+                let mut templates = Vec::new();
+                // files with a target suffix, eg: `config_linux`, are only deployed on their
+                // platform so the directories containing them are deployed file by file too
+                let mut conditional_files = Vec::new();
+                let mapped = group.map(|f| {
+                    if f.has_target_suffix() {
+                        conditional_files.push(f.clone());
+                    }
+                    if f.is_template() {
+                        templates.push(f.path);
+                    }
+                });
+                if let Err(err) = mapped {
+                    return Err(report_error(err, output));
+                }
+
+                // entries that are already symlinked, along with everything inside of them, are
+                // skipped so that only the missing links are created
+                let mut linked: Vec<PathBuf> = self
+                    .symlinked
+                    .get(&group.group_name)
+                    .into_iter()
+                    .flatten()
+                    .map(|f| f.path.clone())
+                    .collect();
+                let already_linked = linked.len();
+                let mut newly_linked = 0;
+
                 // iterate through all the files in group_dir
-                group.map(|f| { symlink_file(f.path, output); } );
+                let mapped = group.map(|f| {
+                    if !only.matches(&f.path) || linked.iter().any(|dir| f.path.starts_with(dir)) {
+                        return;
+                    }
+
+                    let is_excluded = |e: &PathBuf| e.starts_with(&f.path) || f.path.starts_with(e);
+                    if excluded.iter().any(is_excluded) {
+                        return;
+                    }
+
+                    let contains_template = |t: &PathBuf| t.starts_with(&f.path) && *t != f.path;
+                    if templates.iter().any(contains_template) {
+                        return;
+                    }
+
+                    if !f.file_targets_platform() {
+                        return;
+                    }
+                    let contains_conditional =
+                        |c: &Dotfile| c.path.starts_with(&f.path) && c.path != f.path;
+                    if conditional_files.iter().any(contains_conditional) {
+                        return;
+                    }
+
+                    // files with the same target as a more specific file are left for it
+                    let is_overridden = |c: &Dotfile| {
+                        dotfiles::overrides_file(c, &f)
+                            && c.to_target_path(&mut String::new()).ok()
+                                == f.to_target_path(&mut String::new()).ok()
+                    };
+                    if conditional_files.iter().any(is_overridden) {
+                        return;
+                    }
+
+                    if !overridden.is_empty() {
+                        let Ok(target) = f.to_target_path(output) else {
+                            return;
+                        };
+                        if overridden.iter().any(|o| o.starts_with(&target)) {
+                            return;
+                        }
+                    }
+
+                    match symlink_file(f.path.clone(), output) {
+                        Ok(()) => {
+                            newly_linked += 1;
+                            linked.push(f.path);
+                        }
+                        Err(err) => any_failed |= report_symlink_error(err, output),
+                    }
+                });
+                if let Err(err) = mapped {
+                    return Err(report_error(err, output));
+                }
+
+                total_linked += newly_linked;
+                if newly_linked > 0 {
+                    output.push_str(&format!(
+                        "Symlinked {newly_linked} new file(s) from {}, {already_linked} were already symlinked.\n",
+                        group.group_name
+                    ));
+                } else if already_linked > 0 {
+                    output.push_str(&format!("{} is already symlinked.\n", group.group_name));
+                }
             } else {
                 output.push_str(&format!(
-                    "{} {}",
-                    "There's no dotfiles for",
-                    group.group_name,
+                    "{} {}\n",
+                    "There's no dotfiles for", group.group_name,
                 ))
             }
         }
+
+        if any_failed {
+            Err(ReturnCode::CouldntSymlinkFile)
+        } else {
+            Ok(total_linked)
+        }
     }
 
-    /// Deletes symlinks from $HOME if they're owned by dotfiles dir
-    fn remove(&self, group: &str, output: &mut String) {
-        fn remove_symlink(file: PathBuf, output: &mut String) {
+    /// Deletes symlinks from $HOME if they're owned by dotfiles dir, returns how many were removed
+    fn remove(&self, group: &str, trash: bool, output: &mut String) -> usize {
+        let _span = tracing::info_span!("remove", group).entered();
+        let mut removed = 0;
+        let mut remove_symlink = |file: PathBuf, output: &mut String| {
             let dotfile = Dotfile::try_from(file).unwrap();
-            let target_dotfile = dotfile.to_target_path(output);
-            let Ok(linked) = fs::read_link(&target_dotfile) else {
+            let Ok(target) = dotfile.to_target_path(output) else {
                 return;
             };
+            let mut target_dotfiles = vec![target];
+            target_dotfiles.extend(dotfile.extra_target_paths(output).unwrap_or_default());
 
-            if dotfile.path != linked {
-                return;
+            for target_dotfile in target_dotfiles {
+                // rendered templates are only removed while they haven't been edited
+                let is_deployed = if dotfile.is_template() {
+                    templates::is_rendered(&dotfile, &target_dotfile, output)
+                } else {
+                    fs::read_link(&target_dotfile)
+                        .is_ok_and(|linked| dotfiles::same_path(&dotfile.path, &linked))
+                };
+
+                if !is_deployed {
+                    continue;
+                }
+
+                tracing::debug!(target = %target_dotfile.display(), "removing symlink");
+                match fileops::trash_or_remove(&target_dotfile, trash, output) {
+                    Ok(()) => removed += 1,
+                    Err(err) => output.push_str(&format!(
+                        "error with path `{}`: {err}\n",
+                        target_dotfile.display()
+                    )),
+                }
             }
+        };
 
-            if target_dotfile.is_dir() {
-                fs::remove_dir_all(&target_dotfile).unwrap();
-            } else {
-                match fs::remove_file(&target_dotfile)
-                .map_err(|err| format!("error with path `{}`: {err}", target_dotfile.display())) {
-                    Ok(_) => (),
-                    Err(s) => { |output: &mut String| output.push_str(&s); ()}
+        // every related group is checked, not only the symlinked ones, so that partially
+        // symlinked groups don't leave anything behind
+        for dtype in [DotfileType::Configs, DotfileType::Decrypted] {
+            let groups = dotfiles::list_groups(dtype).unwrap_or_default();
+            for group in groups
+                .iter()
+                .filter(|name| dotfiles::is_related_group(name, group))
+            {
+                let group =
+                    Dotfile::try_from(dtype.dir_in(&self.dotfiles_dir).join(group)).unwrap();
+                if let Err(err) = group.map(|f| remove_symlink(f.path, output)) {
+                    report_error(err, output);
                 }
             }
         }
 
-        let Some(groups) = self.get_related_conditional_groups(group, true) else {
-            return;
-        };
+        removed
+    }
 
-        for group in groups {
-            let group = Dotfile::try_from(self.dotfiles_dir.join("Configs").join(&group)).unwrap();
+    /// Deletes the symlinks to the group's files that no longer exist, eg: after they were renamed
+    ///
+    /// Only the directories the group deploys files into are searched, returns how many symlinks
+    /// were deleted
+    fn prune(&self, group: &str, output: &mut String) -> usize {
+        let _span = tracing::info_span!("prune", group).entered();
+
+        let mut pruned = 0;
+        for dtype in [DotfileType::Configs, DotfileType::Decrypted] {
+            let groups = dotfiles::list_groups(dtype).unwrap_or_default();
+            for group in groups
+                .iter()
+                .filter(|name| dotfiles::is_related_group(name, group))
+            {
+                let group =
+                    Dotfile::try_from(dtype.dir_in(&self.dotfiles_dir).join(group)).unwrap();
 
-            if !group.path.exists() {
-                output.push_str("There's no group called ");
-                output.push_str(&group.group_name);
-                continue;
-            }
+                // the directories the group's files are deployed into
+                let mut target_dirs = BTreeSet::new();
+                let mapped = group.map(|f| {
+                    let Ok(target) = f.to_target_path(&mut String::new()) else {
+                        return;
+                    };
+                    if f.path.is_dir() {
+                        target_dirs.insert(target.clone());
+                    }
+                    target_dirs.extend(target.parent().map(Path::to_path_buf));
+                });
+                if let Err(err) = mapped {
+                    report_error(err, output);
+                }
+
+                for target_dir in target_dirs {
+                    let Ok(entries) = fs::read_dir(&target_dir) else {
+                        continue;
+                    };
+
+                    for entry in entries.filter_map(|entry| entry.ok()) {
+                        let target = entry.path();
+                        let is_dangling = fs::read_link(&target).is_ok_and(|source| {
+                            source.starts_with(&group.path) && fs::symlink_metadata(source).is_err()
+                        });
+                        if !is_dangling {
+                            continue;
+                        }
 
-            group.map(|f| remove_symlink(f.path, output));
+                        tracing::debug!(target = %target.display(), "removing dangling symlink");
+                        match fileops::remove_path(&target) {
+                            Ok(()) => pruned += 1,
+                            Err(err) => output.push_str(&format!(
+                                "error with path `{}`: {err}\n",
+                                target.display()
+                            )),
+                        }
+                    }
+                }
+            }
         }
+
+        pruned
     }
 }
 
@@ -332,24 +821,41 @@ where
     // loads the runtime information needed to carry out actions
     let sym = SymlinkHandler::try_new(output)?;
 
-    // detect if user provided an invalid group
-    if let Some(invalid_groups) = dotfiles::check_invalid_groups(DotfileType::Configs, groups, output) {
+    // detect if user provided an invalid group, groups with decrypted secrets are valid as well
+    let invalid_groups = dotfiles::check_invalid_groups(DotfileType::Configs, groups, output)
+        .and_then(|groups| dotfiles::check_invalid_groups(DotfileType::Decrypted, &groups, output));
+    if let Some(invalid_groups) = invalid_groups {
         for group in invalid_groups {
-            output.push_str(&group);
-            output.push_str(" doesn't exist.");
+            output.push_str(&format!("{group} doesn't exist.\n"));
         }
         return Err(ReturnCode::NoSetupFolder.into());
     }
 
+    // groups holding subgroups stand for all of them
+    let expand = |groups: &[String]| -> Vec<String> {
+        groups
+            .iter()
+            .flat_map(|group| {
+                dotfiles::expand_group(group, &[DotfileType::Configs, DotfileType::Decrypted])
+            })
+            .collect()
+    };
+    let exclude = expand(exclude);
+
     // handles wildcard
     if groups.contains(&"*".to_string()) {
-        let symgroups = if symlinked {
-            &sym.not_symlinked
+        // symlinks are removed from every group since partially symlinked groups could be missed
+        let symgroups: Vec<String> = if symlinked {
+            sym.not_symlinked.keys().cloned().collect()
         } else {
-            &sym.symlinked
+            let mut groups = dotfiles::list_groups(DotfileType::Configs)?;
+            groups.extend(dotfiles::list_groups(DotfileType::Decrypted).unwrap_or_default());
+            groups.sort();
+            groups.dedup();
+            groups
         };
 
-        for group in symgroups.keys() {
+        for group in &symgroups {
             // Takes the name of the group to be passed the function
             // Ignore groups in the excludes array
             if exclude.contains(group) {
@@ -363,115 +869,661 @@ where
         return Ok(());
     }
 
-    for group in groups {
-        if exclude.contains(group) {
+    for group in expand(groups) {
+        if exclude.contains(&group) {
             continue;
         }
-        func(&sym, group, output);
+        func(&sym, &group, output);
     }
 
     Ok(())
 }
 
-/// Adds symlinks
-pub fn add_cmd(
-    groups: &[String],
-    exclude: &[String],
-    force: bool,
-    adopt: bool,
-) -> (String, ExitCode) {
-    let mut output = "".to_string();
+/// What `add` does with the files that are in the way of its dotfiles
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Leaves the files as they are, their dotfiles aren't symlinked
+    #[default]
+    Skip,
+    /// Replaces the files with symlinks to their dotfiles
+    Overwrite,
+    /// Moves the files out of the way before symlinking their dotfiles
+    Backup,
+    /// Doesn't symlink the group and exits with an error
+    Fail,
+}
 
-    let for_group = foreach_group(groups, exclude, true, &mut output, |sym: &SymlinkHandler, group, mut output| {
-        // Symlink dotfile by force
-        if force {
-            let mut remove_overlapping_files = |status_group: &HashCache| {
-                for (group, group_files) in status_group {
-                    if !groups.contains(group) {
-                        continue;
-                    }
+/// What to do with a file that's in the way of a dotfile
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConflictAction {
+    Skip,
+    Overwrite,
+    Backup,
+}
 
-                    for file in group_files {
-                        let target_file = file.to_target_path(output);
-                        if target_file.is_dir() {
-                            fs::remove_dir_all(target_file).unwrap();
-                        } else if target_file.is_file() {
-                            fs::remove_file(target_file).unwrap();
-                        }
-                    }
-                }
-            };
+/// Asks the user what to do with a target that conflicts with a dotfile
+///
+/// The prompt and diffs are written to stderr since they're only meant for the user, what's done
+/// with the file is reported to the command's output by the caller
+fn prompt_conflict_action(dotfile: &Dotfile, target: &Path) -> ConflictAction {
+    loop {
+        eprint!(
+            "{} already exists. [s]kip / [o]verwrite / [b]ackup / [d]iff: ",
+            target.display()
+        );
+        _ = io::stderr().flush();
 
-            remove_overlapping_files(&sym.not_owned);
-            remove_overlapping_files(&sym.not_symlinked);
+        let mut answer = String::new();
+        match io::stdin().read_line(&mut answer) {
+            // stdin was closed so there's nobody left to answer
+            Ok(0) | Err(_) => return ConflictAction::Skip,
+            Ok(_) => (),
         }
 
-        // Discard dotfile and adopt the conflicting dotfile
-        if adopt {
-            let mut adopt_overlapping_files = |status_group: &HashCache| {
-                for (group, group_files) in status_group {
-                    if !groups.contains(group) {
-                        continue;
-                    }
+        match answer.trim().to_lowercase().as_str() {
+            "s" | "skip" => return ConflictAction::Skip,
+            "o" | "overwrite" => return ConflictAction::Overwrite,
+            "b" | "backup" => return ConflictAction::Backup,
+            "d" | "diff" => {
+                if let Err(e) = Command::new("diff")
+                    .arg("-ru")
+                    .arg(target)
+                    .arg(&dotfile.path)
+                    .stdout(io::stderr())
+                    .status()
+                {
+                    eprintln!("Failed to run diff: {e}");
+                }
+            }
+            _ => eprintln!("Unrecognized option."),
+        }
+    }
+}
 
-                    for file in group_files {
-                        let target_file = file.to_target_path(output);
-                        if target_file.is_dir() {
-                            fs::remove_dir_all(&file.path).unwrap();
-                        } else if target_file.is_file() {
-                            fs::remove_file(&file.path).unwrap();
-                        }
+/// Extension of the backups made of conflicting files, which are named after when they were
+/// made, eg: `.zshrc.1700000000.tuckr-bak`
+pub const BACKUP_EXTENSION: &str = "tuckr-bak";
 
-                        fs::rename(target_file, &file.path).unwrap();
-                    }
-                }
+/// Returns the path a conflicting file is moved to when it's backed up
+///
+/// backup_dir: mirrors the target's path relative to $HOME, or to the root for files outside of
+/// it, inside of this directory instead of leaving the backup next to the target
+fn backup_path(target: &Path, backup_dir: Option<&Path>) -> PathBuf {
+    let backup = match backup_dir {
+        Some(backup_dir) => {
+            let home_dir = dirs::home_dir().unwrap_or_default();
+            let relative_path: PathBuf = match target.strip_prefix(&home_dir) {
+                Ok(relative_path) => relative_path.into(),
+                Err(_) => target
+                    .components()
+                    .filter(|component| matches!(component, Component::Normal(_)))
+                    .collect(),
             };
-
-            adopt_overlapping_files(&sym.not_owned);
-            adopt_overlapping_files(&sym.not_symlinked);
+            backup_dir.join(relative_path)
         }
+        None => target.to_path_buf(),
+    };
 
-        sym.add(group, &mut output);
-    });
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or_default();
+    let file_name = backup.file_name().unwrap_or_default().to_os_string();
+
+    // backups of the same file made within a second are numbered so they don't replace each other
+    let mut timestamped = backup.with_file_name(file_name.clone());
+    for count in 0.. {
+        let mut backup_name = file_name.clone();
+        match count {
+            0 => backup_name.push(format!(".{timestamp}.{BACKUP_EXTENSION}")),
+            _ => backup_name.push(format!(".{timestamp}-{count}.{BACKUP_EXTENSION}")),
+        }
 
-    match for_group {
-        Ok(()) => (output, ExitCode::SUCCESS),
-        Err(e) => (output, e),
+        timestamped = backup.with_file_name(backup_name);
+        if !timestamped.exists() && !timestamped.is_symlink() {
+            break;
+        }
     }
+
+    timestamped
 }
 
-/// Removes symlinks
-pub fn remove_cmd(groups: &[String], exclude: &[String]) ->(String, ExitCode) {
-    let mut output: String = "".into();
-    match foreach_group(groups, exclude, false, &mut output,
-        |sym, p, mut output| sym.remove(p, &mut output)) {
-            Ok(()) => (output, ExitCode::SUCCESS),
-            Err(e) => (output, e),
-        }
+/// Returns when a backup was made from the timestamp in its name, or None if it isn't named like
+/// one of tuckr's backups
+fn backup_time(backup: &Path) -> Option<std::time::SystemTime> {
+    let timestamp: u64 = backup
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.strip_suffix(&format!(".{BACKUP_EXTENSION}")))
+        .and_then(|name| name.rsplit_once('.'))
+        .and_then(|(_, timestamp)| {
+            let timestamp = timestamp
+                .split_once('-')
+                .map_or(timestamp, |(time, _)| time);
+            timestamp.parse().ok()
+        })?;
+
+    std::time::UNIX_EPOCH.checked_add(Duration::from_secs(timestamp))
 }
 
-/// returns a cache with files in dotfiles that already exist in $HOME
-fn get_conflicts_in_cache(cache: &HashCache, output: &mut String) -> HashCache {
-    let mut conflicts = HashCache::new();
+/// Moves a conflicting file out of the way, creating the directories it's moved into if needed
+fn backup_file(target: &Path, backup_dir: Option<&Path>) -> Result<PathBuf, dotfiles::Error> {
+    let backup = backup_path(target, backup_dir);
+    let io_error = |source| dotfiles::Error::Io {
+        operation: "back up",
+        path: target.to_path_buf(),
+        source,
+    };
 
-    // mark group as conflicting if at least one value already exists in $HOME
-    for files in cache.values() {
-        for file in files {
-            if !file.to_target_path(output).exists() || !file.is_valid_target() {
-                continue;
+    if let Some(parent) = backup.parent() {
+        fs::create_dir_all(parent).map_err(io_error)?;
+    }
+    fs::rename(target, &backup).map_err(io_error)?;
+
+    Ok(backup)
+}
+
+/// Asks the user how to handle every conflicting file in the group and symlinks the ones that were freed
+///
+/// backup_dir: where backed up files are moved to, see `backup_path`
+fn resolve_conflicts_interactively(
+    sym: &SymlinkHandler,
+    group: &str,
+    backup_dir: Option<&Path>,
+    output: &mut String,
+) {
+    let existing_files = get_conflicts_in_cache(&sym.not_symlinked, output);
+    let mut conflicts: Vec<_> = sym
+        .not_owned
+        .iter()
+        .chain(existing_files.iter())
+        .filter(|(group_name, _)| dotfiles::is_related_group(group_name, group))
+        .flat_map(|(_, files)| files.iter().cloned())
+        .collect();
+    conflicts.sort_by(|a, b| a.path.cmp(&b.path));
+
+    // scripts and CI can't answer, so nothing is touched instead of waiting on them
+    if !conflicts.is_empty() && !io::stdin().is_terminal() {
+        output.push_str(&format!(
+            "Not prompting since stdin isn't a terminal, {} conflicting file(s) were left as they are. Use --force or --adopt to resolve them without prompting.\n",
+            conflicts.len()
+        ));
+        return;
+    }
+
+    for dotfile in conflicts {
+        let Ok(target) = dotfile.to_target_path(output) else {
+            continue;
+        };
+        let result = match prompt_conflict_action(&dotfile, &target) {
+            ConflictAction::Skip => continue,
+            ConflictAction::Overwrite => {
+                fileops::remove_path(&target).map_err(|source| dotfiles::Error::Io {
+                    operation: "remove",
+                    path: target.clone(),
+                    source,
+                })
             }
+            ConflictAction::Backup => backup_file(&target, backup_dir).map(|backup| {
+                output.push_str(&format!(
+                    "Backed up {} to {}\n",
+                    target.display(),
+                    backup.display()
+                ));
+            }),
+        };
 
-            conflicts.entry(file.group_name.clone()).or_default();
-            let entry = conflicts.get_mut(&file.group_name).unwrap();
-            entry.insert(file.clone());
+        if let Err(err) = result {
+            output.push_str(&format!("{err}\n"));
+            continue;
+        }
+
+        if let Err(err) = symlink_file(dotfile.path, output) {
+            report_symlink_error(err, output);
         }
     }
+}
 
-    conflicts
+/// Returns the dotfiles of a group and of its platform specific groups that have something in
+/// the way of their target
+fn conflicting_files<'a>(
+    sym: &'a SymlinkHandler,
+    group: &'a str,
+) -> impl Iterator<Item = &'a Dotfile> {
+    sym.not_owned
+        .iter()
+        .chain(sym.not_symlinked.iter())
+        .filter(move |(name, _)| dotfiles::is_related_group(name, group))
+        .flat_map(|(_, files)| files.iter())
+        .filter(|file| sym.conflict_kinds.contains_key(&file.path))
 }
 
-fn print_global_status(sym: &SymlinkHandler, output: &mut String) -> Result<(), ExitCode> {
-    #[derive(Tabled, Debug)]
+/// Returns the dotfiles of each group, and of their platform specific groups, that adding them
+/// would symlink without changing anything
+///
+/// overwrite: also returns the conflicting dotfiles, which are skipped otherwise
+pub fn pending_files(
+    groups: &[String],
+    overwrite: bool,
+    output: &mut String,
+) -> Result<HashMap<String, Vec<Dotfile>>, ExitCode> {
+    let sym = SymlinkHandler::try_new_supported(output)?;
+
+    let mut pending = HashMap::new();
+    for group in groups {
+        let mut files: Vec<_> = sym
+            .not_symlinked
+            .iter()
+            .chain(sym.not_owned.iter().filter(|_| overwrite))
+            .filter(|(name, _)| dotfiles::is_related_group(name, group))
+            .flat_map(|(_, files)| files.iter())
+            .filter(|file| overwrite || !sym.conflict_kinds.contains_key(&file.path))
+            .cloned()
+            .collect();
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        pending.insert(group.clone(), files);
+    }
+
+    Ok(pending)
+}
+
+/// How `add_cmd` deals with the groups it symlinks, the default leaves conflicts as they are
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AddOptions<'a> {
+    /// What's done with the files that are in the way of the dotfiles
+    pub on_conflict: ConflictPolicy,
+    /// Moves the conflicting files into the groups, replacing their dotfiles
+    pub adopt: bool,
+    /// Asks what to do with every conflicting file instead of leaving them as is
+    pub interactive: bool,
+    /// Which kind of dotfiles get symlinked
+    pub only: LinkOnly,
+    /// gitignore style patterns of files inside of the groups that aren't symlinked
+    pub exclude_files: &'a [String],
+    /// Where conflicting files are moved to when backing them up
+    pub backup_dir: Option<&'a Path>,
+    /// The only conflicting files `on_conflict` applies to, given either by their path or by the
+    /// path of their dotfile, it applies to every conflict if it's empty
+    pub force_files: &'a [PathBuf],
+}
+
+/// Adds symlinks
+pub fn add_cmd(groups: &[String], exclude: &[String], options: AddOptions) -> (String, ExitCode) {
+    let AddOptions {
+        on_conflict,
+        adopt,
+        interactive,
+        only,
+        exclude_files,
+        backup_dir,
+        force_files,
+    } = options;
+    let mut output = "".to_string();
+    let force_files: Vec<_> = force_files
+        .iter()
+        .filter_map(|file| std::path::absolute(file).ok())
+        .collect();
+
+    let failed = std::cell::Cell::new(false);
+    let symlink_failed = std::cell::Cell::new(false);
+    let changed = std::cell::Cell::new(false);
+
+    let conflicts_of = |sym: &SymlinkHandler, group: &str| -> Vec<Dotfile> {
+        conflicting_files(sym, group)
+            .filter(|file| {
+                force_files.is_empty()
+                    || force_files.iter().any(|forced| {
+                        file.to_target_path(&mut String::new())
+                            .is_ok_and(|target| dotfiles::same_path(forced, &target))
+                            || dotfiles::same_path(forced, &file.path)
+                    })
+            })
+            .cloned()
+            .collect()
+    };
+
+    // every group is checked before anything is symlinked so that failing leaves nothing half done
+    if on_conflict == ConflictPolicy::Fail {
+        let checked = foreach_group(groups, exclude, true, &mut output, |sym, group, output| {
+            if interactive {
+                resolve_conflicts_interactively(sym, group, backup_dir, output);
+            }
+
+            let conflicts = conflicts_of(sym, group);
+            for file in &conflicts {
+                let Ok(target_file) = file.to_target_path(output) else {
+                    continue;
+                };
+                output.push_str(&format!(
+                    "{} is in the way of {}\n",
+                    target_file.display(),
+                    file.path.display()
+                ));
+            }
+
+            if !conflicts.is_empty() {
+                output.push_str(&format!("Didn't symlink {group} since it has conflicts.\n"));
+                failed.set(true);
+            }
+        });
+
+        match checked {
+            Err(e) => return (output, e),
+            Ok(()) if failed.get() => {
+                output.push_str("Nothing was symlinked.\n");
+                return (output, ReturnCode::CouldntSymlinkFile.into());
+            }
+            Ok(()) => (),
+        }
+    }
+
+    let for_group = foreach_group(
+        groups,
+        exclude,
+        true,
+        &mut output,
+        |sym: &SymlinkHandler, group, output| {
+            // nothing else is symlinked once a group failed
+            if failed.get() {
+                return;
+            }
+
+            // conflicts were already resolved while checking the groups
+            if interactive && on_conflict != ConflictPolicy::Fail {
+                resolve_conflicts_interactively(sym, group, backup_dir, output);
+            }
+
+            let conflicts = conflicts_of(sym, group);
+
+            for file in &conflicts {
+                let Ok(target_file) = file.to_target_path(output) else {
+                    continue;
+                };
+                match on_conflict {
+                    ConflictPolicy::Skip => (),
+                    // foreign symlinks are removed without touching what they point to
+                    ConflictPolicy::Overwrite => {
+                        if let Err(e) = fileops::remove_path(&target_file) {
+                            output.push_str(&format!(
+                                "Couldn't remove {}: {e}\n",
+                                target_file.display()
+                            ));
+                        }
+                    }
+                    ConflictPolicy::Backup => match backup_file(&target_file, backup_dir) {
+                        Ok(backup) => output.push_str(&format!(
+                            "Backed up {} to {}\n",
+                            target_file.display(),
+                            backup.display()
+                        )),
+                        Err(err) => output.push_str(&format!("{err}\n")),
+                    },
+                    ConflictPolicy::Fail => {
+                        output.push_str(&format!(
+                            "{} is in the way of {}\n",
+                            target_file.display(),
+                            file.path.display()
+                        ));
+                        failed.set(true);
+                    }
+                }
+            }
+
+            if failed.get() {
+                output.push_str(&format!("Didn't symlink {group} since it has conflicts.\n"));
+                return;
+            }
+
+            // Discard dotfile and adopt the conflicting dotfile
+            if adopt {
+                let mut adopt_overlapping_files = |status_group: &HashCache| {
+                    for (group, group_files) in status_group {
+                        if !groups.contains(group) {
+                            continue;
+                        }
+
+                        for file in group_files {
+                            let Ok(target_file) = file.to_target_path(output) else {
+                                continue;
+                            };
+                            if target_file.is_dir() {
+                                fs::remove_dir_all(&file.path).unwrap();
+                            } else if target_file.is_file() {
+                                fs::remove_file(&file.path).unwrap();
+                            }
+
+                            fs::rename(target_file, &file.path).unwrap();
+                        }
+                    }
+                };
+
+                adopt_overlapping_files(&sym.not_owned);
+                adopt_overlapping_files(&sym.not_symlinked);
+            }
+
+            match sym.add(group, only, exclude_files, output) {
+                Ok(linked) => changed.set(changed.get() || linked > 0),
+                // the files symlinked before the failure still need to be recorded
+                Err(_) => {
+                    symlink_failed.set(true);
+                    changed.set(true);
+                }
+            }
+        },
+    );
+
+    // groups symlinked before one of them failed are kept so the manifest has to know about them
+    if changed.get() {
+        write_manifest(&mut output);
+    }
+
+    match for_group {
+        Ok(()) if failed.get() || symlink_failed.get() => {
+            (output, ReturnCode::CouldntSymlinkFile.into())
+        }
+        Ok(()) => (output, ExitCode::SUCCESS),
+        Err(e) => (output, e),
+    }
+}
+
+/// Removes symlinks
+///
+/// trash: moves the symlinks to the trash instead of deleting them
+pub fn remove_cmd(groups: &[String], exclude: &[String], trash: bool) -> (String, ExitCode) {
+    let mut output: String = "".into();
+    let removed = std::cell::Cell::new(0);
+    match foreach_group(groups, exclude, false, &mut output, |sym, p, output| {
+        removed.set(removed.get() + sym.remove(p, trash, output))
+    }) {
+        Ok(()) => {
+            if removed.get() > 0 {
+                write_manifest(&mut output);
+            }
+            (output, ExitCode::SUCCESS)
+        }
+        Err(e) => (output, e),
+    }
+}
+
+/// Brings the groups' symlinks up to date with their files
+///
+/// Symlinks to files that were removed or renamed are deleted and the files that aren't
+/// symlinked yet get symlinked
+pub fn sync_cmd(groups: &[String]) -> (String, ExitCode) {
+    let mut output = String::new();
+    let symlink_failed = std::cell::Cell::new(false);
+    let changed = std::cell::Cell::new(false);
+    let for_group = foreach_group(groups, &[], false, &mut output, |sym, group, output| {
+        let pruned = sym.prune(group, output);
+        if pruned > 0 {
+            output.push_str(&format!(
+                "Removed {pruned} dangling symlink(s) from {group}.\n"
+            ));
+        }
+
+        match sym.add(group, LinkOnly::Everything, &[], output) {
+            Ok(linked) => changed.set(changed.get() || pruned > 0 || linked > 0),
+            // the files symlinked before the failure still need to be recorded
+            Err(_) => {
+                symlink_failed.set(true);
+                changed.set(true);
+            }
+        }
+    });
+
+    if changed.get() {
+        write_manifest(&mut output);
+    }
+
+    match for_group {
+        Ok(()) if symlink_failed.get() => (output, ReturnCode::CouldntSymlinkFile.into()),
+        Ok(()) => (output, ExitCode::SUCCESS),
+        Err(e) => (output, e),
+    }
+}
+
+/// Deletes the backups made while resolving conflicts that are older than `older_than`
+///
+/// Backups are looked for next to the targets of every dotfile and anywhere inside of the
+/// `backup_dirs`, only files named like tuckr's backups are deleted, see `backup_time`
+pub fn clean_backups_cmd(older_than: Duration, backup_dirs: &[PathBuf]) -> (String, ExitCode) {
+    let mut output = String::new();
+    let dotfiles_dir = match dotfiles::get_dotfiles_path(&mut output) {
+        Ok(dir) => dir,
+        Err(e) => return (output, e.into()),
+    };
+    let Some(cutoff) = std::time::SystemTime::now().checked_sub(older_than) else {
+        return (output, ExitCode::SUCCESS);
+    };
+
+    // backups are only made next to targets so only their directories need to be searched
+    let mut target_dirs = BTreeSet::new();
+    for dtype in [DotfileType::Configs, DotfileType::Decrypted] {
+        let Ok(dir) = Dotfile::try_from(dtype.dir_in(&dotfiles_dir)) else {
+            continue;
+        };
+        if !dir.path.is_dir() {
+            continue;
+        }
+
+        let mapped = dir.map(|f| {
+            if f.path != f.group_path {
+                if let Ok(target) = f.to_target_path(&mut output) {
+                    target_dirs.extend(target.parent().map(Path::to_path_buf));
+                }
+            }
+        });
+        if let Err(err) = mapped {
+            report_error(err, &mut output);
+        }
+    }
+
+    let mut backups = Vec::new();
+    for dir in target_dirs {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+
+        backups.extend(
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| backup_time(path).is_some()),
+        );
+    }
+    for backup_dir in backup_dirs {
+        if !backup_dir.is_dir() {
+            output.push_str(&format!("{} does not exist.\n", backup_dir.display()));
+            continue;
+        }
+
+        // directories mirror where the backups came from, unless they were backed up themselves
+        let mut entries = walkdir::WalkDir::new(backup_dir).min_depth(1).into_iter();
+        while let Some(entry) = entries.next() {
+            let Ok(entry) = entry else {
+                continue;
+            };
+            if backup_time(entry.path()).is_none() {
+                continue;
+            }
+
+            if entry.file_type().is_dir() {
+                entries.skip_current_dir();
+            }
+            backups.push(entry.into_path());
+        }
+    }
+
+    let mut removed = 0;
+    let mut any_failed = false;
+    for backup in backups {
+        if backup_time(&backup).is_none_or(|time| time >= cutoff) {
+            continue;
+        }
+
+        match fileops::remove_path(&backup) {
+            Ok(()) => removed += 1,
+            Err(e) => {
+                output.push_str(&format!("Couldn't remove {}: {e}\n", backup.display()));
+                any_failed = true;
+            }
+        }
+    }
+
+    // directories that were emptied by removing their backups go as well
+    for backup_dir in backup_dirs {
+        for dir in walkdir::WalkDir::new(backup_dir)
+            .min_depth(1)
+            .contents_first(true)
+            .into_iter()
+            .flatten()
+            .filter(|entry| entry.file_type().is_dir())
+        {
+            _ = fs::remove_dir(dir.path());
+        }
+    }
+
+    output.push_str(&format!("Removed {removed} backup(s).\n"));
+    if any_failed {
+        (output, ExitCode::FAILURE)
+    } else {
+        (output, ExitCode::SUCCESS)
+    }
+}
+
+/// Returns true if any of the group's dotfiles are symlinked
+pub fn is_group_symlinked(group: &str, output: &mut String) -> Result<bool, ExitCode> {
+    let sym = SymlinkHandler::try_new(output)?;
+    Ok(sym.symlinked.contains_key(group))
+}
+
+/// returns a cache with files in dotfiles that already exist in $HOME
+fn get_conflicts_in_cache(cache: &HashCache, output: &mut String) -> HashCache {
+    let mut conflicts = HashCache::new();
+
+    // mark group as conflicting if at least one value already exists in $HOME
+    for files in cache.values() {
+        for file in files {
+            if !file
+                .to_target_path(output)
+                .is_ok_and(|target| target.exists())
+                || !file.is_valid_target()
+            {
+                continue;
+            }
+
+            conflicts.entry(file.group_name.clone()).or_default();
+            let entry = conflicts.get_mut(&file.group_name).unwrap();
+            entry.insert(file.clone());
+        }
+    }
+
+    conflicts
+}
+
+/// Lays out the symlinked and not symlinked groups side by side
+fn symlink_table(symlinked: &[&String], not_symlinked: &[&String]) -> Table {
+    #[derive(Tabled, Debug)]
     struct SymlinkRow<'a> {
         #[tabled(rename = "Symlinked")]
         symlinked: &'a str,
@@ -480,6 +1532,47 @@ fn print_global_status(sym: &SymlinkHandler, output: &mut String) -> Result<(),
         not_symlinked: &'a str,
     }
 
+    let empty_str = String::from("");
+    let status_rows: Vec<SymlinkRow> = {
+        let (longest, shortest, symlinked_is_longest) = if symlinked.len() >= not_symlinked.len() {
+            (&symlinked, &not_symlinked, true)
+        } else {
+            (&not_symlinked, &symlinked, false)
+        };
+
+        longest
+            .iter()
+            .zip(shortest.iter().chain(std::iter::repeat(&&empty_str)))
+            .map(|(longest, shortest)| SymlinkRow {
+                symlinked: if symlinked_is_longest {
+                    longest
+                } else {
+                    shortest
+                },
+
+                not_symlinked: if symlinked_is_longest {
+                    shortest
+                } else {
+                    longest
+                },
+            })
+            .collect()
+    };
+
+    use tabled::{format::Format, object::Columns, object::Rows, Margin, Modify, Style};
+
+    let mut sym_table = Table::new(status_rows);
+    sym_table
+        .with(Style::rounded())
+        .with(Margin::new(4, 4, 1, 1))
+        .with(Modify::new(Rows::first()).with(Format::new(|s| s.to_string())))
+        .with(Modify::new(Columns::single(0)).with(Format::new(|s| s.to_string())))
+        .with(Modify::new(Columns::single(1)).with(Format::new(|s| s.to_string())));
+
+    sym_table
+}
+
+fn print_global_status(sym: &SymlinkHandler, output: &mut String) -> Result<(), ExitCode> {
     // --- process status from symlink ---
     // groups that are both in symlinked and not_symlinked
     // will be marked as not_symlinked only
@@ -513,51 +1606,41 @@ fn print_global_status(sym: &SymlinkHandler, output: &mut String) -> Result<(),
         (symlinked, not_symlinked)
     };
 
-    let empty_str = String::from("");
-    let status_rows: Vec<SymlinkRow> = {
-        let (longest, shortest, symlinked_is_longest) = if symlinked.len() >= not_symlinked.len() {
-            (&symlinked, &not_symlinked, true)
-        } else {
-            (&not_symlinked, &symlinked, false)
-        };
-
-        longest
-            .iter()
-            .zip(shortest.iter().chain(std::iter::repeat(&&empty_str)))
-            .map(|(longest, shortest)| SymlinkRow {
-                symlinked: if symlinked_is_longest {
-                    longest
-                } else {
-                    shortest
-                },
+    // --- detect conflicts ---
+    // every conflicting group is listed along with the kinds of conflicts it has
+    let conflicts: Vec<_> = {
+        let mut conflict_labels: HashMap<&str, BTreeSet<&str>> = HashMap::new();
+        for file in sym
+            .not_symlinked
+            .values()
+            .chain(sym.not_owned.values())
+            .flatten()
+        {
+            if let Some(kind) = sym.conflict_kinds.get(&file.path) {
+                conflict_labels
+                    .entry(&file.group_name)
+                    .or_default()
+                    .insert(kind.label());
+            }
+        }
 
-                not_symlinked: if symlinked_is_longest {
-                    shortest
-                } else {
-                    longest
-                },
+        let mut conflicts: Vec<_> = conflict_labels
+            .into_iter()
+            .map(|(group, labels)| {
+                format!(
+                    "{group} ({})",
+                    labels.into_iter().collect::<Vec<_>>().join(", ")
+                )
             })
-            .collect()
+            .collect();
+        conflicts.sort();
+        conflicts
     };
 
-    // --- detect conflicts ---
-    let conflicts = get_conflicts_in_cache(&sym.not_symlinked, output);
-    // whether a conflict is a symlink or a pre-existing file does not matter for global status
-    // so we just add them together
-    let conflicts: HashSet<_> = conflicts.keys().chain(sym.not_owned.keys()).collect();
-
     // --- Creates all the tables and prints them ---
-    use tabled::{
-        col, format::Format, object::Columns, object::Rows, Alignment, Margin, Modify, Style,
-    };
+    use tabled::{col, Alignment, Style};
 
-    let mut sym_table = Table::new(status_rows);
-    sym_table
-        .with(Style::rounded())
-        .with(Margin::new(4, 4, 1, 1))
-        .with(Modify::new(Rows::first()).with(Format::new(|s| s.to_string())))
-        .with(Modify::new(Columns::single(0)).with(Format::new(|s| s.to_string())))
-        .with(Modify::new(Columns::single(1)).with(Format::new(|s| s.to_string())));
+    let sym_table = symlink_table(&symlinked, &not_symlinked);
 
     let mut conflict_table = Table::builder(&conflicts)
         .set_columns(["Conflicting Dotfiles".to_string()])
@@ -578,7 +1661,9 @@ fn print_global_status(sym: &SymlinkHandler, output: &mut String) -> Result<(),
     output.push_str(&(final_table.to_string() + "\n"));
 
     if !conflicts.is_empty() {
-        output.push_str("\nTo learn more about conflicting dotfiles run: `tuckr status <group...>`");
+        output.push_str(
+            "\nTo learn more about conflicting dotfiles run: `tuckr status <group...>`\n",
+        );
     }
 
     // Determines exit code for the command based on the dotfiles' status
@@ -589,8 +1674,12 @@ fn print_global_status(sym: &SymlinkHandler, output: &mut String) -> Result<(),
     }
 }
 
-fn print_groups_status(sym: &SymlinkHandler, groups: Vec<String>, output: &mut String) -> Result<(), ExitCode> {
-    let mut get_related_groups =
+fn print_groups_status(
+    sym: &SymlinkHandler,
+    groups: Vec<String>,
+    output: &mut String,
+) -> Result<(), ExitCode> {
+    let get_related_groups =
         |sym: &SymlinkHandler, not_symlinked_groups: Option<&Vec<String>>| -> Vec<String> {
             let mut related_groups = Vec::new();
 
@@ -626,9 +1715,6 @@ fn print_groups_status(sym: &SymlinkHandler, groups: Vec<String>, output: &mut S
 
             related_groups.sort();
             related_groups.dedup();
-            for group in &related_groups {
-                output.push_str(group);
-            }
             related_groups
         };
 
@@ -645,7 +1731,10 @@ fn print_groups_status(sym: &SymlinkHandler, groups: Vec<String>, output: &mut S
     let unsupported = {
         let mut unsupported = groups
             .iter()
-            .map(|group| Dotfile::try_from(sym.dotfiles_dir.join("Configs").join(group)).unwrap())
+            .map(|group| {
+                Dotfile::try_from(DotfileType::Configs.dir_in(&sym.dotfiles_dir).join(group))
+                    .unwrap()
+            })
             .filter(|group| !group.is_valid_target())
             .map(|group| group.group_name)
             .collect::<Vec<_>>();
@@ -656,114 +1745,1104 @@ fn print_groups_status(sym: &SymlinkHandler, groups: Vec<String>, output: &mut S
     };
 
     if !not_symlinked.is_empty() || !not_owned.is_empty() {
-        let print_conflicts = |conflicts_cache: &HashCache, group: &str, msg: &str, output: &mut String| {
-            let Some(conflicts) = conflicts_cache.get(group) else {
-                return;
+        let print_conflicts = |group: &str, output: &mut String| {
+            let mut conflicts: Vec<_> = sym
+                .not_symlinked
+                .get(group)
+                .into_iter()
+                .chain(sym.not_owned.get(group))
+                .flatten()
+                .filter_map(|file| {
+                    let kind = sym.conflict_kinds.get(&file.path)?;
+                    Some((file.display_target_path(output).ok()?, kind))
+                })
+                .collect();
+            conflicts.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+            for (conflict, kind) in conflicts {
+                output.push_str(&format!(
+                    "\t\t-> {} {}\n",
+                    conflict.display(),
+                    kind.if_supports_color(Stream::Stdout, |text| text.yellow())
+                ));
+            }
+        };
+
+        output.push_str(&format!(
+            "{}\n",
+            "Not Symlinked:".if_supports_color(Stream::Stdout, |text| text.red())
+        ));
+        for group in &not_symlinked {
+            output.push_str(&format!("\t{group}\n"));
+            print_conflicts(group, output);
+        }
+    }
+
+    if !symlinked.is_empty() {
+        output.push_str(&format!(
+            "{}\n",
+            "Symlinked:".if_supports_color(Stream::Stdout, |text| text.green())
+        ));
+        for group in symlinked {
+            output.push_str(&format!("\t{group}\n"));
+        }
+    }
+
+    if !unsupported.is_empty() {
+        output.push_str(&format!(
+            "{}\n",
+            "Not supported on this platform:"
+                .if_supports_color(Stream::Stdout, |text| text.yellow())
+        ));
+        for group in unsupported {
+            output.push_str(&format!("\t{group}\n"));
+        }
+    }
+
+    let invalid_groups = dotfiles::check_invalid_groups(DotfileType::Configs, &groups, output);
+    if let Some(invalid_groups) = &invalid_groups {
+        output.push_str(&format!(
+            "{}\n",
+            "Following groups do not exist:".if_supports_color(Stream::Stdout, |text| text.red())
+        ));
+        for group in invalid_groups {
+            output.push_str(&format!("\t{group}\n"));
+        }
+    }
+
+    if !not_symlinked.is_empty() {
+        output.push_str("\nCheck `tuckr help add` to learn how to fix symlinks.\n");
+    }
+
+    if invalid_groups.is_some() {
+        return Err(ReturnCode::NoSetupFolder.into());
+    }
+
+    Ok(())
+}
+
+/// Watches groups, symlinking the files that are added and removing the symlinks of the ones that
+/// are removed or renamed
+///
+/// It runs until it's interrupted, eg: with Ctrl+C, which stops it right away. The output of each
+/// batch of changes is printed as soon as it's done, only the errors that stop watching are
+/// returned. Changes that were cut short by an interruption are picked up by `tuckr sync`
+pub fn watch_cmd(groups: &[String]) -> (String, ExitCode) {
+    use notify::{EventKind, RecursiveMode, Watcher};
+
+    let mut output = String::new();
+    let dotfiles_dir = match dotfiles::get_dotfiles_path(&mut output) {
+        Ok(dir) => dir,
+        Err(e) => {
+            output.push_str(&e.to_string());
+            return (output, e.into());
+        }
+    };
+
+    if let Some(invalid_groups) =
+        dotfiles::check_invalid_groups(DotfileType::Configs, groups, &mut output)
+    {
+        for group in invalid_groups {
+            output.push_str(&format!("{group} doesn't exist.\n"));
+        }
+        return (output, ReturnCode::NoSetupFolder.into());
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = match notify::recommended_watcher(tx) {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            output.push_str(&format!("Failed to start watching files: {e}\n"));
+            return (output, ExitCode::FAILURE);
+        }
+    };
+
+    let configs_dir = DotfileType::Configs.dir_in(&dotfiles_dir);
+    let watched_dirs: Vec<_> = if groups.contains(&"*".to_string()) {
+        vec![configs_dir]
+    } else {
+        groups.iter().map(|group| configs_dir.join(group)).collect()
+    };
+
+    for dir in &watched_dirs {
+        if let Err(e) = watcher.watch(dir, RecursiveMode::Recursive) {
+            output.push_str(&format!("Failed to watch {}: {e}\n", dir.display()));
+            return (output, ExitCode::FAILURE);
+        }
+    }
+
+    // every batch is printed once it's done since watching never returns on its own
+    let print_batch = |output: &mut String| {
+        print!("{output}");
+        _ = io::stdout().flush();
+        output.clear();
+    };
+
+    output.push_str("Watching for changes, press Ctrl+C to stop.\n");
+    print_batch(&mut output);
+
+    // returns the groups whose files were added or removed by the event
+    let changed_groups = |event: notify::Result<notify::Event>| -> Vec<String> {
+        let Ok(event) = event else {
+            return Vec::new();
+        };
+
+        let is_relevant = matches!(
+            event.kind,
+            EventKind::Create(_)
+                | EventKind::Remove(_)
+                | EventKind::Modify(notify::event::ModifyKind::Name(_))
+        );
+        if !is_relevant {
+            return Vec::new();
+        }
+
+        event
+            .paths
+            .into_iter()
+            .filter_map(|path| Dotfile::try_from(path).ok())
+            .filter(|dotfile| dotfile.path != dotfile.group_path && !dotfile.is_group_metadata())
+            .map(|dotfile| dotfile.group_name)
+            .collect()
+    };
+
+    while let Ok(event) = rx.recv() {
+        let mut pending: BTreeSet<String> = changed_groups(event).into_iter().collect();
+
+        // waits for a burst of events to settle so that groups are only redeployed once
+        while let Ok(event) = rx.recv_timeout(WATCH_DEBOUNCE) {
+            pending.extend(changed_groups(event));
+        }
+
+        if pending.is_empty() {
+            continue;
+        }
+
+        // the dotfiles are only locked while they're being changed so that other commands can
+        // run while watching
+        let _lock = match dotfiles::wait_for_dotfiles_lock(&mut output) {
+            Ok(lock) => lock,
+            Err(e) => return (output, e),
+        };
+        let sym = match SymlinkHandler::try_new(&mut output) {
+            Ok(sym) => sym,
+            Err(e) => return (output, e),
+        };
+
+        for group in pending {
+            // files that were removed or renamed leave their symlinks dangling
+            let pruned = sym.prune(&group, &mut output);
+            if pruned > 0 {
+                output.push_str(&format!("{group}: removed {pruned} dangling symlink(s)\n"));
+            }
+
+            if !sym.not_symlinked.contains_key(&group) {
+                if pruned == 0 {
+                    output.push_str(&format!("{group}: up to date\n"));
+                }
+                continue;
+            }
+
+            // the reasons files couldn't be symlinked are already in the output
+            match sym.add(&group, LinkOnly::Everything, &[], &mut output) {
+                Ok(0) => (),
+                Ok(linked) => {
+                    output.push_str(&format!("{group}: symlinked {linked} new file(s)\n"))
+                }
+                Err(e) => output.push_str(&format!("{group}: {e}\n")),
+            }
+        }
+
+        print_batch(&mut output);
+    }
+
+    (output, ExitCode::SUCCESS)
+}
+
+/// Symlinking status of a single dotfile
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileStatus {
+    Symlinked,
+    NotSymlinked,
+    Conflicting,
+}
+
+impl std::fmt::Display for FileStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileStatus::Symlinked => write!(f, "Symlinked"),
+            FileStatus::NotSymlinked => write!(f, "Not Symlinked"),
+            FileStatus::Conflicting => write!(f, "Conflicting"),
+        }
+    }
+}
+
+/// Returns the symlinking status of every file in a group
+///
+/// Files inside of a symlinked directory are omitted since they're deployed along with it
+fn get_group_file_statuses(group: &Dotfile, output: &mut String) -> Vec<(PathBuf, FileStatus)> {
+    let mut statuses = Vec::new();
+    let mut symlinked_dirs: Vec<PathBuf> = Vec::new();
+
+    let mapped = group.map(|f| {
+        if f.path == f.group_path || symlinked_dirs.iter().any(|dir| f.path.starts_with(dir)) {
+            return;
+        }
+
+        let Ok(target) = f.display_target_path(output) else {
+            return;
+        };
+        let status = if target.is_symlink() {
+            match fs::read_link(&target) {
+                Ok(link) if dotfiles::same_path(&link, &f.path) => {
+                    if f.path.is_dir() {
+                        symlinked_dirs.push(f.path.clone());
+                    }
+                    FileStatus::Symlinked
+                }
+                _ => FileStatus::Conflicting,
+            }
+        } else if target.is_dir() && f.path.is_dir() {
+            // the directory already exists so only the files inside of it are linked
+            return;
+        } else if target.exists() {
+            FileStatus::Conflicting
+        } else {
+            FileStatus::NotSymlinked
+        };
+
+        let relative_path = f.path.strip_prefix(&f.group_path).unwrap().to_path_buf();
+        statuses.push((relative_path, status));
+    });
+    if let Err(err) = mapped {
+        report_error(err, output);
+    }
+
+    statuses.sort_by(|(a, _), (b, _)| a.cmp(b));
+    statuses
+}
+
+/// Prints the symlinking status of every file in the supplied groups
+pub fn status_files_cmd(groups: &[String]) -> (String, ExitCode) {
+    let mut output = String::new();
+    let dotfiles_dir = match dotfiles::get_dotfiles_path(&mut output) {
+        Ok(dir) => dir,
+        Err(e) => return (output, e.into()),
+    };
+
+    if let Some(invalid_groups) =
+        dotfiles::check_invalid_groups(DotfileType::Configs, groups, &mut output)
+    {
+        for group in invalid_groups {
+            output.push_str(&format!("{group} doesn't exist.\n"));
+        }
+        return (output, ReturnCode::NoSetupFolder.into());
+    }
+
+    #[derive(Tabled)]
+    struct FileRow {
+        #[tabled(rename = "File")]
+        file: String,
+        #[tabled(rename = "Status")]
+        status: FileStatus,
+    }
+
+    let mut all_symlinked = true;
+    for group in groups {
+        let group =
+            Dotfile::try_from(DotfileType::Configs.dir_in(&dotfiles_dir).join(group)).unwrap();
+        let statuses = get_group_file_statuses(&group, &mut output);
+        all_symlinked &= statuses
+            .iter()
+            .all(|(_, status)| *status == FileStatus::Symlinked);
+
+        let rows = statuses.into_iter().map(|(file, status)| FileRow {
+            file: file.display().to_string(),
+            status,
+        });
+
+        use tabled::{Margin, Style};
+
+        let mut table = Table::new(rows);
+        table.with(Style::rounded()).with(Margin::new(4, 4, 1, 1));
+        output.push_str(&format!("{}:\n{table}\n", group.group_name));
+    }
+
+    if all_symlinked {
+        (output, ExitCode::SUCCESS)
+    } else {
+        (output, ExitCode::FAILURE)
+    }
+}
+
+/// Prints symlinking status
+/// Explains why a group is not symlinked by pointing at the first file that isn't
+pub fn status_explain_cmd(group: &str) -> (String, ExitCode) {
+    let mut output = "".to_string();
+    let sym = match SymlinkHandler::try_new(&mut output) {
+        Ok(sym) => sym,
+        Err(e) => return (output, e),
+    };
+
+    let group_path = DotfileType::Configs.dir_in(&sym.dotfiles_dir).join(group);
+    if !group_path.is_dir() {
+        output.push_str(&format!("{group} does not exist.\n"));
+        return (output, ReturnCode::NoSuchFileOrDir.into());
+    }
+
+    let Some(offender) = sym.first_offenders.get(&group_path) else {
+        output.push_str(&format!("{group} is symlinked.\n"));
+        return (output, ExitCode::SUCCESS);
+    };
+
+    let offender = Dotfile::try_from(offender.clone()).unwrap();
+    let target = match offender.display_target_path(&mut output) {
+        Ok(target) => target,
+        Err(e) => return (output, e.into()),
+    };
+    let reason = if let Ok(link) = fs::read_link(&target) {
+        format!("symlinks to {} instead", link.display())
+    } else if target.exists() {
+        "already exists".to_string()
+    } else {
+        "hasn't been symlinked yet".to_string()
+    };
+
+    output.push_str(&format!(
+        "{group} is not symlinked because {} {reason}.\n",
+        target.display()
+    ));
+    (output, ExitCode::FAILURE)
+}
+
+/// Returns true if `group_name` is one of the groups, or all of them when none are supplied
+///
+/// Conditional groups are shown along with their base group
+fn is_listed_group(groups: Option<&[String]>, group_name: &str) -> bool {
+    groups.is_none_or(|groups| {
+        groups
+            .iter()
+            .any(|group| dotfiles::is_related_group(group_name, group))
+    })
+}
+
+/// Warns about the targets that more than one of the groups has a file for, only one of them can
+/// be deployed there
+fn print_duplicate_targets(sym: &SymlinkHandler, groups: Option<&[String]>, output: &mut String) {
+    for (target, owners) in &sym.duplicate_targets {
+        if !owners.iter().any(|owner| is_listed_group(groups, owner)) {
+            continue;
+        }
+
+        output.push_str(&format!(
+            "{} is a target of more than one group: {}\n",
+            target.display(),
+            owners.join(", ")
+        ));
+    }
+}
+
+/// Lists every symlink of the groups along with the dotfile it resolves to
+fn print_link_targets(sym: &SymlinkHandler, groups: Option<&[String]>, output: &mut String) {
+    #[derive(Tabled)]
+    struct LinkRow {
+        #[tabled(rename = "Group")]
+        group: String,
+        #[tabled(rename = "Symlink")]
+        symlink: String,
+        #[tabled(rename = "Points To")]
+        points_to: String,
+    }
+
+    let mut rows: Vec<_> = sym
+        .symlinked
+        .values()
+        .flatten()
+        .filter(|file| is_listed_group(groups, &file.group_name))
+        .filter_map(|file| {
+            let symlink = sym.link_targets.get(&file.path)?;
+            Some(LinkRow {
+                group: file.group_name.clone(),
+                symlink: symlink.display().to_string(),
+                points_to: file.path.display().to_string(),
+            })
+        })
+        .collect();
+
+    if rows.is_empty() {
+        return;
+    }
+
+    rows.sort_by(|a, b| (&a.group, &a.symlink).cmp(&(&b.group, &b.symlink)));
+
+    let mut table = Table::new(rows);
+    table.with(tabled::Style::rounded());
+    output.push_str(&format!("\n{table}\n"));
+}
+
+/// Shows how every conflicting file differs from the dotfile that would replace it
+fn print_conflict_diffs(sym: &SymlinkHandler, groups: Option<&[String]>, output: &mut String) {
+    let mut conflicts: Vec<_> = sym
+        .not_symlinked
+        .values()
+        .chain(sym.not_owned.values())
+        .flatten()
+        .filter(|file| is_listed_group(groups, &file.group_name))
+        .filter(|file| {
+            matches!(
+                sym.conflict_kinds.get(&file.path),
+                Some(ConflictKind::File | ConflictKind::Symlink(_))
+            )
+        })
+        .filter(|file| file.path.is_file())
+        .filter_map(|file| Some((file.to_target_path(output).ok()?, &file.path)))
+        .collect();
+    conflicts.sort();
+
+    for (target, dotfile) in conflicts {
+        // directories and broken symlinks can't be compared
+        let (Ok(existing), Ok(replacement)) = (fs::read(&target), fs::read(dotfile)) else {
+            continue;
+        };
+
+        output.push_str(&format!(
+            "\n{}\n",
+            target
+                .display()
+                .if_supports_color(Stream::Stdout, |text| text.bold())
+        ));
+
+        if existing == replacement {
+            output.push_str("no differences\n");
+            continue;
+        }
+
+        // same heuristic as git, text files don't have NUL bytes
+        let is_binary = existing.contains(&0) || replacement.contains(&0);
+        let (false, Ok(existing), Ok(replacement)) = (
+            is_binary,
+            std::str::from_utf8(&existing),
+            std::str::from_utf8(&replacement),
+        ) else {
+            output.push_str("differs (binary)\n");
+            continue;
+        };
+
+        let diff = similar::TextDiff::from_lines(existing, replacement);
+        let diff = diff
+            .unified_diff()
+            .header(
+                &target.display().to_string(),
+                &dotfile.display().to_string(),
+            )
+            .to_string();
+
+        for line in diff.lines() {
+            let line = if line.starts_with('+') && !line.starts_with("+++") {
+                line.if_supports_color(Stream::Stdout, |text| text.green())
+                    .to_string()
+            } else if line.starts_with('-') && !line.starts_with("---") {
+                line.if_supports_color(Stream::Stdout, |text| text.red())
+                    .to_string()
+            } else {
+                line.to_string()
             };
+            output.push_str(&line);
+            output.push('\n');
+        }
+    }
+}
 
-            for file in conflicts {
-                if file.group_name != group {
-                    continue;
+/// Prints everything tuckr knows about a single group
+pub fn info_cmd(group: &str) -> (String, ExitCode) {
+    let mut output = "".to_string();
+    let sym = match SymlinkHandler::try_new(&mut output) {
+        Ok(sym) => sym,
+        Err(e) => return (output, e),
+    };
+
+    let group_dir = |dtype: DotfileType| dtype.dir_in(&sym.dotfiles_dir).join(group);
+    let configs_dir = group_dir(DotfileType::Configs);
+    let hooks_dir = group_dir(DotfileType::Hooks);
+    let secrets_dir = group_dir(DotfileType::Secrets);
+    if !configs_dir.is_dir() && !hooks_dir.is_dir() && !secrets_dir.is_dir() {
+        output.push_str(&format!("{group} does not exist.\n"));
+        return (output, ReturnCode::NoSuchFileOrDir.into());
+    }
+
+    let count_files = |dir: &Path| {
+        let mut count = 0;
+        if dir.is_dir() {
+            fileops::dir_map(dir, |f| {
+                if !f.is_dir() {
+                    count += 1;
                 }
+            });
+        }
+        count
+    };
+
+    let deploy_state = deploy_state(&sym, group);
+    let hooks = hook_kinds(&hooks_dir);
+
+    let Ok(dotfile) = Dotfile::try_from(configs_dir.clone()) else {
+        output.push_str(&format!("{group} is not a valid group.\n"));
+        return (output, ReturnCode::NoSuchFileOrDir.into());
+    };
+
+    output.push_str(&format!("Group: {group}\n"));
+    output.push_str(&format!("Files: {}\n", count_files(&configs_dir)));
+    output.push_str(&format!("Status: {deploy_state}\n"));
+    output.push_str(&format!(
+        "Hooks: {}\n",
+        if hooks.is_empty() {
+            "none".to_string()
+        } else {
+            hooks.join(", ")
+        }
+    ));
+    output.push_str(&format!("Secrets: {}\n", count_files(&secrets_dir)));
+    let supported = if dotfile.is_valid_target() {
+        "yes"
+    } else {
+        "no"
+    };
+    output.push_str(&format!("Supported on this platform: {supported}\n"));
+
+    (output, ExitCode::SUCCESS)
+}
+
+/// Describes how much of a group is symlinked, eg: `Partially symlinked`
+fn deploy_state(sym: &SymlinkHandler, group: &str) -> &'static str {
+    let has_conflicts = sym.not_owned.contains_key(group)
+        || sym.not_symlinked.get(group).is_some_and(|files| {
+            files
+                .iter()
+                .any(|file| sym.conflict_kinds.contains_key(&file.path))
+        });
+    match (
+        sym.symlinked.contains_key(group),
+        sym.not_symlinked.contains_key(group),
+    ) {
+        _ if has_conflicts => "Conflicting",
+        (true, false) => "Symlinked",
+        (true, true) => "Partially symlinked",
+        (false, true) => "Not symlinked",
+        (false, false) => "Nothing to symlink",
+    }
+}
+
+/// Returns which kinds of hooks are in the group's hooks directory, in the order they run
+fn hook_kinds(hooks_dir: &Path) -> Vec<&'static str> {
+    const KINDS: [&str; 3] = ["pre", "install", "post"];
+
+    let mut hooks: Vec<_> = fs::read_dir(hooks_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|hook| {
+            let name = hook.ok()?.file_name().into_string().ok()?;
+            KINDS.into_iter().find(|prefix| name.starts_with(prefix))
+        })
+        .collect();
+    hooks.sort_by_key(|hook| KINDS.iter().position(|kind| kind == hook));
+    hooks.dedup();
+    hooks
+}
+
+/// Shows the configs, hooks and secrets of every group in a single table
+///
+/// all: also shows groups that target another platform
+pub fn status_full_cmd(groups: Option<Vec<String>>, all: bool) -> (String, ExitCode) {
+    let mut output = "".to_string();
+    let sym = match SymlinkHandler::try_new(&mut output) {
+        Ok(sym) => sym,
+        Err(e) => return (output, e),
+    };
 
-                let conflict = file.to_target_path(output);
-                output.push_str("\t\t-> ");
-                output.push_str(conflict.to_str().unwrap_or(""));
-                output.push_str(&msg);
+    #[derive(Tabled)]
+    struct FullRow {
+        #[tabled(rename = "Group")]
+        group: String,
+        #[tabled(rename = "Configs")]
+        configs: &'static str,
+        #[tabled(rename = "Hooks")]
+        hooks: String,
+        #[tabled(rename = "Secrets")]
+        secrets: String,
+    }
+
+    let dtypes = [
+        DotfileType::Configs,
+        DotfileType::Hooks,
+        DotfileType::Secrets,
+    ];
+    let mut all_groups: Vec<_> = dtypes
+        .into_iter()
+        .flat_map(|dtype| dotfiles::list_groups(dtype).unwrap_or_default())
+        .filter(|group| is_listed_group(groups.as_deref(), group))
+        .filter(|group| all || dotfiles::is_valid_target_name(group))
+        .collect();
+    all_groups.sort();
+    all_groups.dedup();
+
+    if all_groups.is_empty() {
+        output.push_str("To get started: add dotfiles using `tuckr push` or add them manually to dotfiles/Configs.\n");
+        return (output, ReturnCode::NoSetupFolder.into());
+    }
+
+    let group_dir = |dtype: DotfileType, group: &str| dtype.dir_in(&sym.dotfiles_dir).join(group);
+    let rows = all_groups.into_iter().map(|group| {
+        let configs = if group_dir(DotfileType::Configs, &group).is_dir() {
+            deploy_state(&sym, &group)
+        } else {
+            "-"
+        };
+
+        let hooks = hook_kinds(&group_dir(DotfileType::Hooks, &group));
+        let hooks = if hooks.is_empty() {
+            "-".to_string()
+        } else {
+            hooks.join(", ")
+        };
+
+        let (decrypted, total) = secrets::decrypted_count(&group_dir(DotfileType::Secrets, &group));
+        let secrets = if total == 0 {
+            "-".to_string()
+        } else {
+            format!("{decrypted}/{total} decrypted")
+        };
+
+        FullRow {
+            group,
+            configs,
+            hooks,
+            secrets,
+        }
+    });
+
+    use tabled::{Margin, Style};
+
+    let mut table = Table::new(rows);
+    table.with(Style::rounded()).with(Margin::new(4, 4, 1, 1));
+    output.push_str(&format!("{table}\n"));
+
+    (output, ExitCode::SUCCESS)
+}
+
+/// Exits successfully only if the groups, or every group if none are supplied, are fully
+/// symlinked without printing anything, eg: for `if tuckr status --check; then ...`
+///
+/// Only errors, like a group not existing, are printed
+///
+/// all: also checks groups that target another platform
+pub fn status_check_cmd(groups: Option<Vec<String>>, all: bool) -> (String, ExitCode) {
+    let mut output = String::new();
+    if let Some(groups) = &groups {
+        let invalid_groups =
+            dotfiles::check_invalid_groups(DotfileType::Configs, groups, &mut output);
+        let invalid_groups = invalid_groups.and_then(|groups| {
+            dotfiles::check_invalid_groups(DotfileType::Decrypted, &groups, &mut output)
+        });
+        if let Some(invalid_groups) = invalid_groups {
+            for group in invalid_groups {
+                output.push_str(&format!("{group} doesn't exist.\n"));
             }
+            return (output, ReturnCode::NoSetupFolder.into());
+        }
+    }
+
+    let sym = if all {
+        SymlinkHandler::try_new(&mut output)
+    } else {
+        SymlinkHandler::try_new_supported(&mut output)
+    };
+    let sym = match sym {
+        Ok(sym) => sym,
+        Err(e) => return (output, e),
+    };
+
+    let is_incomplete = sym
+        .not_symlinked
+        .keys()
+        .chain(sym.not_owned.keys())
+        .any(|group| is_listed_group(groups.as_deref(), group));
+
+    if sym.is_empty() || is_incomplete {
+        (String::new(), ExitCode::FAILURE)
+    } else {
+        (String::new(), ExitCode::SUCCESS)
+    }
+}
+
+/// Prints the symlinking status of the groups, or of every group if none are supplied
+///
+/// links: also lists every symlink along with the dotfile it points to
+/// all: also shows groups that target another platform
+/// diff: also shows how conflicting files differ from the dotfiles that would replace them
+pub fn status_cmd(
+    groups: Option<Vec<String>>,
+    links: bool,
+    all: bool,
+    diff: bool,
+) -> (String, ExitCode) {
+    let mut output = "".to_string();
+    let sym = if all {
+        SymlinkHandler::try_new(&mut output)
+    } else {
+        SymlinkHandler::try_new_supported(&mut output)
+    };
+    let sym = match sym {
+        Ok(sm) => sm,
+        Err(e) => return (output, e),
+    };
+
+    if sym.is_empty() {
+        output.push_str("To get started: add dotfiles using `tuckr push` or add them manually to dotfiles/Configs.\n");
+        return (output, ReturnCode::NoSetupFolder.into());
+    }
+
+    if let Ok(all_groups) = dotfiles::list_groups(DotfileType::Configs) {
+        dotfiles::warn_misspelled_targets(&all_groups, &mut output);
+    }
+
+    // groups holding subgroups stand for all of them
+    let groups: Option<Vec<String>> = groups.map(|groups| {
+        groups
+            .iter()
+            .flat_map(|group| {
+                dotfiles::expand_group(group, &[DotfileType::Configs, DotfileType::Decrypted])
+            })
+            .collect()
+    });
+
+    let result = match &groups {
+        Some(groups) => print_groups_status(&sym, groups.clone(), &mut output),
+        None => print_global_status(&sym, &mut output),
+    };
+
+    print_duplicate_targets(&sym, groups.as_deref(), &mut output);
+
+    if links {
+        print_link_targets(&sym, groups.as_deref(), &mut output);
+    }
+
+    if diff {
+        print_conflict_diffs(&sym, groups.as_deref(), &mut output);
+    }
+
+    match result {
+        Ok(()) => (output, ExitCode::SUCCESS),
+        Err(e) => (output, e),
+    }
+}
+
+/// File in the state directory recording which groups are deployed
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Which groups were deployed the last time tuckr deployed or removed dotfiles, used by
+/// `status --fast` to skip the groups that haven't changed since
+#[derive(Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+struct Manifest {
+    groups: BTreeMap<String, ManifestGroup>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+struct ManifestGroup {
+    symlinked: bool,
+    /// The last time any of the group's files changed when the manifest was written
+    modified: SystemTime,
+}
+
+/// Returns the last time the directory or anything inside of it changed
+fn last_modified(dir: &Path) -> Option<SystemTime> {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok()?.metadata().ok()?.modified().ok())
+        .max()
+}
+
+/// Whether every file of the group is symlinked
+fn is_fully_symlinked(sym: &SymlinkHandler, group: &str) -> bool {
+    sym.symlinked.contains_key(group)
+        && !sym.not_symlinked.contains_key(group)
+        && !sym.not_owned.contains_key(group)
+}
+
+/// Records which of the groups in dotfiles/Configs are deployed for `status --fast`
+///
+/// This is meant to be called after deploying or removing dotfiles, failing to write the manifest
+/// is only reported as `status --fast` falls back to a full scan without it
+fn write_manifest(output: &mut String) {
+    let Ok(sym) = SymlinkHandler::try_new_supported(&mut String::new()) else {
+        return;
+    };
+    let configs_dir = DotfileType::Configs.dir_in(&sym.dotfiles_dir);
+
+    let mut manifest = Manifest::default();
+    let groups = sym
+        .symlinked
+        .keys()
+        .chain(sym.not_symlinked.keys())
+        .chain(sym.not_owned.keys());
+    for group in groups {
+        // decrypted secrets aren't tracked since their groups can be missing from Configs
+        let Some(modified) = last_modified(&configs_dir.join(group)) else {
+            continue;
         };
 
-        let file_conflicts = get_conflicts_in_cache(&sym.not_symlinked, output);
+        manifest.groups.insert(
+            group.clone(),
+            ManifestGroup {
+                symlinked: is_fully_symlinked(&sym, group),
+                modified,
+            },
+        );
+    }
 
-        output.push_str("Not Symlinked:");
-        for group in &not_symlinked {
-            output.push_str("\t");
-            output.push_str(&group);
-            print_conflicts(&file_conflicts, group, "already exists", output);
-            print_conflicts(&sym.not_owned, group, "symlinks elsewhere", output);
+    let written = dotfiles::create_state_dir(&sym.dotfiles_dir).and_then(|state_dir| {
+        let manifest = serde_json::to_string_pretty(&manifest).map_err(io::Error::other)?;
+        fs::write(state_dir.join(MANIFEST_FILE), manifest)
+    });
+    if let Err(e) = written {
+        output.push_str(&format!("Couldn't update the status manifest: {e}\n"));
+    }
+}
+
+/// Reads the manifest written by `write_manifest`, None if it's missing or unreadable
+fn read_manifest(dotfiles_dir: &Path) -> Option<Manifest> {
+    let manifest_path = dotfiles_dir.join(dotfiles::STATE_DIR).join(MANIFEST_FILE);
+    let manifest = fs::read_to_string(manifest_path).ok()?;
+    serde_json::from_str(&manifest).ok()
+}
+
+/// Returns whether each group is fully symlinked, only scanning the groups that changed since the
+/// manifest was written
+///
+/// Groups without files are left out just like in `SymlinkHandler`
+fn fast_group_states(
+    dotfiles_dir: &Path,
+    manifest: &Manifest,
+    output: &mut String,
+) -> Result<BTreeMap<String, bool>, ExitCode> {
+    let configs_dir = DotfileType::Configs.dir_in(dotfiles_dir);
+
+    let mut states = BTreeMap::new();
+    let mut changed = Vec::new();
+    for group in dotfiles::list_groups(DotfileType::Configs)? {
+        if !dotfiles::is_valid_target_name(&group) {
+            continue;
+        }
+
+        let cached = manifest.groups.get(&group).filter(|cached| {
+            last_modified(&configs_dir.join(&group)).is_some_and(|time| time == cached.modified)
+        });
+        match cached {
+            Some(cached) => {
+                states.insert(group, cached.symlinked);
+            }
+            None => changed.push(dotfiles::base_group_name(&group).to_string()),
         }
     }
 
-    if !symlinked.is_empty() {
-        output.push_str("Symlinked:");
-        for group in symlinked {
-            output.push_str("\t");
-            output.push_str(&group);
+    if !changed.is_empty() {
+        let sym = SymlinkHandler::try_new_for(&changed, output)?;
+        let groups = sym
+            .symlinked
+            .keys()
+            .chain(sym.not_symlinked.keys())
+            .chain(sym.not_owned.keys());
+        for group in groups {
+            states.insert(group.clone(), is_fully_symlinked(&sym, group));
         }
     }
 
-    if !unsupported.is_empty() {
-        output.push_str("Not supported on this platform:");
-        for group in unsupported {
-            output.push_str("\t");
-            output.push_str(&group);
-        };
+    Ok(states)
+}
+
+/// Prints which groups are symlinked like `status`, but only checks the groups whose files changed
+/// since dotfiles were last deployed or removed, the others are taken from the manifest
+///
+/// Changes made outside of the dotfiles, eg: deleting a symlink, go unnoticed for unchanged groups.
+/// Falls back to a full `status` if there's no manifest yet
+pub fn status_fast_cmd() -> (String, ExitCode) {
+    let mut output = String::new();
+    let dotfiles_dir = match dotfiles::get_dotfiles_path(&mut output) {
+        Ok(dir) => dir,
+        Err(e) => return (output, e.into()),
+    };
+
+    let Some(manifest) = read_manifest(&dotfiles_dir) else {
+        return status_cmd(None, false, false, false);
+    };
+
+    let states = match fast_group_states(&dotfiles_dir, &manifest, &mut output) {
+        Ok(states) => states,
+        Err(e) => return (output, e),
+    };
+
+    // conditional groups are shown as part of their base group just like in `status`
+    let (symlinked, not_symlinked): (Vec<_>, Vec<_>) = states
+        .iter()
+        .filter(|(group, _)| !dotfiles::group_ends_with_target_name(group))
+        .partition(|(_, symlinked)| **symlinked);
+    let symlinked: Vec<_> = symlinked.into_iter().map(|(group, _)| group).collect();
+    let not_symlinked: Vec<_> = not_symlinked.into_iter().map(|(group, _)| group).collect();
+
+    use tabled::{Alignment, Style};
+
+    let mut table = tabled::col![symlink_table(&symlinked, &not_symlinked)];
+    table.with(Style::empty()).with(Alignment::center());
+    output.push_str(&format!("{table}\n"));
+
+    if !symlinked.is_empty() && not_symlinked.is_empty() {
+        (output, ExitCode::SUCCESS)
+    } else {
+        (output, ExitCode::FAILURE)
+    }
+}
+
+/// Prints every file that's in the way of a dotfile, one per line so that it can be piped
+///
+/// json: prints the conflicts along with their group and kind as a JSON array instead
+pub fn conflicts_cmd(json: bool) -> (String, ExitCode) {
+    let mut output = "".to_string();
+    let sym = match SymlinkHandler::try_new_supported(&mut output) {
+        Ok(sym) => sym,
+        Err(e) => return (output, e),
+    };
+
+    #[derive(serde::Serialize)]
+    struct Conflict {
+        path: PathBuf,
+        group: String,
+        kind: &'static str,
     }
 
-    let invalid_groups = dotfiles::check_invalid_groups(DotfileType::Configs, &groups, output);
-    if let Some(invalid_groups) = &invalid_groups {
-        output.push_str("Following groups do not exist:");
-        for group in invalid_groups {
-            output.push_str("\t");
-            output.push_str(&group);
-        }
-    }
+    let mut conflicts: Vec<_> = sym
+        .not_symlinked
+        .values()
+        .chain(sym.not_owned.values())
+        .flatten()
+        .filter_map(|file| {
+            let kind = sym.conflict_kinds.get(&file.path)?;
+            Some(Conflict {
+                path: file.display_target_path(&mut output).ok()?,
+                group: file.group_name.clone(),
+                kind: kind.label(),
+            })
+        })
+        .collect();
+    conflicts.sort_by(|a, b| a.path.cmp(&b.path));
 
-    if !not_symlinked.is_empty() {
-        output.push_str("Check `tuckr help add` to learn how to fix symlinks.");
+    if json {
+        output.push_str(&(serde_json::to_string_pretty(&conflicts).unwrap() + "\n"));
+        return (output, ExitCode::SUCCESS);
     }
 
-    if invalid_groups.is_none() {
-        return Err(ReturnCode::NoSetupFolder.into());
+    for conflict in conflicts {
+        output.push_str(&format!("{}\n", conflict.path.display()));
     }
 
-    Ok(())
+    (output, ExitCode::SUCCESS)
 }
 
-/// Prints symlinking status
-pub fn status_cmd(groups: Option<Vec<String>>) -> (String, ExitCode) {
-    let mut output = "stats\n".to_string();
-    let sym = match SymlinkHandler::try_new(&mut output) {
-        Ok(sm) => sm,
+/// Checks that every deployed group is still fully symlinked, eg: to detect drift when monitoring
+///
+/// Groups that haven't been symlinked are left out, the check fails if any file of a symlinked
+/// group has been replaced, points somewhere else or is missing
+pub fn verify_cmd(groups: Option<Vec<String>>) -> (String, ExitCode) {
+    let mut output = "".to_string();
+    let sym = match SymlinkHandler::try_new_supported(&mut output) {
+        Ok(sym) => sym,
         Err(e) => return (output, e),
     };
 
-    if sym.is_empty() {
-        output.push_str("To get started: add dotfiles using `tuckr push` or add them manually to dotfiles/Configs.\n");
-        return (output, ReturnCode::NoSetupFolder.into());
+    let mut deployed: Vec<_> = sym
+        .symlinked
+        .keys()
+        .filter(|group| is_listed_group(groups.as_deref(), group))
+        .collect();
+    deployed.sort();
+
+    if deployed.is_empty() {
+        output.push_str("No symlinked groups to verify.\n");
+        return (output, ExitCode::SUCCESS);
     }
 
-    match groups {
-        Some(groups) => match print_groups_status(&sym, groups, &mut output) {
-            Ok(()) => (),
-            Err(e) => return (output, e),
-        },
-        None => match print_global_status(&sym, &mut output) {
-            Ok(()) => (),
-            Err(e) => return (output, e),
-        },
+    let mut drifted = 0;
+    for group in &deployed {
+        let mut drift: Vec<_> = sym
+            .not_symlinked
+            .get(*group)
+            .into_iter()
+            .chain(sym.not_owned.get(*group))
+            .flatten()
+            .map(|file| {
+                let reason = match sym.conflict_kinds.get(&file.path) {
+                    Some(kind) => kind.to_string(),
+                    None => "is missing".into(),
+                };
+                // files without a target are still drift, they're shown by their dotfile instead
+                let target = file
+                    .to_target_path(&mut output)
+                    .unwrap_or_else(|_| file.path.clone());
+                (target, reason)
+            })
+            .collect();
+        drift.sort();
+
+        if drift.is_empty() {
+            output.push_str(&format!(
+                "{} {group}\n",
+                "PASS".if_supports_color(Stream::Stdout, |text| text.green())
+            ));
+            continue;
+        }
+
+        drifted += 1;
+        output.push_str(&format!(
+            "{} {group}\n",
+            "FAIL".if_supports_color(Stream::Stdout, |text| text.red())
+        ));
+        for (target, reason) in drift {
+            output.push_str(&format!("\t-> {} {reason}\n", target.display()));
+        }
     }
 
-    (output, ExitCode::SUCCESS)
+    output.push_str(&format!(
+        "{} group(s) verified, {drifted} drifted.\n",
+        deployed.len()
+    ));
+
+    if drifted == 0 {
+        (output, ExitCode::SUCCESS)
+    } else {
+        (output, ExitCode::FAILURE)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use std::{
         fs::{self, File},
-        io::Write, process::ExitCode,
+        io::Write,
+        path::PathBuf,
+        process::ExitCode,
     };
 
-    use owo_colors::OwoColorize;
-
     use crate::dotfiles;
 
-    use super::SymlinkHandler;
+    use super::{AddOptions, SymlinkHandler};
 
-    struct Test(String);
+    // the guard keeps other tests from touching the dotfiles dir until this one is dropped
+    struct Test(
+        String,
+        #[allow(dead_code)] std::sync::MutexGuard<'static, ()>,
+    );
 
     impl Test {
         fn start() -> Self {
+            let lock = dotfiles::test_lock();
             let mut output = "".to_string();
 
             crate::fileops::init_cmd();
@@ -782,7 +2861,7 @@ mod tests {
             let _ = file2
                 .write("Some random content on file".as_bytes())
                 .unwrap();
-            Self(output)
+            Self(output, lock)
         }
     }
 
@@ -790,7 +2869,7 @@ mod tests {
         fn drop(&mut self) {
             let mut output = "".to_string();
 
-            _ = super::remove_cmd(&["*".to_string()], &[]);
+            _ = super::remove_cmd(&["*".to_string()], &[], false);
             let Ok(dotfiles_dir) = dotfiles::get_dotfiles_path(&mut output) else {
                 output.push_str("Failed to clean up test.");
                 return;
@@ -811,7 +2890,7 @@ mod tests {
         );
 
         assert!(!sym.symlinked.contains_key("Group1"));
-        super::add_cmd(&["Group1".to_string()], &[], false, false);
+        super::add_cmd(&["Group1".to_string()], &[], AddOptions::default());
 
         let sym = SymlinkHandler::try_new(&mut test.0).unwrap();
         assert!(sym.symlinked.contains_key("Group1"));
@@ -820,7 +2899,7 @@ mod tests {
     fn test_removing_symlink() {
         let mut test = Test::start();
 
-        super::add_cmd(&["Group1".to_string()], &[], false, false);
+        super::add_cmd(&["Group1".to_string()], &[], AddOptions::default());
 
         let sym = SymlinkHandler::try_new(&mut test.0).unwrap();
         assert!(
@@ -829,7 +2908,7 @@ mod tests {
 
         assert!(!sym.not_symlinked.contains_key("Group1"));
 
-        super::remove_cmd(&["Group1".to_string()], &[]);
+        super::remove_cmd(&["Group1".to_string()], &[], false);
         let sym = SymlinkHandler::try_new(&mut test.0).unwrap();
         assert!(sym.not_symlinked.contains_key("Group1"));
     }
@@ -839,4 +2918,830 @@ mod tests {
         test_adding_symlink();
         test_removing_symlink();
     }
+
+    #[test]
+    fn unsupported_groups_hidden_from_status() {
+        let mut test = Test::start();
+        let group = if cfg!(target_family = "windows") {
+            "Group2_linux"
+        } else {
+            "Group2_windows"
+        };
+        let group_dir = dotfiles::get_dotfiles_path(&mut test.0)
+            .unwrap()
+            .join("Configs")
+            .join(group);
+        fs::create_dir_all(&group_dir).unwrap();
+        fs::write(group_dir.join(".unsupported_file"), "dotfile").unwrap();
+
+        // a conflict would otherwise list the group in the status
+        let target = dirs::home_dir().unwrap().join(".unsupported_file");
+        fs::write(&target, "conflict").unwrap();
+
+        let sym = SymlinkHandler::try_new_supported(&mut test.0).unwrap();
+        assert!(!sym.not_symlinked.contains_key(group));
+        let (output, _) = super::status_cmd(None, false, false, false);
+        assert!(!output.contains(group));
+
+        let sym = SymlinkHandler::try_new(&mut test.0).unwrap();
+        assert!(sym.not_symlinked.contains_key(group));
+        let (output, _) = super::status_cmd(None, false, true, false);
+        assert!(output.contains(group));
+
+        fs::remove_file(target).unwrap();
+    }
+
+    #[test]
+    fn symlink_to_extra_targets() {
+        let mut test = Test::start();
+        let group_dir = dotfiles::get_dotfiles_path(&mut test.0)
+            .unwrap()
+            .join("Configs")
+            .join("Group1");
+        fs::write(
+            group_dir.join(dotfiles::LINKS_FILE),
+            "group_file_0 = .tuckr_extra_link_0\ngroup_file_0 = .tuckr_extra_link_1\n",
+        )
+        .unwrap();
+
+        super::add_cmd(&["Group1".to_string()], &[], AddOptions::default());
+
+        let home_dir = dirs::home_dir().unwrap();
+        let targets = [
+            home_dir.join("group_file_0"),
+            home_dir.join(".tuckr_extra_link_0"),
+            home_dir.join(".tuckr_extra_link_1"),
+        ];
+        for target in &targets {
+            assert_eq!(
+                fs::read_link(target).unwrap(),
+                group_dir.join("group_file_0")
+            );
+        }
+
+        super::remove_cmd(&["Group1".to_string()], &[], false);
+        for target in &targets {
+            assert!(!target.is_symlink());
+        }
+    }
+
+    #[test]
+    fn specific_group_overrides_base_group() {
+        let mut test = Test::start();
+        let configs_dir = dotfiles::get_dotfiles_path(&mut test.0)
+            .unwrap()
+            .join("Configs");
+        let os_group_dir = configs_dir.join(format!("Group1_{}", std::env::consts::OS));
+        fs::create_dir_all(&os_group_dir).unwrap();
+        fs::write(os_group_dir.join("group_file_0"), "platform specific").unwrap();
+
+        super::add_cmd(&["Group1".to_string()], &[], AddOptions::default());
+
+        let home_dir = dirs::home_dir().unwrap();
+        assert_eq!(
+            fs::read_link(home_dir.join("group_file_0")).unwrap(),
+            os_group_dir.join("group_file_0")
+        );
+        assert_eq!(
+            fs::read_link(home_dir.join(".config").join("group_file")).unwrap(),
+            configs_dir.join("Group1/.config/group_file")
+        );
+
+        let sym = SymlinkHandler::try_new(&mut test.0).unwrap();
+        assert!(sym.symlinked.contains_key("Group1"));
+        assert!(!sym.not_symlinked.contains_key("Group1"));
+        assert!(!sym.not_owned.contains_key("Group1"));
+    }
+
+    #[test]
+    fn force_only_some_files() {
+        let mut test = Test::start();
+        let group_dir = dotfiles::get_dotfiles_path(&mut test.0)
+            .unwrap()
+            .join("Configs")
+            .join("Group1");
+        fs::write(group_dir.join("group_file_1"), "dotfile").unwrap();
+
+        let home_dir = dirs::home_dir().unwrap();
+        let (forced, kept) = (home_dir.join("group_file_0"), home_dir.join("group_file_1"));
+        fs::write(&forced, "conflict").unwrap();
+        fs::write(&kept, "conflict").unwrap();
+
+        super::add_cmd(
+            &["Group1".to_string()],
+            &[],
+            AddOptions {
+                on_conflict: super::ConflictPolicy::Overwrite,
+                force_files: std::slice::from_ref(&forced),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(
+            fs::read_link(&forced).unwrap(),
+            group_dir.join("group_file_0")
+        );
+        assert!(!kept.is_symlink());
+        assert_eq!(fs::read_to_string(&kept).unwrap(), "conflict");
+
+        fs::remove_file(kept).unwrap();
+    }
+
+    /// Adds Group1 while a regular file is in the way of group_file_0, returning the command's
+    /// result along with the conflicting file and its dotfile
+    fn add_with_conflict(
+        test: &mut Test,
+        on_conflict: super::ConflictPolicy,
+        backup_dir: Option<&std::path::Path>,
+    ) -> (String, ExitCode, PathBuf, PathBuf) {
+        let dotfile = dotfiles::get_dotfiles_path(&mut test.0)
+            .unwrap()
+            .join("Configs")
+            .join("Group1")
+            .join("group_file_0");
+        let conflict = dirs::home_dir().unwrap().join("group_file_0");
+        fs::write(&conflict, "conflict").unwrap();
+
+        let (output, exit_code) = super::add_cmd(
+            &["Group1".to_string()],
+            &[],
+            AddOptions {
+                on_conflict,
+                backup_dir,
+                ..Default::default()
+            },
+        );
+
+        (output, exit_code, conflict, dotfile)
+    }
+
+    #[test]
+    fn on_conflict_skip() {
+        let mut test = Test::start();
+        let (_, exit_code, conflict, _) =
+            add_with_conflict(&mut test, super::ConflictPolicy::Skip, None);
+
+        assert_eq!(exit_code, ExitCode::SUCCESS);
+        assert!(!conflict.is_symlink());
+        assert_eq!(fs::read_to_string(&conflict).unwrap(), "conflict");
+
+        fs::remove_file(conflict).unwrap();
+    }
+
+    #[test]
+    fn on_conflict_overwrite() {
+        let mut test = Test::start();
+        let (_, exit_code, conflict, dotfile) =
+            add_with_conflict(&mut test, super::ConflictPolicy::Overwrite, None);
+
+        assert_eq!(exit_code, ExitCode::SUCCESS);
+        assert_eq!(fs::read_link(&conflict).unwrap(), dotfile);
+    }
+
+    #[test]
+    fn on_conflict_backup() {
+        let mut test = Test::start();
+        let backup_dir =
+            std::env::temp_dir().join(format!("tuckr-policy-backup-{}", std::process::id()));
+        let (_, exit_code, conflict, dotfile) =
+            add_with_conflict(&mut test, super::ConflictPolicy::Backup, Some(&backup_dir));
+
+        assert_eq!(exit_code, ExitCode::SUCCESS);
+        assert_eq!(fs::read_link(&conflict).unwrap(), dotfile);
+        let backup = fs::read_dir(&backup_dir).unwrap().next().unwrap().unwrap();
+        assert!(backup
+            .file_name()
+            .to_string_lossy()
+            .starts_with("group_file_0."));
+        assert!(super::backup_time(&backup.path()).is_some());
+        assert_eq!(fs::read_to_string(backup.path()).unwrap(), "conflict");
+
+        fs::remove_dir_all(backup_dir).unwrap();
+    }
+
+    #[test]
+    fn on_conflict_fail() {
+        let mut test = Test::start();
+        let (output, exit_code, conflict, _) =
+            add_with_conflict(&mut test, super::ConflictPolicy::Fail, None);
+
+        assert_eq!(exit_code, dotfiles::ReturnCode::CouldntSymlinkFile.into());
+        assert!(output.contains("Didn't symlink Group1"));
+        assert_eq!(fs::read_to_string(&conflict).unwrap(), "conflict");
+
+        // none of the group's other files are symlinked either
+        let other_file = dirs::home_dir().unwrap().join(".config").join("group_file");
+        assert!(!other_file.is_symlink());
+
+        fs::remove_file(conflict).unwrap();
+    }
+
+    #[test]
+    fn on_conflict_fail_checks_every_group_first() {
+        let mut test = Test::start();
+        let clean_group = dotfiles::get_dotfiles_path(&mut test.0)
+            .unwrap()
+            .join("Configs")
+            .join("Clean");
+        fs::create_dir_all(&clean_group).unwrap();
+        fs::write(clean_group.join("clean_file"), "dotfile").unwrap();
+        let conflict = dirs::home_dir().unwrap().join("group_file_0");
+        fs::write(&conflict, "conflict").unwrap();
+
+        // the group without conflicts comes first
+        let (output, exit_code) = super::add_cmd(
+            &["Clean".to_string(), "Group1".to_string()],
+            &[],
+            AddOptions {
+                on_conflict: super::ConflictPolicy::Fail,
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(exit_code, dotfiles::ReturnCode::CouldntSymlinkFile.into());
+        assert!(output.contains("Didn't symlink Group1"));
+        assert!(!dirs::home_dir().unwrap().join("clean_file").exists());
+        assert_eq!(fs::read_to_string(&conflict).unwrap(), "conflict");
+
+        fs::remove_file(conflict).unwrap();
+    }
+
+    #[test]
+    fn decrypted_secrets_are_symlinked() {
+        let mut test = Test::start();
+        let decrypted_group = dotfiles::DotfileType::Decrypted
+            .dir_in(&dotfiles::get_dotfiles_path(&mut test.0).unwrap())
+            .join("Vault");
+        fs::create_dir_all(&decrypted_group).unwrap();
+        fs::write(decrypted_group.join("vault_file"), "secret").unwrap();
+
+        let (_, exit_code) = super::add_cmd(&["Vault".to_string()], &[], AddOptions::default());
+        assert_eq!(exit_code, ExitCode::SUCCESS);
+
+        let target = dirs::home_dir().unwrap().join("vault_file");
+        assert_eq!(
+            fs::read_link(&target).unwrap(),
+            decrypted_group.join("vault_file")
+        );
+        let sym = SymlinkHandler::try_new(&mut test.0).unwrap();
+        assert!(sym.symlinked.contains_key("Vault"));
+
+        super::remove_cmd(&["Vault".to_string()], &[], false);
+        assert!(!target.exists());
+    }
+
+    #[test]
+    fn clean_old_backups() {
+        let _test = Test::start();
+        let home_dir = dirs::home_dir().unwrap();
+        let day = std::time::Duration::from_secs(60 * 60 * 24);
+        let now = std::time::SystemTime::now();
+        let timestamp = |time: std::time::SystemTime| {
+            time.duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+        };
+
+        // backups next to group_file_0's target are named after when they were made
+        let old_backup = home_dir.join(format!(
+            "group_file_0.{}.tuckr-bak",
+            timestamp(now - day * 40)
+        ));
+        let new_backup = home_dir.join(format!("group_file_0.{}.tuckr-bak", timestamp(now)));
+        fs::write(&old_backup, "old").unwrap();
+        fs::write(&new_backup, "new").unwrap();
+
+        // the ones in a backup dir are named the same way
+        let backup_dir =
+            std::env::temp_dir().join(format!("tuckr-clean-backups-{}", std::process::id()));
+        let old_in_dir = backup_dir
+            .join(".config")
+            .join(format!("old_file.{}.tuckr-bak", timestamp(now - day * 40)));
+        fs::create_dir_all(old_in_dir.parent().unwrap()).unwrap();
+        fs::write(&old_in_dir, "old").unwrap();
+        let new_in_dir = backup_dir.join(format!("new_file.{}.tuckr-bak", timestamp(now)));
+        fs::write(&new_in_dir, "new").unwrap();
+        // files that aren't named like backups are never deleted, however old they are
+        let not_a_backup = backup_dir.join("not_a_backup");
+        File::create(&not_a_backup)
+            .unwrap()
+            .set_modified(now - day * 40)
+            .unwrap();
+
+        let (output, exit_code) =
+            super::clean_backups_cmd(day * 30, std::slice::from_ref(&backup_dir));
+        assert_eq!(exit_code, ExitCode::SUCCESS);
+        assert!(output.contains("Removed 2 backup(s)"));
+
+        assert!(!old_backup.exists());
+        assert!(new_backup.exists());
+        assert!(!backup_dir.join(".config").exists());
+        assert!(new_in_dir.exists());
+        assert!(not_a_backup.exists());
+
+        fs::remove_file(new_backup).unwrap();
+        fs::remove_dir_all(backup_dir).unwrap();
+    }
+
+    #[test]
+    fn verify_detects_drift() {
+        let _test = Test::start();
+        super::add_cmd(&["Group1".to_string()], &[], AddOptions::default());
+
+        let (output, exit_code) = super::verify_cmd(None);
+        assert_eq!(exit_code, std::process::ExitCode::SUCCESS);
+        assert!(output.contains("PASS Group1"));
+
+        let target = dirs::home_dir().unwrap().join("group_file_0");
+        fs::remove_file(&target).unwrap();
+        fs::write(&target, "replaced").unwrap();
+
+        let (output, exit_code) = super::verify_cmd(None);
+        assert_eq!(exit_code, std::process::ExitCode::FAILURE);
+        assert!(output.contains("FAIL Group1"));
+        assert!(output.contains(&target.display().to_string()));
+
+        fs::remove_file(target).unwrap();
+    }
+
+    #[test]
+    fn symlink_into_missing_directories() {
+        let mut test = Test::start();
+        let group_dir = dotfiles::get_dotfiles_path(&mut test.0)
+            .unwrap()
+            .join("Configs")
+            .join("Group1");
+        fs::write(
+            group_dir.join(dotfiles::PREFIX_FILE),
+            ".tuckr_missing/nested",
+        )
+        .unwrap();
+
+        let missing_dir = dirs::home_dir().unwrap().join(".tuckr_missing");
+        assert!(!missing_dir.exists());
+
+        super::add_cmd(&["Group1".to_string()], &[], AddOptions::default());
+
+        assert_eq!(
+            fs::read_link(missing_dir.join("nested/group_file_0")).unwrap(),
+            group_dir.join("group_file_0")
+        );
+
+        super::remove_cmd(&["Group1".to_string()], &[], false);
+        fs::remove_dir_all(missing_dir).unwrap();
+    }
+
+    #[test]
+    fn exclude_files_from_group() {
+        let mut test = Test::start();
+        let group_dir = dotfiles::get_dotfiles_path(&mut test.0)
+            .unwrap()
+            .join("Configs")
+            .join("Group1");
+        fs::write(group_dir.join("group_file_1"), "kept").unwrap();
+
+        super::add_cmd(
+            &["Group1".to_string()],
+            &[],
+            AddOptions {
+                exclude_files: &["group_file_0".to_string(), "group_file".to_string()],
+                ..Default::default()
+            },
+        );
+
+        let home_dir = dirs::home_dir().unwrap();
+        assert!(home_dir.join("group_file_1").is_symlink());
+        assert!(!home_dir.join("group_file_0").exists());
+        // the directory holding an excluded file isn't symlinked as a whole
+        assert!(!home_dir.join(".config").join("group_file").exists());
+    }
+
+    #[test]
+    fn backup_into_backup_dir() {
+        let _test = Test::start();
+        let home_dir = dirs::home_dir().unwrap();
+        let backup_dir = std::env::temp_dir()
+            .join(format!("tuckr-{}", std::process::id()))
+            .join("backups");
+        let target = home_dir.join(".tuckr_backup_test").join("config");
+        fs::create_dir_all(target.parent().unwrap()).unwrap();
+        fs::write(&target, "conflict").unwrap();
+
+        let backup = super::backup_file(&target, Some(&backup_dir)).unwrap();
+        assert_eq!(
+            backup.parent(),
+            Some(&*backup_dir.join(".tuckr_backup_test"))
+        );
+        assert!(super::backup_time(&backup).is_some());
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "conflict");
+        assert!(!target.exists());
+
+        // a second backup of the same path doesn't replace the first one
+        fs::write(&target, "conflict 2").unwrap();
+        let second_backup = super::backup_file(&target, Some(&backup_dir)).unwrap();
+        assert_ne!(second_backup, backup);
+        assert!(second_backup.starts_with(backup.parent().unwrap()));
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "conflict");
+
+        fs::remove_dir_all(home_dir.join(".tuckr_backup_test")).unwrap();
+        fs::remove_dir_all(backup_dir).unwrap();
+    }
+
+    #[test]
+    fn status_check() {
+        let _test = Test::start();
+        let group = Some(vec!["Group1".to_string()]);
+        assert_eq!(
+            super::status_check_cmd(group.clone(), false),
+            (String::new(), std::process::ExitCode::FAILURE)
+        );
+
+        super::add_cmd(&["Group1".to_string()], &[], AddOptions::default());
+        assert_eq!(
+            super::status_check_cmd(group, false),
+            (String::new(), std::process::ExitCode::SUCCESS)
+        );
+
+        // typos aren't taken as fully symlinked groups
+        let (output, code) = super::status_check_cmd(Some(vec!["Gruop1".to_string()]), false);
+        assert_eq!(code, dotfiles::ReturnCode::NoSetupFolder.into());
+        assert!(output.contains("Gruop1 doesn't exist"));
+    }
+
+    #[test]
+    fn renamed_symlink_round_trip() {
+        let mut test = Test::start();
+        let group_dir = dotfiles::get_dotfiles_path(&mut test.0)
+            .unwrap()
+            .join("Configs")
+            .join("Group1");
+        fs::write(
+            group_dir.join(dotfiles::RENAME_FILE),
+            "group_file_0 = .tuckr_renamed_file\n",
+        )
+        .unwrap();
+
+        let target = dirs::home_dir().unwrap().join(".tuckr_renamed_file");
+        super::add_cmd(&["Group1".to_string()], &[], AddOptions::default());
+        assert_eq!(
+            fs::read_link(&target).unwrap(),
+            group_dir.join("group_file_0")
+        );
+
+        let sym = SymlinkHandler::try_new(&mut test.0).unwrap();
+        assert!(sym.symlinked.contains_key("Group1"));
+        assert!(!sym.not_symlinked.contains_key("Group1"));
+        assert_eq!(
+            sym.link_targets.get(&group_dir.join("group_file_0")),
+            Some(&target)
+        );
+
+        super::remove_cmd(&["Group1".to_string()], &[], false);
+        assert!(fs::symlink_metadata(&target).is_err());
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn symlinked_through_symlinked_dir() {
+        let mut test = Test::start();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&mut test.0).unwrap();
+
+        // the links point into the dotfiles through another path, like a symlinked $HOME would
+        let alias = std::env::temp_dir().join(format!("tuckr-alias-{}", std::process::id()));
+        std::os::unix::fs::symlink(dotfiles_dir.parent().unwrap(), &alias).unwrap();
+        let aliased_group = alias
+            .join(dotfiles_dir.file_name().unwrap())
+            .join("Configs")
+            .join("Group1");
+
+        let home_dir = dirs::home_dir().unwrap();
+        let mut links = vec![(home_dir.join("group_file_0"), "group_file_0")];
+        // an existing ~/.config is merged with, so only the file inside of it is linked then
+        if home_dir.join(".config").is_dir() {
+            links.push((home_dir.join(".config/group_file"), ".config/group_file"));
+        } else {
+            links.push((home_dir.join(".config"), ".config"));
+        }
+        for (target, dotfile) in &links {
+            std::os::unix::fs::symlink(aliased_group.join(dotfile), target).unwrap();
+        }
+
+        let sym = SymlinkHandler::try_new(&mut test.0).unwrap();
+        let symlinked = sym.symlinked.contains_key("Group1");
+        let not_symlinked = sym.not_symlinked.contains_key("Group1");
+
+        super::remove_cmd(&["Group1".to_string()], &[], false);
+        let removed = links
+            .iter()
+            .all(|(target, _)| fs::symlink_metadata(target).is_err());
+        fs::remove_file(&alias).unwrap();
+
+        assert!(symlinked);
+        assert!(!not_symlinked);
+        assert!(removed);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn foreign_symlink_conflict() {
+        let mut test = Test::start();
+        let target = dirs::home_dir().unwrap().join("group_file_0");
+        let foreign_target = std::env::temp_dir();
+        std::os::unix::fs::symlink(&foreign_target, &target).unwrap();
+
+        let sym = SymlinkHandler::try_new(&mut test.0).unwrap();
+        let dotfile = sym
+            .dotfiles_dir
+            .join("Configs")
+            .join("Group1")
+            .join("group_file_0");
+        let kind = sym.conflict_kinds.get(&dotfile).cloned();
+        fs::remove_file(&target).unwrap();
+
+        assert_eq!(kind, Some(super::ConflictKind::Symlink(foreign_target)));
+        assert!(sym.not_owned["Group1"]
+            .iter()
+            .any(|file| file.path == dotfile));
+    }
+
+    #[test]
+    fn first_offender_is_recorded() {
+        let mut test = Test::start();
+        let sym = SymlinkHandler::try_new(&mut test.0).unwrap();
+        let group_dir = sym.dotfiles_dir.join("Configs").join("Group1");
+
+        let offender = sym.first_offenders.get(&group_dir).unwrap();
+        assert!(offender.starts_with(&group_dir));
+        assert!(sym.not_symlinked["Group1"]
+            .iter()
+            .all(|file| *offender <= file.path));
+
+        super::add_cmd(&["Group1".to_string()], &[], AddOptions::default());
+        let sym = SymlinkHandler::try_new(&mut test.0).unwrap();
+        assert!(!sym.first_offenders.contains_key(&group_dir));
+    }
+
+    #[test]
+    fn re_add_only_links_missing_files() {
+        let _test = Test::start();
+        let add = || super::add_cmd(&["Group1".to_string()], &[], AddOptions::default()).0;
+
+        assert!(add().contains("Symlinked 2 new file(s) from Group1, 0 were already symlinked."));
+        assert!(add().contains("Group1 is already symlinked."));
+
+        super::remove_cmd(&["Group1".to_string()], &[], false);
+    }
+
+    #[test]
+    fn wildcard_removes_partially_symlinked_groups() {
+        let mut test = Test::start();
+        let group_dir = dotfiles::get_dotfiles_path(&mut test.0)
+            .unwrap()
+            .join("Configs")
+            .join("Group1");
+
+        // only one of the group's files is symlinked
+        super::symlink_file(group_dir.join("group_file_0"), &mut test.0).unwrap();
+        let target = dirs::home_dir().unwrap().join("group_file_0");
+        let sym = SymlinkHandler::try_new(&mut test.0).unwrap();
+        assert!(sym.not_symlinked.contains_key("Group1"));
+
+        super::remove_cmd(&["*".to_string()], &[], false);
+        assert!(fs::symlink_metadata(&target).is_err());
+    }
+
+    #[test]
+    fn group_info() {
+        let _test = Test::start();
+
+        let (output, _) = super::info_cmd("Group1");
+        assert!(output.contains("Files: 2\n"));
+        assert!(output.contains("Status: Not symlinked\n"));
+        assert!(output.contains("Supported on this platform: yes\n"));
+
+        let (_, code) = super::info_cmd("NoSuchGroup");
+        assert_eq!(
+            code,
+            std::process::ExitCode::from(dotfiles::ReturnCode::NoSuchFileOrDir)
+        );
+    }
+
+    #[test]
+    fn full_status() {
+        let mut test = Test::start();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&mut test.0).unwrap();
+        let hooks_dir = dotfiles_dir.join("Hooks").join("Group1");
+        fs::create_dir_all(&hooks_dir).unwrap();
+        fs::write(hooks_dir.join("post.sh"), "").unwrap();
+        let secrets_dir = dotfiles_dir.join("Secrets").join("Group2");
+        fs::create_dir_all(&secrets_dir).unwrap();
+        fs::write(secrets_dir.join("secret"), "").unwrap();
+
+        let (output, code) = super::status_full_cmd(None, false);
+        assert_eq!(code, ExitCode::SUCCESS);
+
+        let row = |group: &str| {
+            output
+                .lines()
+                .find(|line| line.contains(&format!(" {group} ")))
+                .unwrap()
+                .to_string()
+        };
+        let group1 = row("Group1");
+        assert!(group1.contains("Not symlinked"));
+        assert!(group1.contains("post"));
+        let group2 = row("Group2");
+        assert!(group2.contains("0/1 decrypted"));
+    }
+
+    #[test]
+    fn group_file_statuses() {
+        let mut test = Test::start();
+        let group_dir = dotfiles::get_dotfiles_path(&mut test.0)
+            .unwrap()
+            .join("Configs")
+            .join("Group1");
+        let group = dotfiles::Dotfile::try_from(group_dir).unwrap();
+
+        let statuses = super::get_group_file_statuses(&group, &mut test.0);
+        assert!(statuses
+            .iter()
+            .any(|(file, _)| file == std::path::Path::new("group_file_0")));
+        assert!(statuses
+            .iter()
+            .all(|(_, status)| *status != super::FileStatus::Symlinked));
+
+        super::add_cmd(&["Group1".to_string()], &[], AddOptions::default());
+
+        let statuses = super::get_group_file_statuses(&group, &mut test.0);
+        assert!(statuses
+            .iter()
+            .all(|(_, status)| *status == super::FileStatus::Symlinked));
+    }
+
+    #[test]
+    fn fast_status_rescans_changed_groups() {
+        let mut test = Test::start();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&mut test.0).unwrap();
+
+        // without a manifest a full status is printed
+        assert!(super::read_manifest(&dotfiles_dir).is_none());
+        let (output, code) = super::status_fast_cmd();
+        assert_eq!(code, ExitCode::FAILURE);
+        assert!(output.contains("Group1"));
+
+        // it's written by the commands that change what's deployed
+        super::add_cmd(&["Group1".to_string()], &[], AddOptions::default());
+        let manifest = super::read_manifest(&dotfiles_dir).unwrap();
+        assert!(manifest.groups["Group1"].symlinked);
+        assert_eq!(super::status_fast_cmd().1, ExitCode::SUCCESS);
+
+        // commands that didn't change anything leave it alone
+        let manifest_path = dotfiles_dir
+            .join(dotfiles::STATE_DIR)
+            .join(super::MANIFEST_FILE);
+        fs::remove_file(&manifest_path).unwrap();
+        super::add_cmd(&["Group1".to_string()], &[], AddOptions::default());
+        assert!(!manifest_path.exists());
+        super::remove_cmd(&["Group1".to_string()], &[], false);
+        assert!(!super::read_manifest(&dotfiles_dir).unwrap().groups["Group1"].symlinked);
+        super::add_cmd(&["Group1".to_string()], &[], AddOptions::default());
+
+        // unchanged groups are taken from the manifest, even if it's wrong about them
+        let mut wrong_manifest = super::read_manifest(&dotfiles_dir).unwrap();
+        wrong_manifest.groups.get_mut("Group1").unwrap().symlinked = false;
+        let states = super::fast_group_states(&dotfiles_dir, &wrong_manifest, &mut test.0).unwrap();
+        assert!(!states["Group1"]);
+
+        // groups with new files are scanned again
+        let new_file = dotfiles_dir.join("Configs").join("Group1").join("new_file");
+        fs::write(new_file, "").unwrap();
+        let states = super::fast_group_states(&dotfiles_dir, &manifest, &mut test.0).unwrap();
+        assert!(!states["Group1"]);
+    }
+
+    #[test]
+    fn manifest_is_written_when_add_fails() {
+        let mut test = Test::start();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&mut test.0).unwrap();
+        let broken_group = dotfiles_dir.join("Configs").join("Broken");
+        fs::create_dir_all(&broken_group).unwrap();
+        // its target can't be known without the variable
+        fs::write(broken_group.join("${TUCKR_TEST_UNSET_VAR}"), "").unwrap();
+
+        let (_, code) = super::add_cmd(
+            &["Group1".to_string(), "Broken".to_string()],
+            &[],
+            AddOptions::default(),
+        );
+        assert_eq!(code, dotfiles::ReturnCode::CouldntSymlinkFile.into());
+        assert!(super::read_manifest(&dotfiles_dir).unwrap().groups["Group1"].symlinked);
+    }
+
+    #[test]
+    fn file_target_suffixes_in_shared_group() {
+        let mut test = Test::start();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&mut test.0).unwrap();
+        let group_dir = dotfiles_dir.join("Configs").join("Shared");
+        let os_suffix = format!("_{}", std::env::consts::OS);
+
+        let nested_dir = group_dir.join(".config").join("tuckr_shared_dir");
+        fs::create_dir_all(&nested_dir).unwrap();
+        fs::write(group_dir.join("shared_config"), "").unwrap();
+        fs::write(group_dir.join(format!("shared_config{os_suffix}")), "").unwrap();
+        fs::write(group_dir.join("shared_config_none"), "").unwrap();
+        fs::write(nested_dir.join(format!("file{os_suffix}")), "").unwrap();
+        fs::write(nested_dir.join("file_none"), "").unwrap();
+
+        super::add_cmd(&["Shared".to_string()], &[], AddOptions::default());
+
+        // only the file for this platform is deployed, without its suffix
+        let home_dir = dirs::home_dir().unwrap();
+        let target_dir = home_dir.join(".config").join("tuckr_shared_dir");
+        let linked = fs::read_link(home_dir.join("shared_config"));
+        let linked_dir = target_dir.is_symlink();
+        let nested_link = fs::read_link(target_dir.join("file"));
+        let sym = SymlinkHandler::try_new(&mut test.0).unwrap();
+        let is_symlinked = super::is_fully_symlinked(&sym, "Shared");
+
+        drop(test);
+        _ = fs::remove_dir_all(&target_dir);
+
+        assert_eq!(
+            linked.unwrap(),
+            group_dir.join(format!("shared_config{os_suffix}"))
+        );
+        assert!(!linked_dir);
+        assert_eq!(
+            nested_link.unwrap(),
+            nested_dir.join(format!("file{os_suffix}"))
+        );
+        assert!(is_symlinked);
+    }
+
+    #[test]
+    fn duplicate_targets_across_groups() {
+        let mut test = Test::start();
+        let configs_dir = dotfiles::get_dotfiles_path(&mut test.0)
+            .unwrap()
+            .join("Configs");
+
+        let group2_dir = configs_dir.join("Group2");
+        fs::create_dir_all(group2_dir.join(".config")).unwrap();
+        fs::write(group2_dir.join("group_file_0"), "").unwrap();
+        fs::write(group2_dir.join(".config").join("group2_file"), "").unwrap();
+
+        // replacing the files of the base group is what platform specific groups are for
+        let platform_dir = configs_dir.join(format!("Group1_{}", std::env::consts::FAMILY));
+        fs::create_dir_all(platform_dir.join(".config")).unwrap();
+        fs::write(platform_dir.join(".config").join("group_file"), "").unwrap();
+
+        let sym = SymlinkHandler::try_new(&mut test.0).unwrap();
+        let home_dir = dirs::home_dir().unwrap();
+        assert_eq!(
+            sym.duplicate_targets,
+            std::collections::BTreeMap::from([(
+                home_dir.join("group_file_0"),
+                vec!["Group1".to_string(), "Group2".to_string()]
+            )])
+        );
+
+        let (output, _) = super::status_cmd(Some(vec!["Group2".into()]), false, false, false);
+        assert!(output.contains(&format!(
+            "{} is a target of more than one group: Group1, Group2",
+            home_dir.join("group_file_0").display()
+        )));
+    }
+
+    #[test]
+    fn sync_relinks_renamed_files() {
+        let mut test = Test::start();
+        let group_dir = dotfiles::get_dotfiles_path(&mut test.0)
+            .unwrap()
+            .join("Configs")
+            .join("Group1");
+        let home_dir = dirs::home_dir().unwrap();
+
+        let (_, code) = super::add_cmd(&["Group1".into()], &[], AddOptions::default());
+        assert_eq!(code, ExitCode::SUCCESS);
+
+        fs::rename(
+            group_dir.join("group_file_0"),
+            group_dir.join("group_file_1"),
+        )
+        .unwrap();
+
+        let (output, code) = super::sync_cmd(&["Group1".into()]);
+        assert_eq!(code, ExitCode::SUCCESS);
+        assert!(output.contains("Removed 1 dangling symlink(s) from Group1."));
+        assert!(fs::symlink_metadata(home_dir.join("group_file_0")).is_err());
+        assert_eq!(
+            fs::read_link(home_dir.join("group_file_1")).unwrap(),
+            group_dir.join("group_file_1")
+        );
+
+        // syncing again has nothing left to do
+        let (output, _) = super::sync_cmd(&["Group1".into()]);
+        assert_eq!(output, "Group1 is already symlinked.\n");
+    }
 }
@@ -0,0 +1,118 @@
+//! Renders template dotfiles
+//!
+//! Files ending in `.tmpl` aren't symlinked, instead a copy with every `{{ name }}` replaced by
+//! its value from the `[template_values]` table of `tuckr.toml` is written to their target,
+//! which is the same path without the `.tmpl` extension:
+//!
+//! ```toml
+//! [template_values]
+//! email = "me@example.com"
+//! ```
+
+use crate::config::{Config, CONFIG_FILE};
+use crate::dotfiles::{self, Dotfile};
+use std::collections::BTreeMap;
+use std::fs;
+
+/// Extension of the files that are rendered instead of symlinked
+pub const TEMPLATE_EXTENSION: &str = "tmpl";
+
+/// Replaces every `{{ name }}` in content with the value of `name`
+///
+/// Fails on placeholders that don't have a value or that are never closed
+pub fn render(content: &str, values: &BTreeMap<String, String>) -> Result<String, String> {
+    let mut rendered = String::with_capacity(content.len());
+    let mut rest = content;
+
+    while let Some((before, after)) = rest.split_once("{{") {
+        let Some((name, after)) = after.split_once("}}") else {
+            return Err("a `{{` is never closed".into());
+        };
+
+        let name = name.trim();
+        let Some(value) = values.get(name) else {
+            return Err(format!("`{name}` has no value in [template_values]"));
+        };
+
+        rendered.push_str(before);
+        rendered.push_str(value);
+        rest = after;
+    }
+    rendered.push_str(rest);
+
+    Ok(rendered)
+}
+
+/// Renders a template dotfile with the values from the config file
+pub fn render_file(dotfile: &Dotfile, output: &mut String) -> Result<String, dotfiles::Error> {
+    let content = fs::read_to_string(&dotfile.path).map_err(|source| dotfiles::Error::Io {
+        operation: "read",
+        path: dotfile.path.clone(),
+        source,
+    })?;
+    let config = Config::current(output).map_err(|_| dotfiles::Error::Template {
+        path: dotfile.path.clone(),
+        reason: format!("{CONFIG_FILE} couldn't be loaded"),
+    })?;
+
+    render(&content, &config.template_values).map_err(|reason| dotfiles::Error::Template {
+        path: dotfile.path.clone(),
+        reason,
+    })
+}
+
+/// Whether the file at target is an up to date render of the template
+pub fn is_rendered(dotfile: &Dotfile, target: &std::path::Path, output: &mut String) -> bool {
+    if target.is_symlink() || !target.is_file() {
+        return false;
+    }
+
+    match (fs::read_to_string(target), render_file(dotfile, output)) {
+        (Ok(deployed), Ok(rendered)) => deployed == rendered,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Dotfile, CONFIG_FILE};
+    use crate::dotfiles;
+    use std::collections::BTreeMap;
+    use std::fs;
+
+    #[test]
+    fn render_template_values() {
+        let values = BTreeMap::from([
+            ("name".to_string(), "tuckr".to_string()),
+            ("email".to_string(), "tuckr@example.com".to_string()),
+        ]);
+
+        assert_eq!(
+            super::render("user = {{name}} <{{ email }}>\n", &values).unwrap(),
+            "user = tuckr <tuckr@example.com>\n"
+        );
+        assert_eq!(super::render("plain", &values).unwrap(), "plain");
+
+        assert!(super::render("{{ missing }}", &values).is_err());
+        assert!(super::render("{{ name", &values).is_err());
+    }
+
+    #[test]
+    fn invalid_config_fails_rendering() {
+        let _lock = dotfiles::test_lock();
+        crate::fileops::init_cmd();
+        let dotfiles_dir = dotfiles::get_dotfiles_path(&mut String::new()).unwrap();
+        let group_dir = dotfiles_dir.join("Configs").join("Templated");
+        fs::create_dir_all(&group_dir).unwrap();
+        fs::write(group_dir.join(".gitconfig.tmpl"), "plain").unwrap();
+        fs::write(dotfiles_dir.join(CONFIG_FILE), "template_values = 1").unwrap();
+
+        // the defaults aren't silently used instead
+        let dotfile = Dotfile::try_from(group_dir.join(".gitconfig.tmpl")).unwrap();
+        let mut output = String::new();
+        assert!(super::render_file(&dotfile, &mut output).is_err());
+        assert!(output.contains("Invalid tuckr.toml"));
+
+        fs::remove_dir_all(dotfiles_dir).unwrap();
+    }
+}